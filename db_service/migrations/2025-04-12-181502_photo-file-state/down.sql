@@ -0,0 +1,6 @@
+ALTER TABLE photos
+    DROP COLUMN file_size,
+    DROP COLUMN modified_time,
+    DROP COLUMN is_missing,
+    DROP COLUMN is_tagged,
+    DROP COLUMN is_face_tagging_done;
@@ -0,0 +1,13 @@
+ALTER TABLE photos
+    ADD COLUMN file_size BIGINT,
+    ADD COLUMN modified_time TIMESTAMP,
+    ADD COLUMN is_missing BOOLEAN NOT NULL DEFAULT false,
+    ADD COLUMN is_tagged BOOLEAN NOT NULL DEFAULT false,
+    ADD COLUMN is_face_tagging_done BOOLEAN NOT NULL DEFAULT false;
+
+-- Photos imported before this migration inherit the status of their directory
+UPDATE photos
+SET is_tagged = directories.is_tagged,
+    is_face_tagging_done = directories.is_face_tagging_done
+FROM directories
+WHERE photos.path = directories.id;
@@ -0,0 +1,4 @@
+DROP INDEX photos_path_name_pattern_idx;
+
+ALTER TABLE photos
+    ALTER COLUMN name TYPE varchar(255);
@@ -0,0 +1,6 @@
+-- Photo names are now stored relative to the directory root, e.g. `2024/holidays/IMG_0001.JPG`
+ALTER TABLE photos
+    ALTER COLUMN name TYPE varchar(1024);
+
+-- Used for the prefix lookups when browsing the sub-folders of a directory
+CREATE INDEX photos_path_name_pattern_idx ON photos (path, name varchar_pattern_ops);
@@ -0,0 +1,4 @@
+DROP INDEX photos_content_hash_idx;
+
+ALTER TABLE photos
+    DROP COLUMN content_hash;
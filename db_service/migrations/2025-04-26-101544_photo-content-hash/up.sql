@@ -0,0 +1,5 @@
+-- Hex encoded SHA-256 of the file, used to find byte-identical copies across directories
+ALTER TABLE photos
+    ADD COLUMN content_hash varchar(64);
+
+CREATE INDEX photos_content_hash_idx ON photos (content_hash);
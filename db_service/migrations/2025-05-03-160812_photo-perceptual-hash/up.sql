@@ -0,0 +1,3 @@
+-- 64 bit difference hash of the preview, similar photos are within a small Hamming distance
+ALTER TABLE photos
+    ADD COLUMN perceptual_hash BIGINT;
@@ -0,0 +1,4 @@
+DROP TRIGGER photos_notify_change ON photos;
+DROP TRIGGER directories_notify_change ON directories;
+
+DROP FUNCTION notify_library_change();
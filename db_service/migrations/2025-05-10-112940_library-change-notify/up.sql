@@ -0,0 +1,21 @@
+-- Wakes up the services listening on the library_changes channel, the payload is the table name.
+-- Statement level, so a batch insert sends a single notification.
+CREATE FUNCTION notify_library_change() RETURNS trigger AS
+$$
+BEGIN
+    PERFORM pg_notify('library_changes', TG_TABLE_NAME);
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER directories_notify_change
+    AFTER INSERT OR DELETE OR UPDATE OF is_imported, is_tagged, is_face_tagging_done
+    ON directories
+    FOR EACH STATEMENT
+EXECUTE FUNCTION notify_library_change();
+
+CREATE TRIGGER photos_notify_change
+    AFTER INSERT OR DELETE
+    ON photos
+    FOR EACH STATEMENT
+EXECUTE FUNCTION notify_library_change();
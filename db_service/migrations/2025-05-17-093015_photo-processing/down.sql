@@ -0,0 +1,17 @@
+ALTER TABLE photos
+    ADD COLUMN is_tagged BOOLEAN NOT NULL DEFAULT false,
+    ADD COLUMN is_face_tagging_done BOOLEAN NOT NULL DEFAULT false;
+
+UPDATE photos
+SET is_tagged = photo_processing.status = 'done'
+FROM photo_processing
+WHERE photos.id = photo_processing.photo_id
+  AND photo_processing.stage = 'tagging';
+
+UPDATE photos
+SET is_face_tagging_done = photo_processing.status = 'done'
+FROM photo_processing
+WHERE photos.id = photo_processing.photo_id
+  AND photo_processing.stage = 'face_embeddings';
+
+DROP TABLE photo_processing;
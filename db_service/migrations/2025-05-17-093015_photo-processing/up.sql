@@ -0,0 +1,32 @@
+-- State of every processing stage of a photo: preview, tagging and face embeddings
+CREATE TABLE photo_processing (
+    photo_id uuid NOT NULL REFERENCES photos (id) ON DELETE CASCADE,
+    stage varchar(32) NOT NULL,
+    status varchar(16) NOT NULL DEFAULT 'pending'
+        CHECK (status IN ('pending', 'running', 'done', 'failed')),
+    attempts integer NOT NULL DEFAULT 0,
+    error text,
+    model_version varchar(255),
+    updated_at timestamp NOT NULL DEFAULT now(),
+    PRIMARY KEY (photo_id, stage)
+);
+
+CREATE INDEX photo_processing_stage_status_idx ON photo_processing (stage, status);
+
+-- Photos imported before this migration keep the status they had
+INSERT INTO photo_processing (photo_id, stage, status)
+SELECT photos.id, 'preview', CASE WHEN directories.is_imported THEN 'done' ELSE 'pending' END
+FROM photos
+         JOIN directories ON photos.path = directories.id;
+
+INSERT INTO photo_processing (photo_id, stage, status)
+SELECT id, 'tagging', CASE WHEN is_tagged THEN 'done' ELSE 'pending' END
+FROM photos;
+
+INSERT INTO photo_processing (photo_id, stage, status)
+SELECT id, 'face_embeddings', CASE WHEN is_face_tagging_done THEN 'done' ELSE 'pending' END
+FROM photos;
+
+ALTER TABLE photos
+    DROP COLUMN is_tagged,
+    DROP COLUMN is_face_tagging_done;
@@ -0,0 +1,16 @@
+-- Background tasks of the app, kept here so they survive a restart
+CREATE TABLE tasks (
+    id uuid PRIMARY KEY,
+    kind varchar(64) NOT NULL,
+    directory_id uuid REFERENCES directories (id) ON DELETE CASCADE,
+    payload jsonb NOT NULL DEFAULT 'null',
+    priority integer NOT NULL DEFAULT 0,
+    status varchar(16) NOT NULL DEFAULT 'queued'
+        CHECK (status IN ('queued', 'running', 'failed')),
+    error text,
+    created_at timestamp NOT NULL DEFAULT now(),
+    updated_at timestamp NOT NULL DEFAULT now()
+);
+
+CREATE INDEX tasks_queued_idx ON tasks (priority DESC, created_at) WHERE status = 'queued';
+CREATE INDEX tasks_directory_id_idx ON tasks (directory_id);
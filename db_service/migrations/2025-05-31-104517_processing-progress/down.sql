@@ -0,0 +1,3 @@
+DROP TRIGGER processing_progress_notify ON processing_progress;
+DROP FUNCTION notify_processing_progress();
+DROP TABLE processing_progress;
@@ -0,0 +1,30 @@
+-- Progress of the stages run by the tagging service, one row per directory and stage, so the
+-- desktop app can show it without sharing memory with the service
+CREATE TABLE processing_progress (
+    directory_id uuid NOT NULL REFERENCES directories (id) ON DELETE CASCADE,
+    stage varchar(32) NOT NULL,
+    done integer NOT NULL DEFAULT 0,
+    total integer NOT NULL DEFAULT 0,
+    errors integer NOT NULL DEFAULT 0,
+    current_file text,
+    last_error text,
+    finished boolean NOT NULL DEFAULT false,
+    started_at timestamp NOT NULL DEFAULT now(),
+    updated_at timestamp NOT NULL DEFAULT now(),
+    PRIMARY KEY (directory_id, stage)
+);
+
+-- Row level, the payload is `<directory_id>:<stage>` so listeners only reload what changed
+CREATE FUNCTION notify_processing_progress() RETURNS trigger AS
+$$
+BEGIN
+    PERFORM pg_notify('processing_progress', NEW.directory_id || ':' || NEW.stage);
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER processing_progress_notify
+    AFTER INSERT OR UPDATE
+    ON processing_progress
+    FOR EACH ROW
+EXECUTE FUNCTION notify_processing_progress();
@@ -0,0 +1,5 @@
+DROP INDEX exif_metadata_coordinates_idx;
+
+ALTER TABLE exif_metadata
+    DROP COLUMN latitude,
+    DROP COLUMN longitude;
@@ -0,0 +1,51 @@
+-- Signed decimal degrees parsed from the ExifTool strings, e.g. 47 deg 29' 3.12" N, so photos
+-- can be filtered by area. The text columns are kept as read from the file
+ALTER TABLE exif_metadata
+    ADD COLUMN latitude double precision,
+    ADD COLUMN longitude double precision;
+
+CREATE INDEX exif_metadata_coordinates_idx ON exif_metadata (latitude, longitude)
+    WHERE latitude IS NOT NULL AND longitude IS NOT NULL;
+
+-- Same rules as `parse_gps_coordinate` in db_service, only needed for the backfill
+CREATE FUNCTION gps_coordinate_degrees(value text) RETURNS double precision AS
+$$
+DECLARE
+    parts   text[];
+    degrees double precision;
+BEGIN
+    parts := regexp_match(
+            value,
+            '^\s*(-?\d+(?:\.\d+)?)(?:\s*deg)?(?:\s+(\d+(?:\.\d+)?)'')?(?:\s+(\d+(?:\.\d+)?)")?\s*([NSEW])?\s*$'
+             );
+    IF parts IS NULL THEN
+        RETURN NULL;
+    END IF;
+
+    degrees := abs(parts[1]::double precision)
+                   + coalesce(parts[2]::double precision, 0) / 60
+                   + coalesce(parts[3]::double precision, 0) / 3600;
+
+    IF parts[1] LIKE '-%' OR parts[4] IN ('S', 'W') THEN
+        RETURN -degrees;
+    END IF;
+    RETURN degrees;
+END;
+$$ LANGUAGE plpgsql IMMUTABLE;
+
+UPDATE exif_metadata
+SET latitude  = gps_coordinate_degrees(gps_latitude),
+    longitude = gps_coordinate_degrees(gps_longitude)
+WHERE gps_latitude IS NOT NULL
+  AND gps_longitude IS NOT NULL;
+
+-- Half parsed pairs and out of range values come from broken files, they would only end up in
+-- the wrong place
+UPDATE exif_metadata
+SET latitude  = NULL,
+    longitude = NULL
+WHERE (latitude IS NULL) <> (longitude IS NULL)
+   OR abs(latitude) > 90
+   OR abs(longitude) > 180;
+
+DROP FUNCTION gps_coordinate_degrees(text);
@@ -0,0 +1,21 @@
+-- Cities of a GeoNames dump, `id` is the GeoNames id. Region and country are stored as names,
+-- they are only used for browsing
+CREATE TABLE places (
+    id integer PRIMARY KEY,
+    name text NOT NULL,
+    region text,
+    country_code varchar(2) NOT NULL,
+    country text NOT NULL,
+    latitude double precision NOT NULL,
+    longitude double precision NOT NULL,
+    population bigint NOT NULL DEFAULT 0
+);
+
+-- The nearest city of the photos that have coordinates
+CREATE TABLE photo_places (
+    photo_id uuid PRIMARY KEY REFERENCES photos (id) ON DELETE CASCADE,
+    place_id integer NOT NULL REFERENCES places (id) ON DELETE CASCADE,
+    distance_meters double precision NOT NULL
+);
+
+CREATE INDEX photo_places_place_id_idx ON photo_places (place_id);
@@ -0,0 +1,15 @@
+UPDATE exif_metadata
+SET date_time_original = date_time_original + utc_offset_minutes * interval '1 minute'
+WHERE utc_offset_minutes IS NOT NULL;
+
+-- Back to whole seconds, offsets are whole minutes so only the subseconds are dropped
+UPDATE exif_metadata
+SET date_time_original = date_trunc('second', date_time_original)
+WHERE date_time_original IS NOT NULL;
+
+ALTER TABLE directories
+    DROP COLUMN utc_offset_minutes;
+
+ALTER TABLE exif_metadata
+    DROP COLUMN utc_offset_minutes,
+    DROP COLUMN time_zone_source;
@@ -0,0 +1,18 @@
+-- `date_time_original` becomes the actual instant of the capture. Cameras record the local
+-- time of their clock, so the offset it was taken at is kept to show that local time again.
+-- Without any offset the local time is stored as if it was UTC, as it always was. Offsets of
+-- existing rows are only known once their photos are read again
+ALTER TABLE exif_metadata
+    ADD COLUMN utc_offset_minutes integer,
+    ADD COLUMN time_zone_source varchar(16);
+
+-- For cameras that don't record their offset, applies to the photos of the directory
+ALTER TABLE directories
+    ADD COLUMN utc_offset_minutes integer;
+
+-- Subseconds were dropped so far, bursts shot within the same second had the same time
+UPDATE exif_metadata
+SET date_time_original = date_time_original
+    + ('0.' || substring(subsec_time_original FROM '^\s*(\d+)'))::double precision * interval '1 second'
+WHERE date_time_original IS NOT NULL
+  AND subsec_time_original ~ '^\s*\d+';
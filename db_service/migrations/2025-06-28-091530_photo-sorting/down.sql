@@ -0,0 +1,8 @@
+DROP INDEX exif_metadata_date_time_original_idx;
+DROP INDEX exif_metadata_photo_id_idx;
+DROP INDEX photos_file_size_idx;
+DROP INDEX photos_imported_at_idx;
+DROP INDEX photos_path_name_idx;
+
+ALTER TABLE photos
+    DROP COLUMN imported_at;
@@ -0,0 +1,11 @@
+-- When the photo was added to the catalog, the photos imported so far get the time of the
+-- migration
+ALTER TABLE photos
+    ADD COLUMN imported_at timestamp NOT NULL DEFAULT now();
+
+-- Pages of the grid are read in order of the sort key, then of the id to break ties
+CREATE INDEX photos_path_name_idx ON photos (path, name, id);
+CREATE INDEX photos_imported_at_idx ON photos (imported_at, id);
+CREATE INDEX photos_file_size_idx ON photos (file_size, id);
+CREATE INDEX exif_metadata_photo_id_idx ON exif_metadata (photo_id);
+CREATE INDEX exif_metadata_date_time_original_idx ON exif_metadata (date_time_original);
@@ -0,0 +1,6 @@
+DROP INDEX exif_metadata_focal_length_idx;
+DROP INDEX exif_metadata_iso_speed_idx;
+
+ALTER TABLE exif_metadata
+    DROP COLUMN lens_make,
+    DROP COLUMN lens_model;
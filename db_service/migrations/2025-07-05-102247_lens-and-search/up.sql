@@ -0,0 +1,8 @@
+-- The lens, searchable like the camera. Only known for the photos read from now on
+ALTER TABLE exif_metadata
+    ADD COLUMN lens_make text,
+    ADD COLUMN lens_model text;
+
+-- Range criteria of the photo search
+CREATE INDEX exif_metadata_iso_speed_idx ON exif_metadata (iso_speed);
+CREATE INDEX exif_metadata_focal_length_idx ON exif_metadata (focal_length);
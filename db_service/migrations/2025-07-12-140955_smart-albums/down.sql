@@ -0,0 +1,3 @@
+DROP INDEX face_embeddings_cluster_id_idx;
+
+DROP TABLE smart_albums;
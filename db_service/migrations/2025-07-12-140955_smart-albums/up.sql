@@ -0,0 +1,11 @@
+-- Saved searches, `query` is a serialized `PhotoQuery` evaluated each time the album is shown
+CREATE TABLE smart_albums (
+    id uuid PRIMARY KEY,
+    name varchar(255) NOT NULL,
+    query jsonb NOT NULL,
+    created_at timestamp NOT NULL DEFAULT now(),
+    updated_at timestamp NOT NULL DEFAULT now()
+);
+
+-- Face criteria of the searches
+CREATE INDEX face_embeddings_cluster_id_idx ON face_embeddings (cluster_id);
@@ -0,0 +1,3 @@
+DROP TABLE album_photos;
+
+DROP TABLE albums;
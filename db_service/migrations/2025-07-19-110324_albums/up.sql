@@ -0,0 +1,21 @@
+-- Curated albums, a photo can be in any number of them
+CREATE TABLE albums (
+    id uuid PRIMARY KEY,
+    name varchar(255) NOT NULL,
+    -- Falls back to the first photo of the album when unset
+    cover_photo_id uuid REFERENCES photos (id) ON DELETE SET NULL,
+    created_at timestamp NOT NULL DEFAULT now(),
+    updated_at timestamp NOT NULL DEFAULT now()
+);
+
+-- Positions are renumbered from 0 on every change to an album, deleted photos may leave gaps
+CREATE TABLE album_photos (
+    album_id uuid NOT NULL REFERENCES albums (id) ON DELETE CASCADE,
+    photo_id uuid NOT NULL REFERENCES photos (id) ON DELETE CASCADE,
+    position int4 NOT NULL,
+    added_at timestamp NOT NULL DEFAULT now(),
+    PRIMARY KEY (album_id, photo_id)
+);
+
+CREATE INDEX album_photos_position_idx ON album_photos (album_id, position);
+CREATE INDEX album_photos_photo_id_idx ON album_photos (photo_id);
@@ -0,0 +1,4 @@
+ALTER TABLE photos
+    DROP COLUMN color_label,
+    DROP COLUMN flag,
+    DROP COLUMN rating;
@@ -0,0 +1,10 @@
+-- What is decided about a photo while culling: 0 stars is unrated, an unset flag is neither a
+-- pick nor a reject
+ALTER TABLE photos
+    ADD COLUMN rating smallint NOT NULL DEFAULT 0 CHECK (rating BETWEEN 0 AND 5),
+    ADD COLUMN flag varchar(16),
+    ADD COLUMN color_label varchar(16);
+
+CREATE INDEX photos_rating_idx ON photos (rating, id);
+CREATE INDEX photos_flag_idx ON photos (flag) WHERE flag IS NOT NULL;
+CREATE INDEX photos_color_label_idx ON photos (color_label) WHERE color_label IS NOT NULL;
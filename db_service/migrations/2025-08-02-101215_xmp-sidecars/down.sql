@@ -0,0 +1,18 @@
+ALTER TABLE directories
+    DROP COLUMN xmp_sync;
+
+ALTER TABLE face_embeddings
+    DROP COLUMN region_height,
+    DROP COLUMN region_width,
+    DROP COLUMN region_y,
+    DROP COLUMN region_x;
+
+DROP INDEX photo_tags_mappings_lower_tag_idx;
+
+DELETE FROM photo_tags_mappings WHERE source <> 'detection';
+
+ALTER TABLE photo_tags_mappings
+    DROP CONSTRAINT photo_tags_mappings_tag_photo_id_source_key,
+    DROP COLUMN source,
+    ADD CONSTRAINT photo_tags_mappings_tag_photo_id_key UNIQUE (tag, photo_id),
+    ADD CONSTRAINT photo_tags_mappings_tag_fkey FOREIGN KEY (tag) REFERENCES tags (tag) ON DELETE CASCADE;
@@ -0,0 +1,21 @@
+-- Tags don't only come from the detection model anymore, keywords of sidecars and the ones added
+-- by hand can be anything
+ALTER TABLE photo_tags_mappings
+    DROP CONSTRAINT photo_tags_mappings_tag_fkey,
+    DROP CONSTRAINT photo_tags_mappings_tag_photo_id_key,
+    ADD COLUMN source varchar(16) NOT NULL DEFAULT 'detection',
+    ADD CONSTRAINT photo_tags_mappings_tag_photo_id_source_key UNIQUE (tag, photo_id, source);
+
+-- Keywords keep their case, tag queries are lowercased
+CREATE INDEX photo_tags_mappings_lower_tag_idx ON photo_tags_mappings (lower(tag));
+
+-- Where the face is in the photo, normalized from the top left corner. Unknown for the faces
+-- detected before
+ALTER TABLE face_embeddings
+    ADD COLUMN region_x real,
+    ADD COLUMN region_y real,
+    ADD COLUMN region_width real,
+    ADD COLUMN region_height real;
+
+ALTER TABLE directories
+    ADD COLUMN xmp_sync boolean NOT NULL DEFAULT false;
@@ -0,0 +1,4 @@
+DELETE FROM photo_tags_mappings WHERE source = 'embedded';
+
+ALTER TABLE photos
+    DROP COLUMN caption;
@@ -0,0 +1,3 @@
+-- IPTC Caption-Abstract or XMP dc:description, as written by the photographer or the agency
+ALTER TABLE photos
+    ADD COLUMN caption text;
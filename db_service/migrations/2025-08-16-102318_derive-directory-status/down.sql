@@ -0,0 +1,33 @@
+DROP TRIGGER photo_processing_notify_change ON photo_processing;
+DROP TRIGGER directories_notify_change ON directories;
+
+ALTER TABLE directories
+    ADD COLUMN is_imported bool NOT NULL DEFAULT false,
+    ADD COLUMN is_tagged BOOLEAN NOT NULL DEFAULT false,
+    ADD COLUMN is_face_tagging_done BOOLEAN NOT NULL DEFAULT false;
+
+UPDATE directories
+SET is_imported          = NOT EXISTS (SELECT 1
+                                       FROM photos
+                                                JOIN photo_processing ON photos.id = photo_processing.photo_id
+                                       WHERE photos.path = directories.id
+                                         AND photo_processing.stage = 'preview'
+                                         AND photo_processing.status <> 'done'),
+    is_tagged            = NOT EXISTS (SELECT 1
+                                       FROM photos
+                                                JOIN photo_processing ON photos.id = photo_processing.photo_id
+                                       WHERE photos.path = directories.id
+                                         AND photo_processing.stage = 'tagging'
+                                         AND photo_processing.status <> 'done'),
+    is_face_tagging_done = NOT EXISTS (SELECT 1
+                                       FROM photos
+                                                JOIN photo_processing ON photos.id = photo_processing.photo_id
+                                       WHERE photos.path = directories.id
+                                         AND photo_processing.stage = 'face_embeddings'
+                                         AND photo_processing.status <> 'done');
+
+CREATE TRIGGER directories_notify_change
+    AFTER INSERT OR DELETE OR UPDATE OF is_imported, is_tagged, is_face_tagging_done
+    ON directories
+    FOR EACH STATEMENT
+EXECUTE FUNCTION notify_library_change();
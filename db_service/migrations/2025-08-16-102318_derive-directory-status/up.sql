@@ -0,0 +1,20 @@
+-- The processing state of a directory is derived from the stages of its photos
+DROP TRIGGER directories_notify_change ON directories;
+
+ALTER TABLE directories
+    DROP COLUMN is_imported,
+    DROP COLUMN is_tagged,
+    DROP COLUMN is_face_tagging_done;
+
+CREATE TRIGGER directories_notify_change
+    AFTER INSERT OR DELETE
+    ON directories
+    FOR EACH STATEMENT
+EXECUTE FUNCTION notify_library_change();
+
+-- A finished preview is what makes a photo ready for tagging and face embeddings
+CREATE TRIGGER photo_processing_notify_change
+    AFTER INSERT OR UPDATE OF status
+    ON photo_processing
+    FOR EACH STATEMENT
+EXECUTE FUNCTION notify_library_change();
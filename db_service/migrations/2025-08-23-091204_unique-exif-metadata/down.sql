@@ -0,0 +1,4 @@
+ALTER TABLE exif_metadata
+    DROP CONSTRAINT exif_metadata_photo_id_key;
+
+CREATE INDEX exif_metadata_photo_id_idx ON exif_metadata (photo_id);
@@ -0,0 +1,13 @@
+-- A photo has a single metadata row, the latest read is kept when it was read more than once
+DELETE
+FROM exif_metadata
+WHERE id IN (SELECT id
+             FROM (SELECT id,
+                          row_number() OVER (PARTITION BY photo_id ORDER BY created_at DESC NULLS LAST, id) AS position
+                   FROM exif_metadata) AS reads
+             WHERE position > 1);
+
+DROP INDEX exif_metadata_photo_id_idx;
+
+ALTER TABLE exif_metadata
+    ADD CONSTRAINT exif_metadata_photo_id_key UNIQUE (photo_id);
@@ -1,17 +1,163 @@
-use diesel::pg::PgConnection;
+use anyhow::{Context, Result, anyhow, bail};
+use diesel::migration::MigrationSource;
+use diesel::pg::{Pg, PgConnection};
+use diesel::prelude::*;
 use diesel::r2d2::{ConnectionManager, Pool, PooledConnection};
+use diesel::sql_types::BigInt;
+use diesel_migrations::{EmbeddedMigrations, MigrationHarness, embed_migrations};
 use dotenvy::dotenv;
+use std::collections::HashSet;
 use std::env;
 pub type DbPool = Pool<ConnectionManager<PgConnection>>;
 pub type DbPoolConn = PooledConnection<ConnectionManager<PgConnection>>;
 
-pub fn init_pool() -> DbPool {
+/// The migrations from `db_service/migrations`, compiled into every binary using this crate.
+pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations");
+
+/// Key of the advisory lock held while migrating.
+const MIGRATION_LOCK_ID: i64 = 0x70686f746f;
+
+/// Postgres refuses statements with more bind parameters than this.
+const MAX_BIND_PARAMETERS: usize = 65535;
+/// Left for the parameters of the rest of an `INSERT`, e.g. its `ON CONFLICT DO UPDATE`.
+const STATEMENT_PARAMETERS: usize = 100;
+/// Larger batches don't insert faster, they only hold their locks longer.
+const MAX_INSERT_BATCH_SIZE: usize = 5000;
+
+/// How many rows of `columns` values fit in a single multi-row `INSERT`. Inserts that grow
+/// with the library are split in batches of this size.
+pub const fn insert_batch_size(columns: usize) -> usize {
+    let rows = (MAX_BIND_PARAMETERS - STATEMENT_PARAMETERS) / columns;
+    if rows < MAX_INSERT_BATCH_SIZE {
+        rows
+    } else {
+        MAX_INSERT_BATCH_SIZE
+    }
+}
+
+pub(crate) fn database_url() -> Result<String> {
     dotenv().ok();
 
-    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
-    let manager = ConnectionManager::<PgConnection>::new(database_url);
+    env::var("DATABASE_URL").context("DATABASE_URL must be set")
+}
+
+pub fn init_pool() -> Result<DbPool> {
+    let manager = ConnectionManager::<PgConnection>::new(database_url()?);
 
     Pool::builder()
         .build(manager)
-        .expect("Failed to create pool.")
+        .context("Failed to create the database pool")
+}
+
+/// Brings the database schema up to date with the embedded migrations.
+///
+/// Refuses to touch a database that has migrations this binary doesn't know about, as it was
+/// migrated by a newer version and the queries here may no longer match its schema.
+pub fn migrate(pool: &DbPool) -> Result<()> {
+    let conn = &mut pool
+        .get()
+        .context("Can't get a DB connection to run migrations")?;
+
+    // The app and the tagging service both migrate at startup, only one of them should do it
+    diesel::sql_query("SELECT pg_advisory_lock($1)")
+        .bind::<BigInt, _>(MIGRATION_LOCK_ID)
+        .execute(conn)?;
+
+    let result = run_migrations(conn);
+
+    diesel::sql_query("SELECT pg_advisory_unlock($1)")
+        .bind::<BigInt, _>(MIGRATION_LOCK_ID)
+        .execute(conn)?;
+
+    result
+}
+
+fn run_migrations(conn: &mut DbPoolConn) -> Result<()> {
+    let known: HashSet<String> = MigrationSource::<Pg>::migrations(&MIGRATIONS)
+        .map_err(|e| anyhow!("Failed to read the embedded migrations: {}", e))?
+        .iter()
+        .map(|migration| migration.name().version().to_string())
+        .collect();
+
+    let applied = conn
+        .applied_migrations()
+        .map_err(|e| anyhow!("Failed to read the applied migrations: {}", e))?;
+
+    let unknown: Vec<String> = applied
+        .iter()
+        .map(|version| version.to_string())
+        .filter(|version| !known.contains(version))
+        .collect();
+    if !unknown.is_empty() {
+        bail!(
+            "The database schema is newer than this build (unknown migrations: {}), please update the application",
+            unknown.join(", ")
+        );
+    }
+
+    let pending = conn
+        .pending_migrations(MIGRATIONS)
+        .map_err(|e| anyhow!("Failed to find the pending migrations: {}", e))?;
+    if pending.is_empty() {
+        tracing::debug!("Database schema is up to date");
+        return Ok(());
+    }
+
+    tracing::info!("Applying {} pending database migrations", pending.len());
+    for migration in pending {
+        let name = migration.name().to_string();
+        conn.run_migration(&migration)
+            .map_err(|e| anyhow!("Database migration failed: {}", e))?;
+        tracing::info!("Applied migration {}", name);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::schema::{Directory, NewDirectory};
+    use crate::services::directory::insert_directory;
+    use crate::services::photo::insert_photos_from_directory;
+    use std::path::Path;
+    use std::time::Duration;
+    use uuid::Uuid;
+
+    /// A connection to the database of `DATABASE_URL` whose changes are rolled back, `None` when
+    /// there is no database to test against or it isn't migrated.
+    pub(crate) fn test_connection() -> Option<DbPoolConn> {
+        let manager = ConnectionManager::<PgConnection>::new(database_url().ok()?);
+        let pool = Pool::builder()
+            .max_size(1)
+            .connection_timeout(Duration::from_secs(2))
+            .build(manager)
+            .ok()?;
+
+        let mut conn = pool.get().ok()?;
+        if conn.has_pending_migration(MIGRATIONS).ok()? {
+            return None;
+        }
+        conn.begin_test_transaction().ok()?;
+        Some(conn)
+    }
+
+    /// `tests/fixtures` added to the library, with its two photos imported.
+    pub(crate) fn fixture_directory(conn: &mut DbPoolConn) -> Directory {
+        let dir = insert_directory(
+            conn,
+            NewDirectory {
+                id: Uuid::new_v4(),
+                path: Path::new(env!("CARGO_MANIFEST_DIR"))
+                    .join("tests/fixtures")
+                    .to_string_lossy()
+                    .into_owned(),
+                photo_count: 0,
+            },
+        )
+        .unwrap();
+        insert_photos_from_directory(conn, &dir).unwrap();
+
+        dir
+    }
 }
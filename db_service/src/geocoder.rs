@@ -0,0 +1,85 @@
+use crate::schema::Place;
+use crate::services::geo::distance_meters;
+use std::collections::HashMap;
+
+/// Photos further than this from every known city are left without a place.
+pub const MAX_PLACE_DISTANCE_METERS: f64 = 100_000.0;
+
+/// Approximate length of a degree of latitude.
+const METERS_PER_DEGREE: f64 = 111_195.0;
+
+/// Finds the nearest city of a point without any online service. Places are bucketed in one
+/// degree cells, so a lookup only compares the point with the cities around it.
+pub struct PlaceIndex {
+    places: Vec<Place>,
+    cells: HashMap<(i32, i32), Vec<usize>>,
+}
+
+impl PlaceIndex {
+    pub fn new(places: Vec<Place>) -> Self {
+        let mut cells: HashMap<(i32, i32), Vec<usize>> = HashMap::new();
+        for (index, place) in places.iter().enumerate() {
+            cells
+                .entry(cell(place.latitude, place.longitude))
+                .or_default()
+                .push(index);
+        }
+
+        PlaceIndex { places, cells }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.places.is_empty()
+    }
+
+    /// The nearest place within `MAX_PLACE_DISTANCE_METERS` and its distance in meters.
+    pub fn nearest(&self, latitude: f64, longitude: f64) -> Option<(&Place, f64)> {
+        let latitude_delta = MAX_PLACE_DISTANCE_METERS / METERS_PER_DEGREE;
+        let longitude_delta = latitude_delta / latitude.to_radians().cos();
+
+        let rows =
+            (latitude - latitude_delta).floor() as i32..=(latitude + latitude_delta).floor() as i32;
+        // Close to the poles the circle covers every longitude
+        let columns = if longitude_delta.is_finite() && longitude_delta < 180.0 {
+            (longitude - longitude_delta).floor() as i32
+                ..=(longitude + longitude_delta).floor() as i32
+        } else {
+            -180..=179
+        };
+
+        let mut nearest: Option<(&Place, f64)> = None;
+        for row in rows {
+            for column in columns.clone() {
+                let Some(indexes) = self.cells.get(&(row, wrap_column(column))) else {
+                    continue;
+                };
+
+                for &index in indexes {
+                    let place = &self.places[index];
+                    let distance =
+                        distance_meters(latitude, longitude, place.latitude, place.longitude);
+
+                    if distance <= MAX_PLACE_DISTANCE_METERS
+                        && nearest.is_none_or(|(_, best)| distance < best)
+                    {
+                        nearest = Some((place, distance));
+                    }
+                }
+            }
+        }
+
+        nearest
+    }
+}
+
+fn cell(latitude: f64, longitude: f64) -> (i32, i32) {
+    (
+        latitude.floor() as i32,
+        wrap_column(longitude.floor() as i32),
+    )
+}
+
+/// Columns past the antimeridian are the ones on the other side of the map.
+fn wrap_column(column: i32) -> i32 {
+    (column + 180).rem_euclid(360) - 180
+}
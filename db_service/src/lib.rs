@@ -1,4 +1,10 @@
 pub mod db;
+pub mod geocoder;
+pub mod listener;
+pub mod metadata_reader;
 pub mod schema;
 pub mod seed;
 pub mod services;
+pub mod tag_query;
+pub mod track_log;
+pub mod xmp;
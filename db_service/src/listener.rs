@@ -0,0 +1,72 @@
+use crate::db::database_url;
+use anyhow::Result;
+use postgres::fallible_iterator::FallibleIterator;
+use postgres::{Client, NoTls};
+use std::time::{Duration, Instant};
+
+/// Channel the `notify_library_change` trigger publishes on.
+pub const LIBRARY_CHANGES_CHANNEL: &str = "library_changes";
+
+/// Channel the `notify_processing_progress` trigger publishes on.
+pub const PROGRESS_CHANNEL: &str = "processing_progress";
+
+/// How long to keep collecting notifications after the first one, so a burst of
+/// changes wakes the listener only once.
+const SETTLE_TIME: Duration = Duration::from_millis(500);
+
+/// A dedicated connection listening for changes to the directories and photos tables, or to
+/// any other notification channel.
+///
+/// Diesel connections can't receive asynchronous notifications, so this one goes through
+/// the `postgres` client instead of the pool.
+pub struct ChangeListener {
+    client: Client,
+}
+
+impl ChangeListener {
+    pub fn connect() -> Result<Self> {
+        Self::listen(LIBRARY_CHANGES_CHANNEL)
+    }
+
+    pub fn listen(channel: &str) -> Result<Self> {
+        let mut client = Client::connect(&database_url()?, NoTls)?;
+        client.batch_execute(&format!("LISTEN {}", channel))?;
+
+        Ok(Self { client })
+    }
+
+    /// Blocks until a change is notified or `timeout` elapses, returning the distinct payloads,
+    /// e.g. the names of the changed tables. An empty list means nothing happened before the
+    /// timeout.
+    pub fn wait(&mut self, timeout: Duration) -> Result<Vec<String>> {
+        let mut notifications = self.client.notifications();
+
+        let Some(first) = notifications.timeout_iter(timeout).next()? else {
+            return Ok(Vec::new());
+        };
+
+        let mut changed = vec![first.payload().to_string()];
+        let deadline = Instant::now() + SETTLE_TIME;
+
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                break;
+            }
+
+            match notifications.timeout_iter(remaining).next()? {
+                Some(notification) => {
+                    if !changed
+                        .iter()
+                        .any(|payload| payload == notification.payload())
+                    {
+                        changed.push(notification.payload().to_string());
+                    }
+                }
+                None => break,
+            }
+        }
+
+        Ok(changed)
+    }
+}
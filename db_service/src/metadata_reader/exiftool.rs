@@ -0,0 +1,521 @@
+use crate::metadata_reader::{
+    MetadataReader, PhotoMetadata, capture_time, format_exposure_time, format_gps_coordinate,
+    gps_coordinates, to_big_decimal,
+};
+use crate::schema::ExifMetadata;
+use crate::xmp::add_keyword;
+use anyhow::{Result, anyhow};
+use chrono::{NaiveDateTime, Utc};
+use exiftool::{ExifTool, ExifToolError};
+use serde_json::Value;
+use std::path::Path;
+use std::sync::{Condvar, Mutex};
+use uuid::Uuid;
+
+/// Reads metadata with the `exiftool` command line tool, which knows about far more formats
+/// and maker notes than the native reader but needs Perl to be installed.
+pub struct ExifToolReader {
+    pool: ExifToolPool,
+}
+
+impl ExifToolReader {
+    /// Sized for the rayon threads that read metadata.
+    pub fn new() -> Self {
+        Self {
+            pool: ExifToolPool::new(rayon::current_num_threads()),
+        }
+    }
+
+    /// Whether an `exiftool` executable can be started on this machine.
+    pub fn is_available() -> bool {
+        ExifTool::new().is_ok()
+    }
+}
+
+impl Default for ExifToolReader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MetadataReader for ExifToolReader {
+    fn name(&self) -> &'static str {
+        "exiftool"
+    }
+
+    fn read(&self, photo_id: Uuid, file_path: &Path) -> Result<PhotoMetadata> {
+        let data = self.pool.json(file_path)?;
+
+        Ok(PhotoMetadata {
+            exif: exif_from_json(photo_id, &data),
+            caption: caption_from_json(&data),
+            keywords: keywords_from_json(&data),
+        })
+    }
+}
+
+/// Writes tags back into photo files. The files are replaced in place, without the
+/// `_original` copy ExifTool keeps by default.
+pub struct ExifToolWriter {
+    exiftool: ExifTool,
+}
+
+impl ExifToolWriter {
+    pub fn new() -> Result<Self> {
+        let exiftool = ExifTool::new().map_err(|e| anyhow!(e).context("Cannot start ExifTool"))?;
+
+        Ok(Self { exiftool })
+    }
+
+    /// Sets the GPS position of a file, in decimal degrees and meters.
+    pub fn write_gps_position(
+        &mut self,
+        file_path: &Path,
+        latitude: f64,
+        longitude: f64,
+        altitude: Option<f64>,
+    ) -> Result<()> {
+        let mut args = vec![
+            format!("-GPSLatitude={}", latitude.abs()),
+            format!("-GPSLatitudeRef={}", if latitude < 0.0 { "S" } else { "N" }),
+            format!("-GPSLongitude={}", longitude.abs()),
+            format!(
+                "-GPSLongitudeRef={}",
+                if longitude < 0.0 { "W" } else { "E" }
+            ),
+        ];
+        if let Some(altitude) = altitude {
+            args.push(format!("-GPSAltitude={}", altitude.abs()));
+            // Raw value, 1 is below sea level
+            args.push(format!("-GPSAltitudeRef#={}", u8::from(altitude < 0.0)));
+        }
+        args.push("-overwrite_original".to_string());
+        args.push(file_path.to_string_lossy().to_string());
+
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        self.exiftool.execute_raw(&args)?;
+
+        Ok(())
+    }
+}
+
+/// Values as they are stored, not as they are printed for people: the orientation is `6` rather
+/// than `Rotate 90 CW` and the focal length `50` rather than `50.0 mm`.
+const JSON_ARGS: &[&str] = &["-n"];
+
+/// A bounded set of long-lived `exiftool -stay_open` processes.
+///
+/// Starting ExifTool means starting a Perl interpreter, which costs far more than reading the
+/// metadata of a photo, so the processes are kept around and handed out to whichever thread
+/// needs one. At most `size` processes exist, additional callers wait for one to be returned.
+pub struct ExifToolPool {
+    size: usize,
+    state: Mutex<PoolState>,
+    returned: Condvar,
+}
+
+struct PoolState {
+    idle: Vec<ExifTool>,
+    spawned: usize,
+}
+
+impl ExifToolPool {
+    pub fn new(size: usize) -> Self {
+        Self {
+            size: size.max(1),
+            state: Mutex::new(PoolState {
+                idle: Vec::new(),
+                spawned: 0,
+            }),
+            returned: Condvar::new(),
+        }
+    }
+
+    /// Reads the metadata of a single file as the JSON object ExifTool prints for it.
+    pub fn json(&self, file_path: &Path) -> Result<Value> {
+        let mut exiftool = self.acquire()?;
+
+        let result = exiftool.json(file_path, JSON_ARGS);
+        match &result {
+            // The process is gone or out of sync, don't hand it out again
+            Err(
+                ExifToolError::Io(_)
+                | ExifToolError::ProcessTerminated
+                | ExifToolError::StderrDisconnected,
+            ) => self.discard(exiftool),
+            _ => self.release(exiftool),
+        }
+
+        Ok(result?)
+    }
+
+    fn acquire(&self) -> Result<ExifTool> {
+        let mut state = self
+            .state
+            .lock()
+            .map_err(|_| anyhow!("ExifTool pool poisoned"))?;
+
+        loop {
+            if let Some(exiftool) = state.idle.pop() {
+                return Ok(exiftool);
+            }
+
+            if state.spawned < self.size {
+                state.spawned += 1;
+                drop(state);
+
+                return ExifTool::new().map_err(|e| {
+                    self.discard_slot();
+                    anyhow::Error::from(e).context("Cannot start ExifTool")
+                });
+            }
+
+            state = self
+                .returned
+                .wait(state)
+                .map_err(|_| anyhow!("ExifTool pool poisoned"))?;
+        }
+    }
+
+    fn release(&self, exiftool: ExifTool) {
+        if let Ok(mut state) = self.state.lock() {
+            state.idle.push(exiftool);
+        }
+        self.returned.notify_one();
+    }
+
+    fn discard(&self, exiftool: ExifTool) {
+        drop(exiftool);
+        self.discard_slot();
+    }
+
+    /// Frees the slot of a process that is gone, so a new one can be started.
+    fn discard_slot(&self) {
+        if let Ok(mut state) = self.state.lock() {
+            state.spawned -= 1;
+        }
+        self.returned.notify_one();
+    }
+}
+
+/// A tag printed as a single value or as a list, numeric looking values are JSON numbers.
+fn texts(data: &Value, key: &str) -> Vec<String> {
+    let text = |value: &Value| match value {
+        Value::String(s) => Some(s.to_string()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    };
+
+    match data.get(key) {
+        Some(Value::Array(values)) => values.iter().filter_map(text).collect(),
+        Some(value) => text(value).into_iter().collect(),
+        None => Vec::new(),
+    }
+}
+
+/// IPTC `Caption-Abstract`, or XMP `Description` when there is none.
+fn caption_from_json(data: &Value) -> Option<String> {
+    ["Caption-Abstract", "Description"]
+        .iter()
+        .flat_map(|key| texts(data, key))
+        .map(|caption| caption.trim().to_string())
+        .find(|caption| !caption.is_empty())
+}
+
+/// IPTC `Keywords`, XMP `Subject` and the leaves of `HierarchicalSubject`.
+fn keywords_from_json(data: &Value) -> Vec<String> {
+    let mut keywords = Vec::new();
+    for keyword in texts(data, "Keywords")
+        .into_iter()
+        .chain(texts(data, "Subject"))
+    {
+        add_keyword(&mut keywords, &keyword);
+    }
+    for subject in texts(data, "HierarchicalSubject") {
+        add_keyword(
+            &mut keywords,
+            subject.rsplit('|').next().unwrap_or(&subject),
+        );
+    }
+
+    keywords
+}
+
+/// A coordinate in ExifTool's notation, from its decimal degrees and hemisphere reference.
+/// Only the composite tags are signed, the reference is applied to the Exif ones.
+fn gps_coordinate(data: &Value, key: &str, references: (char, char)) -> Option<String> {
+    let degrees = data.get(key)?.as_f64()?;
+    let negative = data
+        .get(format!("{}Ref", key))
+        .and_then(Value::as_str)
+        .is_some_and(|reference| reference.starts_with(references.1));
+
+    let degrees = if negative { -degrees.abs() } else { degrees };
+    Some(format_gps_coordinate(degrees, references))
+}
+
+/// Maps the tags read by ExifTool for a photo to its `exif_metadata` row.
+fn exif_from_json(photo_id: Uuid, data: &Value) -> ExifMetadata {
+    macro_rules! get_str {
+        ($k:expr) => {
+            data.get($k).and_then(|v| v.as_str()).map(|s| s.to_string())
+        };
+    }
+    // Numeric looking strings, e.g. subseconds, are printed as JSON numbers
+    macro_rules! get_text {
+        ($k:expr) => {
+            data.get($k).and_then(|v| match v {
+                Value::String(s) => Some(s.to_string()),
+                Value::Number(n) => Some(n.to_string()),
+                _ => None,
+            })
+        };
+    }
+    macro_rules! get_i32 {
+        ($k:expr) => {
+            data.get($k).and_then(|v| v.as_i64()).map(|i| i as i32)
+        };
+    }
+    macro_rules! get_f64 {
+        ($k:expr) => {
+            data.get($k).and_then(|v| v.as_f64())
+        };
+    }
+    macro_rules! get_bd {
+        ($k:expr) => {
+            get_f64!($k).and_then(to_big_decimal)
+        };
+    }
+    macro_rules! get_dt {
+        ($k:expr) => {
+            data.get($k)
+                .and_then(|v| v.as_str())
+                .and_then(|s| NaiveDateTime::parse_from_str(s, "%Y:%m:%d %H:%M:%S").ok())
+        };
+    }
+
+    // The composite GPSDateTime, GPSTimeStamp alone has no date. It ends with a `Z`
+    let gps_timestamp = get_str!("GPSDateTime").and_then(|s| {
+        NaiveDateTime::parse_from_str(s.trim_end_matches('Z'), "%Y:%m:%d %H:%M:%S%.f").ok()
+    });
+    let subsec_time_original = get_text!("SubSecTimeOriginal");
+    let capture = get_dt!("DateTimeOriginal").map(|local| {
+        capture_time(
+            local,
+            subsec_time_original.as_deref(),
+            get_str!("OffsetTimeOriginal").as_deref(),
+            gps_timestamp,
+        )
+    });
+
+    // Stored in the notation the native reader uses
+    let gps_latitude = gps_coordinate(data, "GPSLatitude", ('N', 'S'));
+    let gps_longitude = gps_coordinate(data, "GPSLongitude", ('E', 'W'));
+    let (latitude, longitude) = gps_coordinates(gps_latitude.as_deref(), gps_longitude.as_deref());
+    // A reference of 1 means below sea level
+    let gps_altitude = get_f64!("GPSAltitude").map(|altitude| match get_i32!("GPSAltitudeRef") {
+        Some(1) => -altitude.abs(),
+        _ => altitude,
+    });
+
+    ExifMetadata {
+        id: Uuid::new_v4(),
+        photo_id,
+
+        make: get_str!("Make"),
+        model: get_str!("Model"),
+        orientation: get_i32!("Orientation"),
+        software: get_str!("Software"),
+
+        x_resolution: get_bd!("XResolution"),
+        y_resolution: get_bd!("YResolution"),
+        resolution_unit: get_i32!("ResolutionUnit"),
+        pixel_x_dimension: get_i32!("ImageWidth"),
+        pixel_y_dimension: get_i32!("ImageHeight"),
+
+        date_time: get_dt!("ModifyDate"),
+        date_time_original: capture.as_ref().map(|capture| capture.date_time),
+        date_time_digitized: get_dt!("CreateDate"),
+        subsec_time: get_text!("SubSecTime"),
+        subsec_time_original,
+        subsec_time_digitized: get_text!("SubSecTimeDigitized"),
+
+        exposure_time: get_f64!("ExposureTime").map(format_exposure_time),
+        f_number: get_bd!("FNumber"),
+        exposure_program: get_i32!("ExposureProgram"),
+        iso_speed: get_i32!("ISO"),
+        shutter_speed_value: get_bd!("ShutterSpeedValue"),
+        aperture_value: get_bd!("ApertureValue"),
+        brightness_value: get_bd!("BrightnessValue"),
+        exposure_bias: get_bd!("ExposureCompensation"),
+        max_aperture_value: get_bd!("MaxApertureValue"),
+        exposure_mode: get_i32!("ExposureMode"),
+
+        metering_mode: get_i32!("MeteringMode"),
+        light_source: get_i32!("LightSource"),
+        flash: get_i32!("Flash"),
+        focal_length: get_bd!("FocalLength"),
+        focal_length_in_35mm_film: get_i32!("FocalLengthIn35mmFormat"),
+
+        white_balance: get_i32!("WhiteBalance"),
+        scene_capture_type: get_i32!("SceneCaptureType"),
+        custom_rendered: get_i32!("CustomRendered"),
+        contrast: get_i32!("Contrast"),
+        saturation: get_i32!("Saturation"),
+        sharpness: get_i32!("Sharpness"),
+        gain_control: get_bd!("GainControl"),
+
+        exif_version: get_text!("ExifVersion"),
+        image_unique_id: get_str!("ImageUniqueID"),
+        components_configuration: get_str!("ComponentsConfiguration"),
+
+        // `2 3 0 0` with `-n`
+        gps_version_id: get_str!("GPSVersionID").map(|version| version.replace(' ', ".")),
+        gps_latitude,
+        gps_longitude,
+        gps_altitude: gps_altitude.and_then(to_big_decimal),
+        gps_timestamp,
+        gps_processing_method: get_str!("GPSProcessingMethod"),
+        gps_date_stamp: get_str!("GPSDateStamp"),
+
+        created_at: Some(Utc::now().naive_utc()),
+
+        latitude,
+        longitude,
+
+        utc_offset_minutes: capture
+            .as_ref()
+            .and_then(|capture| capture.utc_offset_minutes),
+        time_zone_source: capture.and_then(|capture| capture.source),
+
+        lens_make: get_str!("LensMake"),
+        // Older cameras only write the lens into their maker notes
+        lens_model: get_str!("LensModel").or_else(|| get_str!("Lens")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata_reader::NativeReader;
+    use crate::metadata_reader::tests::{comparable, fixture};
+    use serde_json::json;
+
+    /// What `exiftool -json -n` prints for `tests/fixtures/exif.jpg`.
+    fn fixture_json() -> Value {
+        json!({
+            "Make": "Canon",
+            "Model": "Canon EOS 5D Mark IV",
+            "Orientation": 6,
+            "XResolution": 72,
+            "YResolution": 72,
+            "ResolutionUnit": 2,
+            "Software": "Firmware 1.2.3",
+            "ModifyDate": "2025:06:22 09:00:00",
+            "ExposureTime": 0.008,
+            "FNumber": 2.8,
+            "ExposureProgram": 3,
+            "ISO": 400,
+            "ExifVersion": "0232",
+            "DateTimeOriginal": "2025:06:21 14:30:15",
+            "CreateDate": "2025:06:21 14:30:15",
+            "OffsetTimeOriginal": "+02:00",
+            "ExposureCompensation": -0.5,
+            "MeteringMode": 5,
+            "Flash": 16,
+            "FocalLength": 50,
+            "SubSecTimeOriginal": 25,
+            "ExifImageWidth": 8,
+            "ExifImageHeight": 8,
+            "FocalLengthIn35mmFormat": 50,
+            "LensModel": "EF50mm f/1.8 STM",
+            "GPSVersionID": "2 3 0 0",
+            "GPSLatitudeRef": "S",
+            "GPSLongitudeRef": "E",
+            "GPSAltitudeRef": 1,
+            "GPSTimeStamp": "12:30:15",
+            "GPSDateStamp": "2025:06:21",
+            "ImageWidth": 8,
+            "ImageHeight": 8,
+            "GPSAltitude": -5,
+            "GPSDateTime": "2025:06:21 12:30:15Z",
+            "GPSLatitude": -33.8701666666667,
+            "GPSLongitude": 151.208333333333,
+        })
+    }
+
+    fn native_exif() -> ExifMetadata {
+        NativeReader
+            .read(Uuid::nil(), &fixture("exif.jpg"))
+            .unwrap()
+            .exif
+    }
+
+    #[test]
+    fn maps_numeric_values_like_the_native_reader() {
+        let exif = exif_from_json(Uuid::nil(), &fixture_json());
+
+        assert_eq!(exif.orientation, Some(6));
+        assert_eq!(exif.flash, Some(16));
+        assert_eq!(exif.exposure_time.as_deref(), Some("1/125"));
+        assert_eq!(exif.gps_latitude.as_deref(), Some("33 deg 52' 12.60\" S"));
+        assert_eq!(comparable(&exif), comparable(&native_exif()));
+    }
+
+    #[test]
+    fn applies_the_hemisphere_to_unsigned_coordinates() {
+        // Without the composite tags, the Exif ones are unsigned
+        let mut data = fixture_json();
+        data["GPSLatitude"] = json!(33.8701666666667);
+        data["GPSAltitude"] = json!(5);
+
+        let exif = exif_from_json(Uuid::nil(), &data);
+        assert_eq!(exif.gps_latitude.as_deref(), Some("33 deg 52' 12.60\" S"));
+        assert!(exif.latitude.unwrap() < 0.0);
+        assert_eq!(exif.gps_altitude, to_big_decimal(-5.0));
+    }
+
+    #[test]
+    fn reads_keywords_from_every_group() {
+        let data = json!({
+            "Keywords": ["dog", " beach "],
+            "Subject": ["beach", "cat", ""],
+            "HierarchicalSubject": ["Animals|dog", "Places|France|Brittany", "2024"],
+        });
+        assert_eq!(
+            keywords_from_json(&data),
+            ["dog", "beach", "cat", "Brittany", "2024"]
+        );
+
+        // A single keyword isn't printed as a list, numeric ones are numbers
+        let data = json!({ "Keywords": "dog", "Subject": 2024 });
+        assert_eq!(keywords_from_json(&data), ["dog", "2024"]);
+        assert!(keywords_from_json(&json!({})).is_empty());
+    }
+
+    #[test]
+    fn prefers_the_iptc_caption() {
+        let data = json!({
+            "Caption-Abstract": " At the beach ",
+            "Description": "Sur la plage",
+        });
+        assert_eq!(caption_from_json(&data).as_deref(), Some("At the beach"));
+
+        let data = json!({ "Caption-Abstract": "  ", "Description": "Sur la plage" });
+        assert_eq!(caption_from_json(&data).as_deref(), Some("Sur la plage"));
+        assert_eq!(caption_from_json(&json!({ "Description": "" })), None);
+    }
+
+    #[test]
+    fn reads_the_same_metadata_as_the_native_reader() {
+        if !ExifToolReader::is_available() {
+            eprintln!("ExifTool is not installed, skipping");
+            return;
+        }
+
+        let metadata = ExifToolReader::new()
+            .read(Uuid::nil(), &fixture("exif.jpg"))
+            .unwrap();
+        assert_eq!(comparable(&metadata.exif), comparable(&native_exif()));
+    }
+}
@@ -0,0 +1,213 @@
+//! IPTC-IIM, the text properties news agencies embedded in photos long before XMP existed and
+//! still write next to it. Files store them as a Photoshop image resource, in the APP13 segment
+//! of JPEG files.
+
+use crate::xmp::add_keyword;
+
+/// The IPTC properties the catalog keeps.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Iptc {
+    pub caption: Option<String>,
+    /// Without duplicates.
+    pub keywords: Vec<String>,
+}
+
+/// Header of the Photoshop image resource holding the IPTC records.
+const IPTC_RESOURCE: &[u8] = b"8BIM\x04\x04";
+/// Starts every dataset.
+const TAG_MARKER: u8 = 0x1c;
+const APPLICATION_RECORD: u8 = 2;
+const KEYWORDS: u8 = 25;
+const CAPTION_ABSTRACT: u8 = 120;
+
+/// Finds the IPTC records of a photo file without knowing its format.
+pub fn read_iptc(data: &[u8]) -> Option<Iptc> {
+    let start = data
+        .windows(IPTC_RESOURCE.len())
+        .position(|window| window == IPTC_RESOURCE)?
+        + IPTC_RESOURCE.len();
+
+    // A Pascal string name, the length byte included it is padded to an even size
+    let name_length = *data.get(start)? as usize;
+    let size_start = start + ((name_length + 2) & !1);
+    let size = u32::from_be_bytes(data.get(size_start..size_start + 4)?.try_into().ok()?);
+    let records_start = size_start + 4;
+    let records_end = (records_start + size as usize).min(data.len());
+
+    Some(parse_records(data.get(records_start..records_end)?))
+}
+
+fn parse_records(mut data: &[u8]) -> Iptc {
+    let mut iptc = Iptc::default();
+
+    while let [TAG_MARKER, record, dataset, high, low, rest @ ..] = data {
+        let length = u16::from_be_bytes([*high, *low]) as usize;
+        // Extended datasets give the number of bytes of their length instead
+        let (length, rest) = if length & 0x8000 != 0 {
+            let length_size = length & 0x7fff;
+            if length_size > size_of::<usize>() || rest.len() < length_size {
+                break;
+            }
+            let (length, rest) = rest.split_at(length_size);
+            let length = length
+                .iter()
+                .fold(0usize, |length, byte| (length << 8) | *byte as usize);
+            (length, rest)
+        } else {
+            (length, rest)
+        };
+        if rest.len() < length {
+            break;
+        }
+        let (value, rest) = rest.split_at(length);
+
+        match (*record, *dataset) {
+            (APPLICATION_RECORD, KEYWORDS) => add_keyword(&mut iptc.keywords, &decode(value)),
+            (APPLICATION_RECORD, CAPTION_ABSTRACT) => {
+                let caption = decode(value);
+                let caption = caption.trim();
+                if !caption.is_empty() {
+                    iptc.caption = Some(caption.to_string());
+                }
+            }
+            _ => {}
+        }
+
+        data = rest;
+    }
+
+    iptc
+}
+
+/// Most files are UTF-8 whether they declare it or not, the older ones are Latin-1.
+fn decode(value: &[u8]) -> String {
+    let value = value.strip_suffix(b"\0").unwrap_or(value);
+    match std::str::from_utf8(value) {
+        Ok(text) => text.to_string(),
+        Err(_) => value.iter().map(|byte| *byte as char).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dataset(record: u8, dataset: u8, value: &[u8]) -> Vec<u8> {
+        let mut bytes = vec![TAG_MARKER, record, dataset];
+        bytes.extend_from_slice(&(value.len() as u16).to_be_bytes());
+        bytes.extend_from_slice(value);
+        bytes
+    }
+
+    /// An APP13 segment of a JPEG file holding the records, in a resource named `name`.
+    fn app13(name: &[u8], records: &[Vec<u8>]) -> Vec<u8> {
+        let records = records.concat();
+        let mut bytes = b"\xff\xd8\xff\xedPhotoshop 3.0\0".to_vec();
+        bytes.extend_from_slice(IPTC_RESOURCE);
+        bytes.push(name.len() as u8);
+        bytes.extend_from_slice(name);
+        if name.len().is_multiple_of(2) {
+            bytes.push(0);
+        }
+        bytes.extend_from_slice(&(records.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(&records);
+        bytes.extend_from_slice(b"\xff\xd9");
+        bytes
+    }
+
+    #[test]
+    fn reads_keywords_and_caption() {
+        let data = app13(
+            b"",
+            &[
+                dataset(1, 90, b"\x1b%G"),
+                dataset(APPLICATION_RECORD, KEYWORDS, b"dog"),
+                dataset(APPLICATION_RECORD, 5, b"Object name"),
+                dataset(APPLICATION_RECORD, KEYWORDS, b" beach "),
+                dataset(APPLICATION_RECORD, KEYWORDS, b"dog"),
+                dataset(APPLICATION_RECORD, KEYWORDS, b""),
+                dataset(APPLICATION_RECORD, CAPTION_ABSTRACT, b"  At the beach\0"),
+            ],
+        );
+
+        assert_eq!(
+            read_iptc(&data),
+            Some(Iptc {
+                caption: Some("At the beach".to_string()),
+                keywords: vec!["dog".to_string(), "beach".to_string()],
+            })
+        );
+    }
+
+    #[test]
+    fn skips_the_resource_name() {
+        for name in [&b"IPTC"[..], b"IPTC data"] {
+            let data = app13(name, &[dataset(APPLICATION_RECORD, KEYWORDS, b"cat")]);
+            assert_eq!(read_iptc(&data).unwrap().keywords, ["cat"]);
+        }
+    }
+
+    #[test]
+    fn decodes_utf8_and_latin1() {
+        let data = app13(
+            b"",
+            &[
+                dataset(APPLICATION_RECORD, KEYWORDS, "été".as_bytes()),
+                dataset(APPLICATION_RECORD, KEYWORDS, b"caf\xe9"),
+                dataset(APPLICATION_RECORD, CAPTION_ABSTRACT, b"Fa\xe7ade"),
+            ],
+        );
+        let iptc = read_iptc(&data).unwrap();
+
+        assert_eq!(iptc.keywords, ["été", "café"]);
+        assert_eq!(iptc.caption.as_deref(), Some("Façade"));
+    }
+
+    #[test]
+    fn blank_caption_is_ignored() {
+        let data = app13(
+            b"",
+            &[
+                dataset(APPLICATION_RECORD, CAPTION_ABSTRACT, b"First"),
+                dataset(APPLICATION_RECORD, CAPTION_ABSTRACT, b"   "),
+            ],
+        );
+
+        assert_eq!(read_iptc(&data).unwrap().caption.as_deref(), Some("First"));
+    }
+
+    #[test]
+    fn reads_extended_datasets() {
+        let mut extended = vec![TAG_MARKER, APPLICATION_RECORD, CAPTION_ABSTRACT, 0x80, 0x04];
+        extended.extend_from_slice(&5u32.to_be_bytes());
+        extended.extend_from_slice(b"Hello");
+        let data = app13(
+            b"",
+            &[extended, dataset(APPLICATION_RECORD, KEYWORDS, b"dog")],
+        );
+        let iptc = read_iptc(&data).unwrap();
+
+        assert_eq!(iptc.caption.as_deref(), Some("Hello"));
+        assert_eq!(iptc.keywords, ["dog"]);
+    }
+
+    #[test]
+    fn stops_at_truncated_records() {
+        let mut truncated = dataset(APPLICATION_RECORD, KEYWORDS, b"sunset");
+        truncated.truncate(8);
+        let records = [dataset(APPLICATION_RECORD, KEYWORDS, b"dog"), truncated].concat();
+
+        assert_eq!(parse_records(&records).keywords, ["dog"]);
+
+        // The resource size is larger than the file
+        let mut data = app13(b"", &[dataset(APPLICATION_RECORD, KEYWORDS, b"dog")]);
+        data.truncate(data.len() - 3);
+        assert_eq!(read_iptc(&data).unwrap().keywords, Vec::<String>::new());
+    }
+
+    #[test]
+    fn files_without_iptc() {
+        assert_eq!(read_iptc(b"\xff\xd8\xff\xd9"), None);
+        assert_eq!(read_iptc(b"8BIM\x04\x04\x00\x00\x00"), None);
+    }
+}
@@ -0,0 +1,442 @@
+use crate::schema::ExifMetadata;
+use crate::schema::types::TimeZoneSource;
+use anyhow::Result;
+use bigdecimal::BigDecimal;
+use chrono::{NaiveDateTime, TimeDelta};
+use dotenvy::dotenv;
+use std::env;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::OnceLock;
+use uuid::Uuid;
+
+pub mod exiftool;
+pub mod iptc;
+pub mod native;
+
+pub use self::exiftool::{ExifToolReader, ExifToolWriter};
+pub use self::native::NativeReader;
+
+/// Environment variable forcing a metadata reader, `exiftool` or `native`. When unset,
+/// ExifTool is used if it is installed.
+pub const METADATA_READER_VAR: &str = "METADATA_READER";
+
+/// What is read from a photo file.
+pub struct PhotoMetadata {
+    pub exif: ExifMetadata,
+    /// IPTC `Caption-Abstract`, or XMP `dc:description` when there is none.
+    pub caption: Option<String>,
+    /// IPTC `Keywords`, XMP `dc:subject` and the last level of `lr:hierarchicalSubject`, without
+    /// duplicates.
+    pub keywords: Vec<String>,
+}
+
+/// Extracts the metadata of a photo file: the row stored in `exif_metadata`, and the caption
+/// and keywords photographers and agencies embed.
+pub trait MetadataReader: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    fn read(&self, photo_id: Uuid, file_path: &Path) -> Result<PhotoMetadata>;
+}
+
+static READER: OnceLock<Box<dyn MetadataReader>> = OnceLock::new();
+
+/// The reader used for the whole process, chosen the first time metadata is read.
+pub fn metadata_reader() -> &'static dyn MetadataReader {
+    READER.get_or_init(select_reader).as_ref()
+}
+
+fn select_reader() -> Box<dyn MetadataReader> {
+    dotenv().ok();
+
+    let reader: Box<dyn MetadataReader> = match env::var(METADATA_READER_VAR).as_deref() {
+        Ok("exiftool") => Box::new(ExifToolReader::new()),
+        Ok("native") => Box::new(NativeReader),
+        Ok(other) => {
+            tracing::warn!("Unknown {} {:?}, ignoring it", METADATA_READER_VAR, other);
+            detect_reader()
+        }
+        Err(_) => detect_reader(),
+    };
+
+    tracing::info!("Reading metadata with {}", reader.name());
+    reader
+}
+
+/// Parses a coordinate as stored in the `exif_metadata` table, in ExifTool's notation
+/// `47 deg 29' 3.12" N`, or as signed decimal degrees like ExifTool reads them with `-n`.
+/// Southern and western hemispheres are negative.
+pub fn parse_gps_coordinate(value: &str) -> Option<f64> {
+    let value = value.trim();
+    let (value, hemisphere_negative) = match value.chars().last()? {
+        'N' | 'E' => (&value[..value.len() - 1], false),
+        'S' | 'W' => (&value[..value.len() - 1], true),
+        _ => (value, false),
+    };
+
+    let mut parts = value
+        .split(|c: char| c.is_whitespace() || c == '\'' || c == '"')
+        .map(|part| part.trim_end_matches("deg"))
+        .filter(|part| !part.is_empty());
+
+    let degrees: f64 = parts.next()?.parse().ok()?;
+    let minutes: f64 = parts.next().map_or(Ok(0.0), str::parse).ok()?;
+    let seconds: f64 = parts.next().map_or(Ok(0.0), str::parse).ok()?;
+    if parts.next().is_some() {
+        return None;
+    }
+
+    let magnitude = degrees.abs() + minutes / 60.0 + seconds / 3600.0;
+    if degrees.is_sign_negative() || hemisphere_negative {
+        Some(-magnitude)
+    } else {
+        Some(magnitude)
+    }
+}
+
+/// Offsets go from -12:00 to +14:00, anything further is a camera or GPS clock gone wrong.
+pub const MAX_UTC_OFFSET_MINUTES: i32 = 14 * 60;
+
+/// A capture time with the UTC offset it was taken at.
+pub struct CaptureTime {
+    /// UTC when the offset is known, the local time of the camera otherwise.
+    pub date_time: NaiveDateTime,
+    pub utc_offset_minutes: Option<i32>,
+    pub source: Option<TimeZoneSource>,
+}
+
+/// Combines the local time of the camera with its subseconds and offset. Cameras that don't
+/// record an offset but have a GPS fix get the difference with the GPS time, which is UTC.
+pub fn capture_time(
+    local: NaiveDateTime,
+    subseconds: Option<&str>,
+    offset: Option<&str>,
+    gps_time: Option<NaiveDateTime>,
+) -> CaptureTime {
+    let local = with_subseconds(local, subseconds);
+
+    let (utc_offset_minutes, source) = match offset.and_then(parse_utc_offset) {
+        Some(minutes) => (Some(minutes), Some(TimeZoneSource::Exif)),
+        None => match gps_time.and_then(|gps_time| gps_utc_offset(local, gps_time)) {
+            Some(minutes) => (Some(minutes), Some(TimeZoneSource::Gps)),
+            None => (None, None),
+        },
+    };
+
+    CaptureTime {
+        date_time: local - TimeDelta::minutes(utc_offset_minutes.unwrap_or_default() as i64),
+        utc_offset_minutes,
+        source,
+    }
+}
+
+/// `+02:00`, `-0530` or `Z`, in minutes east of UTC.
+pub fn parse_utc_offset(value: &str) -> Option<i32> {
+    let value = value.trim();
+    if value == "Z" {
+        return Some(0);
+    }
+
+    let sign = match value.chars().next()? {
+        '+' => 1,
+        '-' => -1,
+        _ => return None,
+    };
+    let digits: String = value[1..].chars().filter(|c| *c != ':').collect();
+    if digits.len() != 4 || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    let minutes = digits[..2].parse::<i32>().ok()? * 60 + digits[2..].parse::<i32>().ok()?;
+
+    (minutes <= MAX_UTC_OFFSET_MINUTES).then_some(sign * minutes)
+}
+
+/// `SubSecTimeOriginal` holds the digits after the decimal point, `25` is 250 milliseconds.
+fn with_subseconds(local: NaiveDateTime, subseconds: Option<&str>) -> NaiveDateTime {
+    let digits: String = subseconds
+        .unwrap_or_default()
+        .trim()
+        .chars()
+        .take_while(char::is_ascii_digit)
+        .take(9)
+        .collect();
+    if digits.is_empty() {
+        return local;
+    }
+
+    let nanoseconds: i64 = format!("{:0<9}", digits).parse().unwrap_or_default();
+    local + TimeDelta::nanoseconds(nanoseconds)
+}
+
+/// Time zones are whole quarters of an hour, the rest is the drift of the camera clock.
+fn gps_utc_offset(local: NaiveDateTime, gps_time: NaiveDateTime) -> Option<i32> {
+    let minutes = ((local - gps_time).num_seconds() as f64 / 900.0).round() as i32 * 15;
+
+    (minutes.abs() <= MAX_UTC_OFFSET_MINUTES).then_some(minutes)
+}
+
+/// Goes through the shortest decimal representation, so that `2.8` isn't stored as
+/// `2.79999...`.
+pub fn to_big_decimal(value: f64) -> Option<BigDecimal> {
+    if !value.is_finite() {
+        return None;
+    }
+
+    BigDecimal::from_str(&value.to_string()).ok()
+}
+
+/// Short exposures as a fraction of a second, the way cameras display them.
+pub fn format_exposure_time(seconds: f64) -> String {
+    if seconds > 0.0 && seconds < 0.25 {
+        format!("1/{}", (1.0 / seconds).round())
+    } else {
+        format!("{}", seconds)
+    }
+}
+
+/// Decimal degrees in ExifTool's notation, `47 deg 29' 3.12" N`. `references` are the
+/// hemispheres of positive and negative values, `('N', 'S')` or `('E', 'W')`.
+pub fn format_gps_coordinate(value: f64, references: (char, char)) -> String {
+    let reference = if value < 0.0 {
+        references.1
+    } else {
+        references.0
+    };
+
+    // Rounded once, so 59.999 seconds don't end up printed as 60.00
+    let hundredths = (value.abs() * 360_000.0).round() as u64;
+    let degrees = hundredths / 360_000;
+    let minutes = hundredths % 360_000 / 6_000;
+    let seconds = (hundredths % 6_000) as f64 / 100.0;
+
+    format!(
+        "{} deg {}' {:.2}\" {}",
+        degrees, minutes, seconds, reference
+    )
+}
+
+/// Latitude and longitude in decimal degrees, only when both are present and in range.
+pub fn gps_coordinates(
+    latitude: Option<&str>,
+    longitude: Option<&str>,
+) -> (Option<f64>, Option<f64>) {
+    let latitude = latitude.and_then(parse_gps_coordinate);
+    let longitude = longitude.and_then(parse_gps_coordinate);
+
+    match (latitude, longitude) {
+        (Some(lat), Some(lon)) if lat.abs() <= 90.0 && lon.abs() <= 180.0 => (Some(lat), Some(lon)),
+        _ => (None, None),
+    }
+}
+
+fn detect_reader() -> Box<dyn MetadataReader> {
+    if ExifToolReader::is_available() {
+        Box::new(ExifToolReader::new())
+    } else {
+        tracing::warn!("ExifTool is not installed, falling back to the native reader");
+        Box::new(NativeReader)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use serde_json::Value;
+    use std::path::PathBuf;
+
+    /// A file of `tests/fixtures`.
+    pub(super) fn fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures")
+            .join(name)
+    }
+
+    /// The row as JSON, without the columns that differ between two reads of the same file.
+    pub(super) fn comparable(exif: &ExifMetadata) -> Value {
+        let mut value = serde_json::to_value(exif).unwrap();
+        let fields = value.as_object_mut().unwrap();
+        for key in ["id", "photo_id", "created_at"] {
+            fields.remove(key);
+        }
+
+        value
+    }
+
+    fn date_time(hour: u32, minute: u32, second: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2025, 6, 21)
+            .unwrap()
+            .and_hms_opt(hour, minute, second)
+            .unwrap()
+    }
+
+    fn assert_close(actual: Option<f64>, expected: f64) {
+        let actual = actual.unwrap_or_else(|| panic!("expected {}, got None", expected));
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "expected {}, got {}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn parses_exiftool_coordinates() {
+        assert_close(parse_gps_coordinate("47 deg 29' 3.12\" N"), 47.484_2);
+        assert_close(
+            parse_gps_coordinate("33 deg 52' 12.60\" S"),
+            -33.870_166_666_666_67,
+        );
+        assert_close(
+            parse_gps_coordinate("122 deg 25' 9.00\" W"),
+            -122.419_166_666_666_67,
+        );
+        assert_close(parse_gps_coordinate("8 deg 0' 0.00\" E"), 8.0);
+    }
+
+    #[test]
+    fn parses_decimal_coordinates() {
+        assert_close(parse_gps_coordinate("47.4842"), 47.4842);
+        assert_close(parse_gps_coordinate("-33.870167"), -33.870167);
+        assert_close(parse_gps_coordinate(" 151.208333 "), 151.208333);
+        // A signed value with a hemisphere is still negative
+        assert_close(parse_gps_coordinate("-33.5 S"), -33.5);
+    }
+
+    #[test]
+    fn rejects_malformed_coordinates() {
+        assert_eq!(parse_gps_coordinate(""), None);
+        assert_eq!(parse_gps_coordinate("N"), None);
+        assert_eq!(parse_gps_coordinate("north"), None);
+        assert_eq!(parse_gps_coordinate("1 deg 2' 3\" 4 N"), None);
+    }
+
+    #[test]
+    fn formatted_coordinates_parse_back() {
+        for value in [47.484_2, -33.870_166_666_666_67, 0.0, -179.999_999] {
+            let text = format_gps_coordinate(value, ('E', 'W'));
+            let parsed = parse_gps_coordinate(&text).unwrap();
+            // Seconds are kept to the hundredth, about 30 cm
+            assert!(
+                (parsed - value).abs() < 0.01 / 3600.0,
+                "{} from {}",
+                text,
+                value
+            );
+        }
+        assert_eq!(
+            format_gps_coordinate(-33.870_166_666_666_67, ('N', 'S')),
+            "33 deg 52' 12.60\" S"
+        );
+        assert_eq!(
+            format_gps_coordinate(10.999_999_9, ('N', 'S')),
+            "11 deg 0' 0.00\" N"
+        );
+    }
+
+    #[test]
+    fn keeps_coordinates_only_in_pairs_and_in_range() {
+        let (lat, lon) = gps_coordinates(Some("47 deg 29' 3.12\" N"), Some("8.5"));
+        assert_close(lat, 47.484_2);
+        assert_close(lon, 8.5);
+
+        assert_eq!(gps_coordinates(Some("47.5"), None), (None, None));
+        assert_eq!(gps_coordinates(Some("91"), Some("8.5")), (None, None));
+        assert_eq!(gps_coordinates(Some("47.5"), Some("-180.5")), (None, None));
+    }
+
+    #[test]
+    fn parses_utc_offsets() {
+        assert_eq!(parse_utc_offset("+02:00"), Some(120));
+        assert_eq!(parse_utc_offset("-05:30"), Some(-330));
+        assert_eq!(parse_utc_offset("+0545"), Some(345));
+        assert_eq!(parse_utc_offset(" -0000 "), Some(0));
+        assert_eq!(parse_utc_offset("Z"), Some(0));
+        assert_eq!(parse_utc_offset("+14:00"), Some(14 * 60));
+    }
+
+    #[test]
+    fn rejects_malformed_utc_offsets() {
+        assert_eq!(parse_utc_offset(""), None);
+        assert_eq!(parse_utc_offset("02:00"), None);
+        assert_eq!(parse_utc_offset("+2:00"), None);
+        assert_eq!(parse_utc_offset("+02:00:00"), None);
+        assert_eq!(parse_utc_offset("+0a:00"), None);
+        assert_eq!(parse_utc_offset("+14:15"), None);
+        assert_eq!(parse_utc_offset("   :  "), None);
+    }
+
+    #[test]
+    fn capture_time_uses_the_recorded_offset() {
+        let capture = capture_time(
+            date_time(14, 30, 15),
+            Some("25"),
+            Some("+02:00"),
+            Some(date_time(9, 0, 0)),
+        );
+
+        // The recorded offset wins over the GPS clock
+        assert_eq!(capture.utc_offset_minutes, Some(120));
+        assert_eq!(capture.source, Some(TimeZoneSource::Exif));
+        assert_eq!(
+            capture.date_time,
+            date_time(12, 30, 15) + TimeDelta::milliseconds(250)
+        );
+    }
+
+    #[test]
+    fn capture_time_derives_the_offset_from_the_gps_clock() {
+        // The camera clock is 40 seconds ahead of the GPS, the offset is still a whole hour
+        let capture = capture_time(
+            date_time(14, 30, 55),
+            None,
+            None,
+            Some(date_time(12, 30, 15)),
+        );
+        assert_eq!(capture.utc_offset_minutes, Some(120));
+        assert_eq!(capture.source, Some(TimeZoneSource::Gps));
+        assert_eq!(capture.date_time, date_time(12, 30, 55));
+
+        // West of Greenwich and on a quarter hour
+        let capture = capture_time(
+            date_time(6, 15, 0),
+            None,
+            Some(""),
+            Some(date_time(9, 45, 0)),
+        );
+        assert_eq!(capture.utc_offset_minutes, Some(-210));
+        assert_eq!(capture.date_time, date_time(9, 45, 0));
+    }
+
+    #[test]
+    fn capture_time_ignores_a_gps_clock_too_far_off() {
+        let gps_time = date_time(14, 30, 0) - TimeDelta::days(2);
+        let capture = capture_time(date_time(14, 30, 0), None, None, Some(gps_time));
+
+        assert_eq!(capture.utc_offset_minutes, None);
+        assert_eq!(capture.source, None);
+        assert_eq!(capture.date_time, date_time(14, 30, 0));
+    }
+
+    #[test]
+    fn capture_time_without_offset_keeps_the_local_time() {
+        let capture = capture_time(date_time(14, 30, 15), Some("007 "), None, None);
+
+        assert_eq!(capture.utc_offset_minutes, None);
+        assert_eq!(capture.source, None);
+        assert_eq!(
+            capture.date_time,
+            date_time(14, 30, 15) + TimeDelta::milliseconds(7)
+        );
+    }
+
+    #[test]
+    fn formats_exposure_times_like_cameras() {
+        assert_eq!(format_exposure_time(0.008), "1/125");
+        assert_eq!(format_exposure_time(1.0 / 3.0), "0.3333333333333333");
+        assert_eq!(format_exposure_time(0.2), "1/5");
+        assert_eq!(format_exposure_time(2.0), "2");
+        assert_eq!(format_exposure_time(0.5), "0.5");
+    }
+}
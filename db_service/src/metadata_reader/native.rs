@@ -0,0 +1,464 @@
+use crate::metadata_reader::iptc::read_iptc;
+use crate::metadata_reader::{
+    MetadataReader, PhotoMetadata, capture_time, format_exposure_time, gps_coordinates,
+    to_big_decimal,
+};
+use crate::schema::ExifMetadata;
+use crate::xmp::{add_keyword, read_packet};
+use anyhow::{Result, bail};
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use exif::{Exif, Field, In, Reader, Tag, Value};
+use std::fs;
+use std::io::Cursor;
+use std::path::Path;
+use uuid::Uuid;
+
+/// Reads the Exif block of JPEG, TIFF, HEIF, PNG and WebP files in process, without any
+/// external tool, along with their IPTC records and XMP packet.
+///
+/// Values are stored the way ExifTool prints them where the column is text, e.g. `1/125` for
+/// the exposure time. Numeric columns hold the raw Exif codes, as ExifTool reads them with `-n`.
+pub struct NativeReader;
+
+impl MetadataReader for NativeReader {
+    fn name(&self) -> &'static str {
+        "native"
+    }
+
+    fn read(&self, photo_id: Uuid, file_path: &Path) -> Result<PhotoMetadata> {
+        let data = fs::read(file_path)?;
+        let exif = match Reader::new().read_from_container(&mut Cursor::new(&data)) {
+            Ok(exif) => Some(exif),
+            // Most PNG files have no Exif data, their dimensions are still worth keeping
+            Err(exif::Error::NotFound(_)) => None,
+            Err(e) => return Err(e.into()),
+        };
+
+        let dimensions = image::image_dimensions(file_path).ok();
+        if exif.is_none() && dimensions.is_none() {
+            bail!("No metadata found in {:?}", file_path);
+        }
+
+        let tags = Tags(exif.as_ref());
+        let (width, height) = match (
+            tags.uint(Tag::PixelXDimension),
+            tags.uint(Tag::PixelYDimension),
+        ) {
+            (Some(width), Some(height)) => (Some(width), Some(height)),
+            _ => match dimensions {
+                Some((width, height)) => (Some(width as i32), Some(height as i32)),
+                None => (None, None),
+            },
+        };
+
+        let gps_latitude = tags.gps_coordinate(Tag::GPSLatitude, Tag::GPSLatitudeRef);
+        let gps_longitude = tags.gps_coordinate(Tag::GPSLongitude, Tag::GPSLongitudeRef);
+        let (latitude, longitude) =
+            gps_coordinates(gps_latitude.as_deref(), gps_longitude.as_deref());
+
+        let subsec_time_original = tags.text(Tag::SubSecTimeOriginal);
+        let gps_timestamp = tags.gps_timestamp();
+        let capture = tags.date_time(Tag::DateTimeOriginal).map(|local| {
+            capture_time(
+                local,
+                subsec_time_original.as_deref(),
+                tags.text(Tag::OffsetTimeOriginal).as_deref(),
+                gps_timestamp,
+            )
+        });
+
+        let exif = ExifMetadata {
+            id: Uuid::new_v4(),
+            photo_id,
+
+            make: tags.text(Tag::Make),
+            model: tags.text(Tag::Model),
+            orientation: tags.uint(Tag::Orientation),
+            software: tags.text(Tag::Software),
+
+            x_resolution: tags.decimal(Tag::XResolution).and_then(to_big_decimal),
+            y_resolution: tags.decimal(Tag::YResolution).and_then(to_big_decimal),
+            resolution_unit: tags.uint(Tag::ResolutionUnit),
+            pixel_x_dimension: width,
+            pixel_y_dimension: height,
+
+            date_time: tags.date_time(Tag::DateTime),
+            date_time_original: capture.as_ref().map(|capture| capture.date_time),
+            date_time_digitized: tags.date_time(Tag::DateTimeDigitized),
+            subsec_time: tags.text(Tag::SubSecTime),
+            subsec_time_original,
+            subsec_time_digitized: tags.text(Tag::SubSecTimeDigitized),
+
+            exposure_time: tags.decimal(Tag::ExposureTime).map(format_exposure_time),
+            f_number: tags.decimal(Tag::FNumber).and_then(to_big_decimal),
+            exposure_program: tags.uint(Tag::ExposureProgram),
+            iso_speed: tags.uint(Tag::PhotographicSensitivity),
+            // APEX values, converted to seconds and f-numbers like ExifTool does
+            shutter_speed_value: tags
+                .decimal(Tag::ShutterSpeedValue)
+                .and_then(|apex| to_big_decimal(2f64.powf(-apex))),
+            aperture_value: tags
+                .decimal(Tag::ApertureValue)
+                .and_then(|apex| to_big_decimal(2f64.powf(apex / 2.0))),
+            brightness_value: tags.decimal(Tag::BrightnessValue).and_then(to_big_decimal),
+            exposure_bias: tags
+                .decimal(Tag::ExposureBiasValue)
+                .and_then(to_big_decimal),
+            max_aperture_value: tags
+                .decimal(Tag::MaxApertureValue)
+                .and_then(|apex| to_big_decimal(2f64.powf(apex / 2.0))),
+            exposure_mode: tags.uint(Tag::ExposureMode),
+
+            metering_mode: tags.uint(Tag::MeteringMode),
+            light_source: tags.uint(Tag::LightSource),
+            flash: tags.uint(Tag::Flash),
+            focal_length: tags.decimal(Tag::FocalLength).and_then(to_big_decimal),
+            focal_length_in_35mm_film: tags.uint(Tag::FocalLengthIn35mmFilm),
+
+            white_balance: tags.uint(Tag::WhiteBalance),
+            scene_capture_type: tags.uint(Tag::SceneCaptureType),
+            custom_rendered: tags.uint(Tag::CustomRendered),
+            contrast: tags.uint(Tag::Contrast),
+            saturation: tags.uint(Tag::Saturation),
+            sharpness: tags.uint(Tag::Sharpness),
+            gain_control: tags.decimal(Tag::GainControl).and_then(to_big_decimal),
+
+            exif_version: tags.bytes_text(Tag::ExifVersion),
+            image_unique_id: tags.text(Tag::ImageUniqueID),
+            components_configuration: tags.display(Tag::ComponentsConfiguration),
+
+            gps_version_id: tags.gps_version(),
+            gps_latitude,
+            gps_longitude,
+            gps_altitude: tags.gps_altitude().and_then(to_big_decimal),
+            gps_timestamp,
+            gps_processing_method: tags.gps_processing_method(),
+            gps_date_stamp: tags.text(Tag::GPSDateStamp),
+
+            created_at: Some(Utc::now().naive_utc()),
+
+            latitude,
+            longitude,
+
+            utc_offset_minutes: capture
+                .as_ref()
+                .and_then(|capture| capture.utc_offset_minutes),
+            time_zone_source: capture.and_then(|capture| capture.source),
+
+            lens_make: tags.text(Tag::LensMake),
+            lens_model: tags.text(Tag::LensModel),
+        };
+
+        Ok(embedded_text(exif, &data, file_path))
+    }
+}
+
+/// Adds the caption and keywords, IPTC first like ExifTool. A broken XMP packet doesn't lose
+/// the Exif data.
+fn embedded_text(exif: ExifMetadata, data: &[u8], file_path: &Path) -> PhotoMetadata {
+    let iptc = read_iptc(data).unwrap_or_default();
+    let xmp = read_packet(data).unwrap_or_else(|err| {
+        tracing::warn!("Ignoring the XMP packet of {:?}: {:#}", file_path, err);
+        None
+    });
+
+    let mut keywords = iptc.keywords;
+    let mut caption = iptc.caption;
+    if let Some(xmp) = xmp {
+        for keyword in &xmp.keywords {
+            add_keyword(&mut keywords, keyword);
+        }
+        caption = caption.or(xmp.caption);
+    }
+
+    PhotoMetadata {
+        exif,
+        caption,
+        keywords,
+    }
+}
+
+/// The fields of the primary image, the one the thumbnail IFD describes is ignored.
+struct Tags<'a>(Option<&'a Exif>);
+
+impl Tags<'_> {
+    fn field(&self, tag: Tag) -> Option<&Field> {
+        self.0?.get_field(tag, In::PRIMARY)
+    }
+
+    fn text(&self, tag: Tag) -> Option<String> {
+        match &self.field(tag)?.value {
+            Value::Ascii(values) => values
+                .first()
+                .map(|bytes| String::from_utf8_lossy(bytes).trim().to_string())
+                .filter(|text| !text.is_empty()),
+            _ => None,
+        }
+    }
+
+    /// Text stored as undefined bytes, e.g. the `0232` of the Exif version.
+    fn bytes_text(&self, tag: Tag) -> Option<String> {
+        match &self.field(tag)?.value {
+            Value::Undefined(bytes, _) => Some(String::from_utf8_lossy(bytes).to_string()),
+            _ => None,
+        }
+    }
+
+    fn display(&self, tag: Tag) -> Option<String> {
+        Some(self.field(tag)?.display_value().to_string())
+    }
+
+    fn uint(&self, tag: Tag) -> Option<i32> {
+        self.field(tag)?.value.get_uint(0).map(|value| value as i32)
+    }
+
+    fn decimals(&self, tag: Tag) -> Option<Vec<f64>> {
+        let decimals = match &self.field(tag)?.value {
+            Value::Rational(values) => values.iter().map(|value| value.to_f64()).collect(),
+            Value::SRational(values) => values.iter().map(|value| value.to_f64()).collect(),
+            value => value.iter_uint()?.map(f64::from).collect(),
+        };
+
+        Some(decimals)
+    }
+
+    fn decimal(&self, tag: Tag) -> Option<f64> {
+        self.decimals(tag)?.first().copied()
+    }
+
+    fn date_time(&self, tag: Tag) -> Option<NaiveDateTime> {
+        NaiveDateTime::parse_from_str(&self.text(tag)?, "%Y:%m:%d %H:%M:%S").ok()
+    }
+
+    fn gps_version(&self) -> Option<String> {
+        match &self.field(Tag::GPSVersionID)?.value {
+            Value::Byte(bytes) => Some(
+                bytes
+                    .iter()
+                    .map(|byte| byte.to_string())
+                    .collect::<Vec<_>>()
+                    .join("."),
+            ),
+            _ => None,
+        }
+    }
+
+    /// Degrees, minutes and seconds in ExifTool's notation: `48 deg 51' 24.00" N`.
+    fn gps_coordinate(&self, tag: Tag, reference: Tag) -> Option<String> {
+        let parts = self.decimals(tag)?;
+        let [degrees, minutes, seconds] = parts[..] else {
+            return None;
+        };
+        let reference = self.text(reference).unwrap_or_default();
+
+        Some(
+            format!(
+                "{} deg {}' {:.2}\" {}",
+                degrees, minutes, seconds, reference
+            )
+            .trim_end()
+            .to_string(),
+        )
+    }
+
+    fn gps_altitude(&self) -> Option<f64> {
+        let altitude = self.decimal(Tag::GPSAltitude)?;
+
+        // A reference of 1 means below sea level
+        match self.uint(Tag::GPSAltitudeRef) {
+            Some(1) => Some(-altitude),
+            _ => Some(altitude),
+        }
+    }
+
+    /// The GPS date and time combined, both are UTC.
+    fn gps_timestamp(&self) -> Option<NaiveDateTime> {
+        let date = NaiveDate::parse_from_str(&self.text(Tag::GPSDateStamp)?, "%Y:%m:%d").ok()?;
+        let parts = self.decimals(Tag::GPSTimeStamp)?;
+        let [hours, minutes, seconds] = parts[..] else {
+            return None;
+        };
+        let time = NaiveTime::from_hms_milli_opt(
+            hours as u32,
+            minutes as u32,
+            seconds.trunc() as u32,
+            (seconds.fract() * 1000.0).round() as u32,
+        )?;
+
+        Some(date.and_time(time))
+    }
+
+    /// Stored as undefined bytes behind an 8 byte character code, e.g. `ASCII\0\0\0`.
+    fn gps_processing_method(&self) -> Option<String> {
+        match &self.field(Tag::GPSProcessingMethod)?.value {
+            Value::Undefined(bytes, _) if bytes.len() > 8 => {
+                let text = String::from_utf8_lossy(&bytes[8..]);
+                Some(text.trim_end_matches('\0').trim().to_string())
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata_reader::tests::{comparable, fixture};
+    use crate::schema::types::TimeZoneSource;
+    use chrono::TimeDelta;
+
+    fn read(name: &str) -> ExifMetadata {
+        NativeReader.read(Uuid::nil(), &fixture(name)).unwrap().exif
+    }
+
+    #[test]
+    fn reads_the_exif_of_a_jpeg() {
+        let exif = read("exif.jpg");
+
+        assert_eq!(exif.make.as_deref(), Some("Canon"));
+        assert_eq!(exif.model.as_deref(), Some("Canon EOS 5D Mark IV"));
+        assert_eq!(exif.lens_model.as_deref(), Some("EF50mm f/1.8 STM"));
+        assert_eq!(exif.orientation, Some(6));
+        assert_eq!(
+            (exif.pixel_x_dimension, exif.pixel_y_dimension),
+            (Some(8), Some(8))
+        );
+
+        assert_eq!(exif.exposure_time.as_deref(), Some("1/125"));
+        assert_eq!(exif.f_number, to_big_decimal(2.8));
+        assert_eq!(exif.exposure_bias, to_big_decimal(-0.5));
+        assert_eq!(exif.iso_speed, Some(400));
+        assert_eq!(exif.exposure_program, Some(3));
+        assert_eq!(exif.metering_mode, Some(5));
+        assert_eq!(exif.flash, Some(16));
+        assert_eq!(exif.focal_length, to_big_decimal(50.0));
+        assert_eq!(exif.focal_length_in_35mm_film, Some(50));
+        assert_eq!(exif.exif_version.as_deref(), Some("0232"));
+    }
+
+    #[test]
+    fn reads_the_capture_time_in_utc() {
+        let exif = read("exif.jpg");
+        let local = NaiveDate::from_ymd_opt(2025, 6, 21)
+            .unwrap()
+            .and_hms_milli_opt(14, 30, 15, 250)
+            .unwrap();
+
+        assert_eq!(exif.subsec_time_original.as_deref(), Some("25"));
+        assert_eq!(exif.utc_offset_minutes, Some(120));
+        assert_eq!(exif.time_zone_source, Some(TimeZoneSource::Exif));
+        assert_eq!(exif.date_time_original, Some(local - TimeDelta::hours(2)));
+    }
+
+    #[test]
+    fn reads_the_gps_position() {
+        let exif = read("exif.jpg");
+
+        assert_eq!(exif.gps_version_id.as_deref(), Some("2.3.0.0"));
+        assert_eq!(exif.gps_latitude.as_deref(), Some("33 deg 52' 12.60\" S"));
+        assert_eq!(exif.gps_longitude.as_deref(), Some("151 deg 12' 30.00\" E"));
+        assert!((exif.latitude.unwrap() + 33.870_166_666).abs() < 1e-6);
+        assert!((exif.longitude.unwrap() - 151.208_333_333).abs() < 1e-6);
+        // Below sea level
+        assert_eq!(exif.gps_altitude, to_big_decimal(-5.0));
+        assert_eq!(
+            exif.gps_timestamp,
+            NaiveDate::from_ymd_opt(2025, 6, 21)
+                .unwrap()
+                .and_hms_opt(12, 30, 15)
+        );
+    }
+
+    #[test]
+    fn reads_tiff_files_of_either_byte_order() {
+        // The JPEG holds a big endian Exif block, the TIFF file is little endian
+        assert_eq!(comparable(&read("exif.tif")), comparable(&read("exif.jpg")));
+    }
+
+    #[test]
+    fn fails_without_any_metadata() {
+        let file = std::env::temp_dir().join(format!("{}.jpg", Uuid::new_v4()));
+        fs::write(&file, b"not a photo").unwrap();
+
+        let result = NativeReader.read(Uuid::nil(), &file);
+        fs::remove_file(&file).unwrap();
+
+        assert!(result.is_err());
+    }
+
+    /// A Photoshop IPTC resource with the keywords and caption.
+    fn iptc(keywords: &[&str], caption: Option<&str>) -> Vec<u8> {
+        let mut records = Vec::new();
+        let datasets = keywords
+            .iter()
+            .map(|keyword| (25, *keyword))
+            .chain(caption.map(|caption| (120, caption)));
+        for (dataset, value) in datasets {
+            records.extend_from_slice(&[0x1c, 2, dataset]);
+            records.extend_from_slice(&(value.len() as u16).to_be_bytes());
+            records.extend_from_slice(value.as_bytes());
+        }
+
+        let mut data = b"Photoshop 3.0\0".to_vec();
+        // An empty resource name, padded to two bytes
+        data.extend_from_slice(b"8BIM\x04\x04\0\0");
+        data.extend_from_slice(&(records.len() as u32).to_be_bytes());
+        data.extend_from_slice(&records);
+        data
+    }
+
+    const XMP_PACKET: &str = r#"<x:xmpmeta xmlns:x="adobe:ns:meta/">
+ <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+  <rdf:Description rdf:about=""
+    xmlns:dc="http://purl.org/dc/elements/1.1/"
+    xmlns:lr="http://ns.adobe.com/lightroom/1.0/">
+   <dc:subject><rdf:Bag><rdf:li>cat</rdf:li><rdf:li>dog</rdf:li></rdf:Bag></dc:subject>
+   <lr:hierarchicalSubject><rdf:Bag><rdf:li>Places|Paris</rdf:li></rdf:Bag></lr:hierarchicalSubject>
+   <dc:description><rdf:Alt><rdf:li xml:lang="x-default">XMP caption</rdf:li></rdf:Alt></dc:description>
+  </rdf:Description>
+ </rdf:RDF>
+</x:xmpmeta>"#;
+
+    #[test]
+    fn merges_iptc_and_xmp_text() {
+        let data = [
+            iptc(&["dog", "beach"], Some("IPTC caption")),
+            XMP_PACKET.as_bytes().to_vec(),
+        ]
+        .concat();
+        let metadata = embedded_text(read("exif.jpg"), &data, Path::new("a.jpg"));
+
+        // IPTC first, like ExifTool
+        assert_eq!(metadata.keywords, ["dog", "beach", "cat", "Paris"]);
+        assert_eq!(metadata.caption.as_deref(), Some("IPTC caption"));
+        assert_eq!(metadata.exif.make.as_deref(), Some("Canon"));
+
+        let data = [iptc(&["dog"], None), XMP_PACKET.as_bytes().to_vec()].concat();
+        let metadata = embedded_text(read("exif.jpg"), &data, Path::new("a.jpg"));
+        assert_eq!(metadata.keywords, ["dog", "cat", "Paris"]);
+        assert_eq!(metadata.caption.as_deref(), Some("XMP caption"));
+    }
+
+    #[test]
+    fn broken_xmp_keeps_the_iptc_text() {
+        let data = [
+            iptc(&["dog"], Some("IPTC caption")),
+            b"<x:xmpmeta xmlns:x=\"adobe:ns:meta/\"><rdf:RDF>".to_vec(),
+        ]
+        .concat();
+        let metadata = embedded_text(read("exif.jpg"), &data, Path::new("a.jpg"));
+
+        assert_eq!(metadata.keywords, ["dog"]);
+        assert_eq!(metadata.caption.as_deref(), Some("IPTC caption"));
+    }
+
+    #[test]
+    fn fixtures_have_no_embedded_text() {
+        let metadata = NativeReader
+            .read(Uuid::nil(), &fixture("exif.jpg"))
+            .unwrap();
+
+        assert!(metadata.keywords.is_empty());
+        assert_eq!(metadata.caption, None);
+    }
+}
@@ -1,3 +1,5 @@
+use crate::schema::types::{ColorLabel, PhotoFlag, ProcessingStage, TagSource, TimeZoneSource};
+use crate::services::photo::PhotoQuery;
 use bigdecimal::BigDecimal;
 use chrono::NaiveDateTime;
 use diesel::prelude::*;
@@ -5,6 +7,7 @@ use pgvector::Vector;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+#[allow(clippy::module_inception)]
 pub mod schema;
 pub mod types;
 
@@ -15,11 +18,12 @@ pub mod types;
 pub struct Directory {
     pub id: Uuid,
     pub path: String,
-    pub is_imported: bool,
     pub added_time: NaiveDateTime,
     pub photo_count: i32,
-    pub is_tagged: bool,
-    pub is_face_tagging_done: bool,
+    /// Offset of the camera clocks, for the photos that don't record theirs.
+    pub utc_offset_minutes: Option<i32>,
+    /// Whether changes made in the catalog are written to the XMP sidecars of the photos.
+    pub xmp_sync: bool,
 }
 
 #[derive(Insertable)]
@@ -40,6 +44,54 @@ pub struct Photo {
     pub name: String,
 }
 
+#[derive(Insertable, Clone)]
+#[diesel(table_name = crate::schema::schema::photos)]
+pub struct NewPhoto {
+    pub id: Uuid,
+    pub path: Uuid,
+    pub name: String,
+    pub file_size: Option<i64>,
+    pub modified_time: Option<NaiveDateTime>,
+    pub content_hash: Option<String>,
+}
+
+impl From<&NewPhoto> for Photo {
+    fn from(photo: &NewPhoto) -> Self {
+        Photo {
+            id: photo.id,
+            path: photo.path,
+            name: photo.name.clone(),
+        }
+    }
+}
+
+/// The on-disk state we remember for a photo, used to detect changes when rescanning.
+#[derive(Queryable, Selectable)]
+#[diesel(table_name = crate::schema::schema::photos)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct PhotoFileState {
+    pub id: Uuid,
+    pub name: String,
+    pub file_size: Option<i64>,
+    pub modified_time: Option<NaiveDateTime>,
+    pub is_missing: bool,
+}
+
+#[derive(Serialize, Default, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RescanSummary {
+    pub added: Vec<Uuid>,
+    pub updated: Vec<Uuid>,
+    pub missing: Vec<Uuid>,
+    pub photo_count: i32,
+}
+
+impl RescanSummary {
+    pub fn has_changes(&self) -> bool {
+        !self.added.is_empty() || !self.updated.is_empty() || !self.missing.is_empty()
+    }
+}
+
 // #[derive(Selectable, Deserialize, Serialize)]
 // pub struct PhotoWithTags {
 //     pub id: Uuid,
@@ -67,6 +119,8 @@ pub struct ExifMetadata {
     pub pixel_y_dimension: Option<i32>,
 
     pub date_time: Option<NaiveDateTime>,
+    /// The instant of the capture, in UTC when `utc_offset_minutes` is known and in the local
+    /// time of the camera otherwise.
     pub date_time_original: Option<NaiveDateTime>,
     pub date_time_digitized: Option<NaiveDateTime>,
     pub subsec_time: Option<String>,
@@ -111,6 +165,16 @@ pub struct ExifMetadata {
     pub gps_date_stamp: Option<String>,
 
     pub created_at: Option<NaiveDateTime>,
+
+    /// Signed decimal degrees, only set when both coordinates could be read.
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+
+    pub utc_offset_minutes: Option<i32>,
+    pub time_zone_source: Option<TimeZoneSource>,
+
+    pub lens_make: Option<String>,
+    pub lens_model: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -118,7 +182,9 @@ pub struct PhotoSummary {
     pub id: Uuid,
     pub make: Option<String>,
     pub model: Option<String>,
+    /// Local time of the capture, as the camera displayed it.
     pub date_time_original: Option<NaiveDateTime>,
+    pub utc_offset_minutes: Option<i32>,
     pub width: Option<i32>,
     pub height: Option<i32>,
     pub iso_speed: Option<i32>,
@@ -127,6 +193,7 @@ pub struct PhotoSummary {
     pub focal_length: Option<f64>,
     pub gps_latitude: Option<String>,
     pub gps_longitude: Option<String>,
+    pub caption: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -134,12 +201,338 @@ pub struct Folders {
     pub name: String,
 }
 
+/// One of the copies of a file that exists several times in the catalog.
+#[derive(Queryable, Selectable, Serialize, Clone, Debug)]
+#[diesel(table_name = crate::schema::schema::photos)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicatePhoto {
+    pub id: Uuid,
+    #[diesel(select_expression = crate::schema::schema::directories::id)]
+    pub directory_id: Uuid,
+    #[diesel(select_expression = crate::schema::schema::directories::path)]
+    pub directory_path: String,
+    pub name: String,
+    pub file_size: Option<i64>,
+    pub modified_time: Option<NaiveDateTime>,
+    pub content_hash: Option<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateGroup {
+    pub content_hash: String,
+    pub photos: Vec<DuplicatePhoto>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateReport {
+    pub groups: Vec<DuplicateGroup>,
+    /// Some photos have no content hash yet, their copies are missing from the groups.
+    pub incomplete: bool,
+}
+
+/// A photo of a group of visually similar photos, e.g. a burst or a resized copy.
+#[derive(Queryable, Selectable, Serialize, Clone, Debug)]
+#[diesel(table_name = crate::schema::schema::photos)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+#[serde(rename_all = "camelCase")]
+pub struct SimilarPhoto {
+    pub id: Uuid,
+    pub path: Uuid,
+    pub name: String,
+    pub file_size: Option<i64>,
+    #[diesel(select_expression = crate::schema::schema::exif_metadata::date_time_original.nullable())]
+    pub date_time_original: Option<NaiveDateTime>,
+    #[diesel(select_expression = crate::schema::schema::exif_metadata::pixel_x_dimension.nullable())]
+    pub width: Option<i32>,
+    #[diesel(select_expression = crate::schema::schema::exif_metadata::pixel_y_dimension.nullable())]
+    pub height: Option<i32>,
+    #[serde(skip)]
+    pub perceptual_hash: Option<i64>,
+}
+
+/// An area of the map in decimal degrees. `west` is greater than `east` when the area crosses
+/// the antimeridian.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct GeoBounds {
+    pub north: f64,
+    pub south: f64,
+    pub east: f64,
+    pub west: f64,
+}
+
+/// A photo with a known position.
+#[derive(Queryable, Selectable, Serialize, Clone, Debug)]
+#[diesel(table_name = crate::schema::schema::photos)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+#[serde(rename_all = "camelCase")]
+pub struct GeoPhoto {
+    pub id: Uuid,
+    pub path: Uuid,
+    pub name: String,
+    #[diesel(select_expression = crate::schema::schema::exif_metadata::date_time_original)]
+    pub date_time_original: Option<NaiveDateTime>,
+    #[diesel(select_expression = crate::schema::schema::exif_metadata::latitude.assume_not_null())]
+    pub latitude: f64,
+    #[diesel(select_expression = crate::schema::schema::exif_metadata::longitude.assume_not_null())]
+    pub longitude: f64,
+}
+
+/// The photos of one cell of the map grid at a given zoom level, positioned at their centroid.
+/// `photo_id` is the most recent of them, shown as the cover of the cluster.
+#[derive(QueryableByName, Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PhotoCluster {
+    #[diesel(sql_type = diesel::sql_types::Float8)]
+    pub latitude: f64,
+    #[diesel(sql_type = diesel::sql_types::Float8)]
+    pub longitude: f64,
+    #[diesel(sql_type = diesel::sql_types::BigInt)]
+    pub photo_count: i64,
+    #[diesel(sql_type = diesel::sql_types::Uuid)]
+    pub photo_id: Uuid,
+    #[diesel(sql_type = diesel::sql_types::Float8)]
+    pub north: f64,
+    #[diesel(sql_type = diesel::sql_types::Float8)]
+    pub south: f64,
+    #[diesel(sql_type = diesel::sql_types::Float8)]
+    pub east: f64,
+    #[diesel(sql_type = diesel::sql_types::Float8)]
+    pub west: f64,
+}
+
+/// How photo times relate to the times of a track log, which are UTC.
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct GeotagOptions {
+    /// How far the camera clock was ahead of the real time, in seconds.
+    pub camera_offset_seconds: i64,
+    /// Offset from UTC of the time zone the camera clock was set to, in minutes. Only used for
+    /// the photos whose offset isn't known.
+    pub utc_offset_minutes: i32,
+    /// Photos further than this from every track point are left alone.
+    pub max_gap_seconds: Option<i64>,
+    /// Only match the photos of this directory.
+    pub directory_id: Option<Uuid>,
+}
+
+/// The position found on a track log for a photo without coordinates.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct GeotagMatch {
+    pub photo_id: Uuid,
+    pub name: String,
+    pub date_time_original: NaiveDateTime,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub altitude: Option<f64>,
+    /// Time between the photo and the closest track point.
+    pub gap_seconds: i64,
+}
+
+/// A city of the GeoNames dump photos are geocoded against.
+#[derive(Queryable, Selectable, Insertable, Serialize, Clone, Debug)]
+#[diesel(table_name = crate::schema::schema::places)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+#[serde(rename_all = "camelCase")]
+pub struct Place {
+    pub id: i32,
+    pub name: String,
+    pub region: Option<String>,
+    pub country_code: String,
+    pub country: String,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub population: i64,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::schema::photo_places)]
+pub struct PhotoPlace {
+    pub photo_id: Uuid,
+    pub place_id: i32,
+    pub distance_meters: f64,
+}
+
+/// Restricts photos to a country, and optionally to one of its regions or cities.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaceFilter {
+    pub country_code: String,
+    pub region: Option<String>,
+    pub place_id: Option<i32>,
+}
+
+/// A country, region or city with the number of photos taken there. `filter` selects the
+/// photos of the node.
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaceNode {
+    pub name: String,
+    pub filter: PlaceFilter,
+    pub photo_count: i64,
+    pub children: Vec<PlaceNode>,
+}
+
+/// A folder inside an imported directory, `path` is relative to the directory root.
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct FolderNode {
+    pub name: String,
+    pub path: String,
+    pub photo_count: i32,
+    pub children: Vec<FolderNode>,
+}
+
+/// What the photo grid is sorted by. Photos without a capture date or a file size come last,
+/// in both directions.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum PhotoSort {
+    CaptureDate,
+    ImportDate,
+    #[default]
+    Name,
+    FileSize,
+    Rating,
+}
+
+/// The keys of the last photo of a page, the next page starts right after it. All the keys are
+/// kept so the cursor doesn't depend on the sort.
+#[derive(Queryable, Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PhotoCursor {
+    pub id: Uuid,
+    pub name: String,
+    pub date_time_original: Option<NaiveDateTime>,
+    pub imported_at: NaiveDateTime,
+    pub file_size: Option<i64>,
+    pub rating: i16,
+}
+
+/// A page of the photo grid, the first one when `after` is `None`.
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct PhotoPageRequest {
+    #[serde(default)]
+    pub sort: PhotoSort,
+    #[serde(default)]
+    pub descending: bool,
+    pub after: Option<PhotoCursor>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PhotoPage {
+    pub photos: Vec<Photo>,
+    /// `None` once the last page is reached.
+    pub next_cursor: Option<PhotoCursor>,
+}
+
+/// The rating, flag and color label given to a photo while culling.
+#[derive(Queryable, Selectable, Serialize, Clone, Debug)]
+#[diesel(table_name = crate::schema::schema::photos)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+#[serde(rename_all = "camelCase")]
+pub struct PhotoCulling {
+    #[diesel(column_name = id)]
+    pub photo_id: Uuid,
+    pub rating: i16,
+    pub flag: Option<PhotoFlag>,
+    pub color_label: Option<ColorLabel>,
+}
+
+/// Changes to the culling state of a photo, the fields left to `None` are not touched.
+#[derive(AsChangeset)]
+#[diesel(table_name = crate::schema::schema::photos)]
+pub struct PhotoCullingUpdate {
+    pub rating: Option<i16>,
+    pub flag: Option<PhotoFlag>,
+    pub color_label: Option<ColorLabel>,
+}
+
+/// A saved search, `query` holds a serialized `PhotoQuery`.
+#[derive(Queryable, Selectable, Insertable, Clone, Debug)]
+#[diesel(table_name = crate::schema::schema::smart_albums)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct SmartAlbumRow {
+    pub id: Uuid,
+    pub name: String,
+    pub query: serde_json::Value,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+/// A smart album as the sidebar shows it, `photo_count` is counted when it is read.
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SmartAlbum {
+    pub id: Uuid,
+    pub name: String,
+    pub query: PhotoQuery,
+    pub photo_count: i64,
+}
+
+#[derive(Queryable, Selectable, Insertable, Clone, Debug)]
+#[diesel(table_name = crate::schema::schema::albums)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct AlbumRow {
+    pub id: Uuid,
+    pub name: String,
+    pub cover_photo_id: Option<Uuid>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Insertable, Clone, Debug)]
+#[diesel(table_name = crate::schema::schema::album_photos)]
+pub struct NewAlbumPhoto {
+    pub album_id: Uuid,
+    pub photo_id: Uuid,
+    pub position: i32,
+}
+
+/// An album as the sidebar shows it, `cover_photo` is the chosen cover or else the first photo.
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Album {
+    pub id: Uuid,
+    pub name: String,
+    pub cover_photo: Option<Photo>,
+    pub photo_count: i64,
+}
+
+#[derive(Queryable, Selectable, Clone, Debug)]
+#[diesel(table_name = crate::schema::schema::tasks)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct QueuedTask {
+    pub id: Uuid,
+    pub kind: String,
+    pub directory_id: Option<Uuid>,
+    pub payload: serde_json::Value,
+    pub priority: i32,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::schema::tasks)]
+pub struct NewTask {
+    pub id: Uuid,
+    pub kind: String,
+    pub directory_id: Option<Uuid>,
+    pub payload: serde_json::Value,
+    pub priority: i32,
+}
+
 #[derive(Insertable, Serialize, Deserialize)]
 #[diesel(table_name = crate::schema::schema::photo_tags_mappings)]
 pub struct NewPhotoTagMapping {
     pub id: Uuid,
     pub tag: String,
     pub photo_id: Uuid,
+    pub source: TagSource,
 }
 
 #[derive(Insertable, Deserialize, Selectable)]
@@ -156,6 +549,19 @@ pub struct FaceEmbedding {
     pub photo_id: Uuid,
     pub embedding: Vector,
     pub cluster_id: Option<Uuid>,
+    pub region_x: Option<f32>,
+    pub region_y: Option<f32>,
+    pub region_width: Option<f32>,
+    pub region_height: Option<f32>,
+}
+
+/// Where a face is in its photo, normalized to the size of the photo from the top left corner.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FaceRegion {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
 }
 
 #[derive(Queryable, Selectable)]
@@ -179,3 +585,20 @@ pub struct NewCluster {
 pub struct FaceEmbeddingClusterUpdate {
     pub cluster_id: Option<Uuid>,
 }
+
+/// Progress of a stage run by the tagging service for a directory.
+#[derive(Queryable, Selectable, Clone, Debug)]
+#[diesel(table_name = crate::schema::schema::processing_progress)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct ProcessingProgress {
+    pub directory_id: Uuid,
+    pub stage: ProcessingStage,
+    pub done: i32,
+    pub total: i32,
+    pub errors: i32,
+    pub current_file: Option<String>,
+    pub last_error: Option<String>,
+    pub finished: bool,
+    pub started_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
@@ -1,5 +1,31 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    use diesel::sql_types::*;
+    use pgvector::sql_types::*;
+
+    album_photos (album_id, photo_id) {
+        album_id -> Uuid,
+        photo_id -> Uuid,
+        position -> Int4,
+        added_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use pgvector::sql_types::*;
+
+    albums (id) {
+        id -> Uuid,
+        #[max_length = 255]
+        name -> Varchar,
+        cover_photo_id -> Nullable<Uuid>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     use diesel::sql_types::*;
     use pgvector::sql_types::*;
@@ -19,11 +45,10 @@ diesel::table! {
         id -> Uuid,
         #[max_length = 512]
         path -> Varchar,
-        is_imported -> Bool,
         added_time -> Timestamp,
         photo_count -> Int4,
-        is_tagged -> Bool,
-        is_face_tagging_done -> Bool,
+        utc_offset_minutes -> Nullable<Int4>,
+        xmp_sync -> Bool,
     }
 }
 
@@ -82,6 +107,13 @@ diesel::table! {
         gps_processing_method -> Nullable<Text>,
         gps_date_stamp -> Nullable<Text>,
         created_at -> Nullable<Timestamptz>,
+        latitude -> Nullable<Float8>,
+        longitude -> Nullable<Float8>,
+        utc_offset_minutes -> Nullable<Int4>,
+        #[max_length = 16]
+        time_zone_source -> Nullable<Varchar>,
+        lens_make -> Nullable<Text>,
+        lens_model -> Nullable<Text>,
     }
 }
 
@@ -94,6 +126,39 @@ diesel::table! {
         photo_id -> Uuid,
         embedding -> Vector,
         cluster_id -> Nullable<Uuid>,
+        region_x -> Nullable<Float4>,
+        region_y -> Nullable<Float4>,
+        region_width -> Nullable<Float4>,
+        region_height -> Nullable<Float4>,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use pgvector::sql_types::*;
+
+    photo_places (photo_id) {
+        photo_id -> Uuid,
+        place_id -> Int4,
+        distance_meters -> Float8,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use pgvector::sql_types::*;
+
+    photo_processing (photo_id, stage) {
+        photo_id -> Uuid,
+        #[max_length = 32]
+        stage -> Varchar,
+        #[max_length = 16]
+        status -> Varchar,
+        attempts -> Int4,
+        error -> Nullable<Text>,
+        #[max_length = 255]
+        model_version -> Nullable<Varchar>,
+        updated_at -> Timestamp,
     }
 }
 
@@ -106,6 +171,8 @@ diesel::table! {
         #[max_length = 255]
         tag -> Varchar,
         photo_id -> Uuid,
+        #[max_length = 16]
+        source -> Varchar,
     }
 }
 
@@ -116,8 +183,71 @@ diesel::table! {
     photos (id) {
         id -> Uuid,
         path -> Uuid,
+        #[max_length = 1024]
+        name -> Varchar,
+        file_size -> Nullable<Int8>,
+        modified_time -> Nullable<Timestamp>,
+        is_missing -> Bool,
+        #[max_length = 64]
+        content_hash -> Nullable<Varchar>,
+        perceptual_hash -> Nullable<Int8>,
+        imported_at -> Timestamp,
+        rating -> Int2,
+        #[max_length = 16]
+        flag -> Nullable<Varchar>,
+        #[max_length = 16]
+        color_label -> Nullable<Varchar>,
+        caption -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use pgvector::sql_types::*;
+
+    places (id) {
+        id -> Int4,
+        name -> Text,
+        region -> Nullable<Text>,
+        #[max_length = 2]
+        country_code -> Varchar,
+        country -> Text,
+        latitude -> Float8,
+        longitude -> Float8,
+        population -> Int8,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use pgvector::sql_types::*;
+
+    processing_progress (directory_id, stage) {
+        directory_id -> Uuid,
+        #[max_length = 32]
+        stage -> Varchar,
+        done -> Int4,
+        total -> Int4,
+        errors -> Int4,
+        current_file -> Nullable<Text>,
+        last_error -> Nullable<Text>,
+        finished -> Bool,
+        started_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use pgvector::sql_types::*;
+
+    smart_albums (id) {
+        id -> Uuid,
         #[max_length = 255]
         name -> Varchar,
+        query -> Jsonb,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
     }
 }
 
@@ -132,17 +262,52 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    use diesel::sql_types::*;
+    use pgvector::sql_types::*;
+
+    tasks (id) {
+        id -> Uuid,
+        #[max_length = 64]
+        kind -> Varchar,
+        directory_id -> Nullable<Uuid>,
+        payload -> Jsonb,
+        priority -> Int4,
+        #[max_length = 16]
+        status -> Varchar,
+        error -> Nullable<Text>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::joinable!(album_photos -> albums (album_id));
+diesel::joinable!(album_photos -> photos (photo_id));
+diesel::joinable!(albums -> photos (cover_photo_id));
 diesel::joinable!(exif_metadata -> photos (photo_id));
 diesel::joinable!(face_embeddings -> photos (photo_id));
+diesel::joinable!(photo_places -> photos (photo_id));
+diesel::joinable!(photo_places -> places (place_id));
+diesel::joinable!(photo_processing -> photos (photo_id));
 diesel::joinable!(photo_tags_mappings -> photos (photo_id));
 diesel::joinable!(photos -> directories (path));
+diesel::joinable!(processing_progress -> directories (directory_id));
+diesel::joinable!(tasks -> directories (directory_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
+    album_photos,
+    albums,
     clusters,
     directories,
     exif_metadata,
     face_embeddings,
+    photo_places,
+    photo_processing,
     photo_tags_mappings,
     photos,
+    places,
+    processing_progress,
+    smart_albums,
     tags,
+    tasks,
 );
@@ -1,3 +1,11 @@
+use diesel::deserialize::{self, FromSql, FromSqlRow};
+use diesel::expression::AsExpression;
+use diesel::pg::{Pg, PgValue};
+use diesel::serialize::{self, IsNull, Output, ToSql};
+use diesel::sql_types::Text;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::io::Write;
 use uuid::Uuid;
 
 pub struct ProcessedPhoto {
@@ -6,3 +14,320 @@ pub struct ProcessedPhoto {
     pub name: String,
     pub has_preview: bool,
 }
+
+/// A step every imported photo goes through, tracked per photo in `photo_processing`.
+#[derive(AsExpression, FromSqlRow, Serialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[diesel(sql_type = Text)]
+#[serde(rename_all = "camelCase")]
+pub enum ProcessingStage {
+    Preview,
+    Tagging,
+    FaceEmbeddings,
+}
+
+impl ProcessingStage {
+    pub const ALL: [ProcessingStage; 3] = [
+        ProcessingStage::Preview,
+        ProcessingStage::Tagging,
+        ProcessingStage::FaceEmbeddings,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProcessingStage::Preview => "preview",
+            ProcessingStage::Tagging => "tagging",
+            ProcessingStage::FaceEmbeddings => "face_embeddings",
+        }
+    }
+}
+
+impl fmt::Display for ProcessingStage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl ToSql<Text, Pg> for ProcessingStage {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+        out.write_all(self.as_str().as_bytes())?;
+        Ok(IsNull::No)
+    }
+}
+
+impl FromSql<Text, Pg> for ProcessingStage {
+    fn from_sql(bytes: PgValue<'_>) -> deserialize::Result<Self> {
+        match bytes.as_bytes() {
+            b"preview" => Ok(ProcessingStage::Preview),
+            b"tagging" => Ok(ProcessingStage::Tagging),
+            b"face_embeddings" => Ok(ProcessingStage::FaceEmbeddings),
+            other => Err(format!(
+                "Unknown processing stage: {}",
+                String::from_utf8_lossy(other)
+            )
+            .into()),
+        }
+    }
+}
+
+/// Where a photo is in one of its processing stages.
+#[derive(AsExpression, FromSqlRow, Serialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[diesel(sql_type = Text)]
+#[serde(rename_all = "camelCase")]
+pub enum ProcessingStatus {
+    Pending,
+    Running,
+    Done,
+    Failed,
+}
+
+impl ProcessingStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProcessingStatus::Pending => "pending",
+            ProcessingStatus::Running => "running",
+            ProcessingStatus::Done => "done",
+            ProcessingStatus::Failed => "failed",
+        }
+    }
+}
+
+impl ToSql<Text, Pg> for ProcessingStatus {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+        out.write_all(self.as_str().as_bytes())?;
+        Ok(IsNull::No)
+    }
+}
+
+impl FromSql<Text, Pg> for ProcessingStatus {
+    fn from_sql(bytes: PgValue<'_>) -> deserialize::Result<Self> {
+        match bytes.as_bytes() {
+            b"pending" => Ok(ProcessingStatus::Pending),
+            b"running" => Ok(ProcessingStatus::Running),
+            b"done" => Ok(ProcessingStatus::Done),
+            b"failed" => Ok(ProcessingStatus::Failed),
+            other => Err(format!(
+                "Unknown processing status: {}",
+                String::from_utf8_lossy(other)
+            )
+            .into()),
+        }
+    }
+}
+
+/// Where a background task of the `tasks` table is. Finished tasks are deleted.
+#[derive(AsExpression, FromSqlRow, Serialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[diesel(sql_type = Text)]
+#[serde(rename_all = "camelCase")]
+pub enum TaskStatus {
+    Queued,
+    Running,
+    Failed,
+}
+
+impl TaskStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TaskStatus::Queued => "queued",
+            TaskStatus::Running => "running",
+            TaskStatus::Failed => "failed",
+        }
+    }
+}
+
+impl ToSql<Text, Pg> for TaskStatus {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+        out.write_all(self.as_str().as_bytes())?;
+        Ok(IsNull::No)
+    }
+}
+
+impl FromSql<Text, Pg> for TaskStatus {
+    fn from_sql(bytes: PgValue<'_>) -> deserialize::Result<Self> {
+        match bytes.as_bytes() {
+            b"queued" => Ok(TaskStatus::Queued),
+            b"running" => Ok(TaskStatus::Running),
+            b"failed" => Ok(TaskStatus::Failed),
+            other => Err(format!("Unknown task status: {}", String::from_utf8_lossy(other)).into()),
+        }
+    }
+}
+
+/// Where the UTC offset of a capture time comes from.
+#[derive(AsExpression, FromSqlRow, Serialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[diesel(sql_type = Text)]
+#[serde(rename_all = "camelCase")]
+pub enum TimeZoneSource {
+    /// The `OffsetTimeOriginal` tag recorded by the camera.
+    Exif,
+    /// The difference between the camera time and the UTC time of the GPS fix.
+    Gps,
+    /// The offset set on the directory of the photo.
+    Directory,
+}
+
+impl TimeZoneSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TimeZoneSource::Exif => "exif",
+            TimeZoneSource::Gps => "gps",
+            TimeZoneSource::Directory => "directory",
+        }
+    }
+}
+
+impl ToSql<Text, Pg> for TimeZoneSource {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+        out.write_all(self.as_str().as_bytes())?;
+        Ok(IsNull::No)
+    }
+}
+
+impl FromSql<Text, Pg> for TimeZoneSource {
+    fn from_sql(bytes: PgValue<'_>) -> deserialize::Result<Self> {
+        match bytes.as_bytes() {
+            b"exif" => Ok(TimeZoneSource::Exif),
+            b"gps" => Ok(TimeZoneSource::Gps),
+            b"directory" => Ok(TimeZoneSource::Directory),
+            other => Err(format!(
+                "Unknown time zone source: {}",
+                String::from_utf8_lossy(other)
+            )
+            .into()),
+        }
+    }
+}
+
+/// Whether a photo was kept or discarded while culling.
+#[derive(
+    AsExpression, FromSqlRow, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash,
+)]
+#[diesel(sql_type = Text)]
+#[serde(rename_all = "camelCase")]
+pub enum PhotoFlag {
+    Pick,
+    Reject,
+}
+
+impl PhotoFlag {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PhotoFlag::Pick => "pick",
+            PhotoFlag::Reject => "reject",
+        }
+    }
+}
+
+impl ToSql<Text, Pg> for PhotoFlag {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+        out.write_all(self.as_str().as_bytes())?;
+        Ok(IsNull::No)
+    }
+}
+
+impl FromSql<Text, Pg> for PhotoFlag {
+    fn from_sql(bytes: PgValue<'_>) -> deserialize::Result<Self> {
+        match bytes.as_bytes() {
+            b"pick" => Ok(PhotoFlag::Pick),
+            b"reject" => Ok(PhotoFlag::Reject),
+            other => Err(format!("Unknown photo flag: {}", String::from_utf8_lossy(other)).into()),
+        }
+    }
+}
+
+/// The color labels of Lightroom and Bridge, their meaning is up to the user.
+#[derive(
+    AsExpression, FromSqlRow, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash,
+)]
+#[diesel(sql_type = Text)]
+#[serde(rename_all = "camelCase")]
+pub enum ColorLabel {
+    Red,
+    Yellow,
+    Green,
+    Blue,
+    Purple,
+}
+
+impl ColorLabel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ColorLabel::Red => "red",
+            ColorLabel::Yellow => "yellow",
+            ColorLabel::Green => "green",
+            ColorLabel::Blue => "blue",
+            ColorLabel::Purple => "purple",
+        }
+    }
+}
+
+impl ToSql<Text, Pg> for ColorLabel {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+        out.write_all(self.as_str().as_bytes())?;
+        Ok(IsNull::No)
+    }
+}
+
+impl FromSql<Text, Pg> for ColorLabel {
+    fn from_sql(bytes: PgValue<'_>) -> deserialize::Result<Self> {
+        match bytes.as_bytes() {
+            b"red" => Ok(ColorLabel::Red),
+            b"yellow" => Ok(ColorLabel::Yellow),
+            b"green" => Ok(ColorLabel::Green),
+            b"blue" => Ok(ColorLabel::Blue),
+            b"purple" => Ok(ColorLabel::Purple),
+            other => Err(format!("Unknown color label: {}", String::from_utf8_lossy(other)).into()),
+        }
+    }
+}
+
+/// Where the tag of a photo comes from. Only the detected ones are dropped when a photo is
+/// tagged again.
+#[derive(
+    AsExpression, FromSqlRow, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash,
+)]
+#[diesel(sql_type = Text)]
+#[serde(rename_all = "camelCase")]
+pub enum TagSource {
+    /// An object found by the detection model.
+    Detection,
+    /// A keyword of the XMP sidecar of the photo.
+    Sidecar,
+    /// A person named in a face region of the XMP sidecar of the photo.
+    Face,
+    /// An IPTC or XMP keyword stored in the photo file itself.
+    Embedded,
+    /// Added in the catalog.
+    Manual,
+}
+
+impl TagSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TagSource::Detection => "detection",
+            TagSource::Sidecar => "sidecar",
+            TagSource::Face => "face",
+            TagSource::Embedded => "embedded",
+            TagSource::Manual => "manual",
+        }
+    }
+}
+
+impl ToSql<Text, Pg> for TagSource {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+        out.write_all(self.as_str().as_bytes())?;
+        Ok(IsNull::No)
+    }
+}
+
+impl FromSql<Text, Pg> for TagSource {
+    fn from_sql(bytes: PgValue<'_>) -> deserialize::Result<Self> {
+        match bytes.as_bytes() {
+            b"detection" => Ok(TagSource::Detection),
+            b"sidecar" => Ok(TagSource::Sidecar),
+            b"face" => Ok(TagSource::Face),
+            b"embedded" => Ok(TagSource::Embedded),
+            b"manual" => Ok(TagSource::Manual),
+            other => Err(format!("Unknown tag source: {}", String::from_utf8_lossy(other)).into()),
+        }
+    }
+}
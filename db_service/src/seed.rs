@@ -1,10 +1,21 @@
 use crate::db::DbPoolConn;
-use crate::schema::Tag;
-use anyhow::Result;
+use crate::schema::schema::places;
+use crate::schema::{Place, Tag};
+use anyhow::{Context, Result};
 use diesel::prelude::*;
 use serde::Deserialize;
 use std::collections::HashMap;
 use std::fs;
+use std::path::Path;
+
+/// Environment variable pointing to the GeoNames cities dump places are loaded from.
+pub const GEONAMES_CITIES_VAR: &str = "GEONAMES_CITIES";
+
+/// Used when the variable is unset, relative to the working directory of the app.
+pub const DEFAULT_GEONAMES_CITIES: &str = "geonames/cities15000.txt";
+
+/// Rows per insert, `places` has 8 columns and Postgres accepts up to 65535 parameters.
+const PLACES_BATCH_SIZE: usize = 5000;
 
 #[derive(Deserialize)]
 pub struct DatasetYaml {
@@ -35,3 +46,92 @@ pub fn insert_tags_from_yaml(conn: &mut DbPoolConn, yaml_path: &str) -> Result<(
 
     Ok(())
 }
+
+/// Loads the cities of a GeoNames dump (`cities15000.txt` and alike) into `places`. Region and
+/// country names are read from `admin1CodesASCII.txt` and `countryInfo.txt` when they are next
+/// to it, the codes are used otherwise.
+pub fn insert_places_from_geonames(conn: &mut DbPoolConn, cities_path: &Path) -> Result<usize> {
+    let count: i64 = places::table.count().get_result(conn)?;
+    if count > 0 {
+        tracing::info!("Places table already populated. Skipping...");
+        return Ok(0);
+    }
+
+    let directory = cities_path.parent().unwrap_or(Path::new("."));
+    let regions = read_geonames_names(&directory.join("admin1CodesASCII.txt"), 1);
+    let countries = read_geonames_names(&directory.join("countryInfo.txt"), 4);
+
+    let cities = fs::read_to_string(cities_path)
+        .with_context(|| format!("Cannot read {:?}", cities_path))?;
+
+    let mut entries: Vec<Place> = Vec::new();
+    for line in cities.lines() {
+        let columns: Vec<&str> = line.split('\t').collect();
+        if columns.len() < 15 {
+            continue;
+        }
+
+        let (Ok(id), Ok(latitude), Ok(longitude)) = (
+            columns[0].parse::<i32>(),
+            columns[4].parse::<f64>(),
+            columns[5].parse::<f64>(),
+        ) else {
+            tracing::warn!("Skipping malformed GeoNames line: {}", line);
+            continue;
+        };
+
+        let country_code = columns[8].to_string();
+        let region_code = format!("{}.{}", country_code, columns[10]);
+
+        entries.push(Place {
+            id,
+            name: columns[1].to_string(),
+            region: regions
+                .get(&region_code)
+                .cloned()
+                .or_else(|| Some(columns[10].to_string()).filter(|code| !code.is_empty())),
+            country: countries
+                .get(&country_code)
+                .cloned()
+                .unwrap_or_else(|| country_code.clone()),
+            country_code,
+            latitude,
+            longitude,
+            population: columns[14].parse().unwrap_or_default(),
+        });
+    }
+
+    conn.transaction::<_, anyhow::Error, _>(|conn| {
+        for batch in entries.chunks(PLACES_BATCH_SIZE) {
+            diesel::insert_into(places::table)
+                .values(batch)
+                .on_conflict_do_nothing()
+                .execute(conn)?;
+        }
+        Ok(())
+    })?;
+
+    tracing::info!("Inserted {} places from {:?}", entries.len(), cities_path);
+
+    Ok(entries.len())
+}
+
+/// Code to name lookup of a GeoNames side file, an empty map when the file is missing.
+fn read_geonames_names(path: &Path, name_column: usize) -> HashMap<String, String> {
+    let Ok(content) = fs::read_to_string(path) else {
+        tracing::debug!("No {:?}, places keep their codes", path);
+        return HashMap::new();
+    };
+
+    content
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| {
+            let columns: Vec<&str> = line.split('\t').collect();
+            Some((
+                columns.first()?.to_string(),
+                columns.get(name_column)?.to_string(),
+            ))
+        })
+        .collect()
+}
@@ -0,0 +1,303 @@
+use crate::db::{DbPoolConn, insert_batch_size};
+use crate::schema::schema::{album_photos, albums, photos};
+use crate::schema::{Album, AlbumRow, NewAlbumPhoto, Photo};
+use anyhow::{Context, Result, bail};
+use chrono::Utc;
+use diesel::prelude::*;
+use diesel::sql_types::{Array, Uuid as SqlUuid};
+use std::collections::HashSet;
+use uuid::Uuid;
+
+/// Longest name the `albums` table accepts.
+const MAX_NAME_LENGTH: usize = 255;
+
+/// Rows of the 3 columns of a [`NewAlbumPhoto`] inserted at once.
+const ALBUM_PHOTO_BATCH_SIZE: usize = insert_batch_size(3);
+
+fn validate_name(name: &str) -> Result<&str> {
+    let name = name.trim();
+    if name.is_empty() {
+        bail!("An album needs a name");
+    }
+    if name.chars().count() > MAX_NAME_LENGTH {
+        bail!("Album names are limited to {} characters", MAX_NAME_LENGTH);
+    }
+
+    Ok(name)
+}
+
+/// Marks an album as changed, failing when it doesn't exist.
+fn touch(conn: &mut DbPoolConn, album_id: &Uuid) -> Result<AlbumRow> {
+    diesel::update(albums::table.find(album_id))
+        .set(albums::updated_at.eq(Utc::now().naive_utc()))
+        .returning(AlbumRow::as_returning())
+        .get_result(conn)
+        .optional()?
+        .with_context(|| format!("No album with id {}", album_id))
+}
+
+/// The photo ids of an album, in their order.
+fn album_order(conn: &mut DbPoolConn, album_id: &Uuid) -> QueryResult<Vec<Uuid>> {
+    album_photos::table
+        .filter(album_photos::album_id.eq(album_id))
+        .order(album_photos::position)
+        .select(album_photos::photo_id)
+        .load(conn)
+}
+
+/// Numbers the photos of an album from 0 in the order given, in a single statement.
+fn write_order(conn: &mut DbPoolConn, album_id: &Uuid, order: &[Uuid]) -> QueryResult<usize> {
+    diesel::sql_query(
+        "UPDATE album_photos a \
+         SET position = o.position - 1 \
+         FROM unnest($2) WITH ORDINALITY AS o (photo_id, position) \
+         WHERE a.album_id = $1 AND a.photo_id = o.photo_id",
+    )
+    .bind::<SqlUuid, _>(album_id)
+    .bind::<Array<SqlUuid>, _>(order)
+    .execute(conn)
+}
+
+fn with_details(conn: &mut DbPoolConn, row: AlbumRow) -> Result<Album> {
+    let album_photos = album_photos::table
+        .inner_join(photos::table)
+        .filter(album_photos::album_id.eq(row.id))
+        .filter(photos::is_missing.eq(false));
+
+    let photo_count = album_photos.count().get_result(conn)?;
+    let cover_photo = match row.cover_photo_id {
+        Some(cover_photo_id) => album_photos
+            .filter(photos::id.eq(cover_photo_id))
+            .select(Photo::as_select())
+            .first(conn)
+            .optional()?,
+        None => None,
+    };
+    let cover_photo = match cover_photo {
+        Some(photo) => Some(photo),
+        None => album_photos
+            .order(album_photos::position)
+            .select(Photo::as_select())
+            .first(conn)
+            .optional()?,
+    };
+
+    Ok(Album {
+        id: row.id,
+        name: row.name,
+        cover_photo,
+        photo_count,
+    })
+}
+
+pub fn create_album(conn: &mut DbPoolConn, name: &str) -> Result<Album> {
+    let now = Utc::now().naive_utc();
+    let row = diesel::insert_into(albums::table)
+        .values(&AlbumRow {
+            id: Uuid::new_v4(),
+            name: validate_name(name)?.to_string(),
+            cover_photo_id: None,
+            created_at: now,
+            updated_at: now,
+        })
+        .returning(AlbumRow::as_returning())
+        .get_result(conn)?;
+
+    with_details(conn, row)
+}
+
+pub fn rename_album(conn: &mut DbPoolConn, album_id: &Uuid, name: &str) -> Result<Album> {
+    let row = diesel::update(albums::table.find(album_id))
+        .set((
+            albums::name.eq(validate_name(name)?),
+            albums::updated_at.eq(Utc::now().naive_utc()),
+        ))
+        .returning(AlbumRow::as_returning())
+        .get_result(conn)
+        .optional()?
+        .with_context(|| format!("No album with id {}", album_id))?;
+
+    with_details(conn, row)
+}
+
+/// Deletes the album only, its photos stay in the library.
+pub fn delete_album(conn: &mut DbPoolConn, album_id: &Uuid) -> Result<usize> {
+    let deleted = diesel::delete(albums::table.find(album_id)).execute(conn)?;
+
+    Ok(deleted)
+}
+
+/// Every album by name, with its cover and number of photos.
+pub fn get_albums(conn: &mut DbPoolConn) -> Result<Vec<Album>> {
+    let rows = albums::table
+        .select(AlbumRow::as_select())
+        .order(albums::name)
+        .load(conn)?;
+
+    rows.into_iter()
+        .map(|row| with_details(conn, row))
+        .collect()
+}
+
+/// The photos of an album in their order. Photos missing from disk keep their place, they are
+/// back once a rescan finds them.
+pub fn get_album_photos(conn: &mut DbPoolConn, album_id: &Uuid) -> Result<Vec<Photo>> {
+    let photos = album_photos::table
+        .inner_join(photos::table)
+        .filter(album_photos::album_id.eq(album_id))
+        .filter(photos::is_missing.eq(false))
+        .order(album_photos::position)
+        .select(Photo::as_select())
+        .load(conn)?;
+
+    Ok(photos)
+}
+
+/// Appends photos to the end of an album in the order given, photos already in it are skipped.
+/// Returns the number of photos added.
+pub fn add_photos_to_album(
+    conn: &mut DbPoolConn,
+    album_id: &Uuid,
+    photo_ids: &[Uuid],
+) -> Result<usize> {
+    conn.transaction::<_, anyhow::Error, _>(|conn| {
+        touch(conn, album_id)?;
+
+        let mut present: HashSet<Uuid> = album_order(conn, album_id)?.into_iter().collect();
+        let first_position = present.len() as i32;
+        let new_photos: Vec<NewAlbumPhoto> = photo_ids
+            .iter()
+            .filter(|photo_id| present.insert(**photo_id))
+            .enumerate()
+            .map(|(i, photo_id)| NewAlbumPhoto {
+                album_id: *album_id,
+                photo_id: *photo_id,
+                position: first_position + i as i32,
+            })
+            .collect();
+
+        let mut added = 0;
+        for batch in new_photos.chunks(ALBUM_PHOTO_BATCH_SIZE) {
+            added += diesel::insert_into(album_photos::table)
+                .values(batch)
+                .execute(conn)?;
+        }
+
+        Ok(added)
+    })
+}
+
+/// Removes photos from an album, the others close the gaps. The cover is reset when it is one
+/// of them.
+pub fn remove_photos_from_album(
+    conn: &mut DbPoolConn,
+    album_id: &Uuid,
+    photo_ids: &[Uuid],
+) -> Result<usize> {
+    conn.transaction::<_, anyhow::Error, _>(|conn| {
+        let album = touch(conn, album_id)?;
+
+        let removed = diesel::delete(
+            album_photos::table
+                .filter(album_photos::album_id.eq(album_id))
+                .filter(album_photos::photo_id.eq_any(photo_ids)),
+        )
+        .execute(conn)?;
+
+        let order = album_order(conn, album_id)?;
+        write_order(conn, album_id, &order)?;
+
+        if album
+            .cover_photo_id
+            .is_some_and(|cover_photo_id| photo_ids.contains(&cover_photo_id))
+        {
+            diesel::update(albums::table.find(album_id))
+                .set(albums::cover_photo_id.eq(None::<Uuid>))
+                .execute(conn)?;
+        }
+
+        Ok(removed)
+    })
+}
+
+/// Moves photos of an album right before `before`, or to the end when it is `None`. The moved
+/// photos keep the order they are given in, which can be used to reorder a whole album.
+pub fn move_album_photos(
+    conn: &mut DbPoolConn,
+    album_id: &Uuid,
+    photo_ids: &[Uuid],
+    before: Option<&Uuid>,
+) -> Result<()> {
+    if before.is_some_and(|before| photo_ids.contains(before)) {
+        bail!("Photos can't be moved before one of themselves");
+    }
+
+    conn.transaction::<_, anyhow::Error, _>(|conn| {
+        touch(conn, album_id)?;
+
+        let order = album_order(conn, album_id)?;
+        let present: HashSet<&Uuid> = order.iter().collect();
+        if let Some(photo_id) = photo_ids
+            .iter()
+            .find(|photo_id| !present.contains(photo_id))
+        {
+            bail!("Photo {} is not in the album", photo_id);
+        }
+
+        let moved: HashSet<&Uuid> = photo_ids.iter().collect();
+        let mut new_order: Vec<Uuid> = order
+            .iter()
+            .filter(|photo_id| !moved.contains(photo_id))
+            .copied()
+            .collect();
+        let index = match before {
+            Some(before) => new_order
+                .iter()
+                .position(|photo_id| photo_id == before)
+                .with_context(|| format!("Photo {} is not in the album", before))?,
+            None => new_order.len(),
+        };
+
+        let mut seen = HashSet::new();
+        new_order.splice(
+            index..index,
+            photo_ids
+                .iter()
+                .filter(|photo_id| seen.insert(**photo_id))
+                .copied(),
+        );
+
+        write_order(conn, album_id, &new_order)?;
+
+        Ok(())
+    })
+}
+
+/// Picks the cover of an album among its photos, `None` goes back to the first photo.
+pub fn set_album_cover(
+    conn: &mut DbPoolConn,
+    album_id: &Uuid,
+    photo_id: Option<&Uuid>,
+) -> Result<Album> {
+    if let Some(photo_id) = photo_id {
+        let in_album = diesel::select(diesel::dsl::exists(
+            album_photos::table.find((album_id, photo_id)),
+        ))
+        .get_result::<bool>(conn)?;
+        if !in_album {
+            bail!("Photo {} is not in the album", photo_id);
+        }
+    }
+
+    let row = diesel::update(albums::table.find(album_id))
+        .set((
+            albums::cover_photo_id.eq(photo_id),
+            albums::updated_at.eq(Utc::now().naive_utc()),
+        ))
+        .returning(AlbumRow::as_returning())
+        .get_result(conn)
+        .optional()?
+        .with_context(|| format!("No album with id {}", album_id))?;
+
+    with_details(conn, row)
+}
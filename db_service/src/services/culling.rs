@@ -0,0 +1,59 @@
+use crate::db::DbPoolConn;
+use crate::schema::PhotoCulling;
+use crate::schema::schema::photos;
+use crate::schema::types::{ColorLabel, PhotoFlag};
+use anyhow::{Result, bail};
+use diesel::prelude::*;
+use uuid::Uuid;
+
+/// The most stars a photo can have.
+pub const MAX_RATING: i16 = 5;
+
+/// Rates every photo at once, 0 removes the rating. Returns the number of photos updated.
+pub fn set_rating(conn: &mut DbPoolConn, photo_ids: &[Uuid], rating: i16) -> Result<usize> {
+    if !(0..=MAX_RATING).contains(&rating) {
+        bail!("Ratings go from 0 to {} stars, not {}", MAX_RATING, rating);
+    }
+
+    let updated = diesel::update(photos::table.filter(photos::id.eq_any(photo_ids)))
+        .set(photos::rating.eq(rating))
+        .execute(conn)?;
+
+    Ok(updated)
+}
+
+/// Picks or rejects every photo at once, `None` clears the flag.
+pub fn set_flag(
+    conn: &mut DbPoolConn,
+    photo_ids: &[Uuid],
+    flag: Option<PhotoFlag>,
+) -> Result<usize> {
+    let updated = diesel::update(photos::table.filter(photos::id.eq_any(photo_ids)))
+        .set(photos::flag.eq(flag))
+        .execute(conn)?;
+
+    Ok(updated)
+}
+
+/// Labels every photo at once, `None` clears the label.
+pub fn set_color_label(
+    conn: &mut DbPoolConn,
+    photo_ids: &[Uuid],
+    color_label: Option<ColorLabel>,
+) -> Result<usize> {
+    let updated = diesel::update(photos::table.filter(photos::id.eq_any(photo_ids)))
+        .set(photos::color_label.eq(color_label))
+        .execute(conn)?;
+
+    Ok(updated)
+}
+
+/// The culling state of the photos, in no particular order. Unknown ids are left out.
+pub fn get_photo_culling(conn: &mut DbPoolConn, photo_ids: &[Uuid]) -> Result<Vec<PhotoCulling>> {
+    let culling = photos::table
+        .filter(photos::id.eq_any(photo_ids))
+        .select(PhotoCulling::as_select())
+        .load(conn)?;
+
+    Ok(culling)
+}
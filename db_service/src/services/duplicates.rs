@@ -0,0 +1,179 @@
+use crate::db::DbPoolConn;
+use crate::schema::schema::photos::dsl::photos as photos_dsl;
+use crate::schema::schema::{directories, photos};
+use crate::schema::{Directory, DuplicateGroup, DuplicatePhoto, DuplicateReport};
+use crate::services::photo::update_photo_count_by_id;
+use anyhow::{Result, anyhow};
+use diesel::dsl::{count_star, exists, select};
+use diesel::prelude::*;
+use rayon::prelude::*;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashSet};
+use std::fs::File;
+use std::io;
+use std::path::Path;
+use uuid::Uuid;
+
+/// Hex encoded SHA-256 of the file contents, streamed so big files don't end up in memory.
+pub fn hash_file(file_path: &Path) -> io::Result<String> {
+    let mut file = File::open(file_path)?;
+    let mut hasher = Sha256::new();
+    io::copy(&mut file, &mut hasher)?;
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// The directories holding photos imported before content hashes were stored.
+pub fn get_directories_with_unhashed_photos(conn: &mut DbPoolConn) -> Result<Vec<Directory>> {
+    let directories: Vec<Directory> = directories::table
+        .filter(exists(
+            photos_dsl
+                .filter(photos::path.eq(directories::id))
+                .filter(photos::content_hash.is_null())
+                .filter(photos::is_missing.eq(false)),
+        ))
+        .select(Directory::as_select())
+        .load(conn)?;
+
+    Ok(directories)
+}
+
+/// Computes the content hash of the photos of a directory imported before hashes were stored.
+pub fn hash_unhashed_photos(conn: &mut DbPoolConn, dir_id: &Uuid) -> Result<usize> {
+    let pending: Vec<(Uuid, String, String)> = photos_dsl
+        .inner_join(directories::table)
+        .filter(photos::path.eq(dir_id))
+        .filter(photos::content_hash.is_null())
+        .filter(photos::is_missing.eq(false))
+        .select((photos::id, directories::path, photos::name))
+        .load(conn)?;
+
+    let hashes: Vec<(Uuid, String)> = pending
+        .par_iter()
+        .filter_map(|(photo_id, dir_path, name)| {
+            let file_path = Path::new(dir_path).join(name);
+            match hash_file(&file_path) {
+                Ok(hash) => Some((*photo_id, hash)),
+                Err(e) => {
+                    tracing::error!("Failed to hash {:?}: {}", file_path, e);
+                    None
+                }
+            }
+        })
+        .collect();
+
+    conn.transaction::<_, anyhow::Error, _>(|conn| {
+        for (photo_id, hash) in &hashes {
+            diesel::update(photos_dsl.filter(photos::id.eq(photo_id)))
+                .set(photos::content_hash.eq(hash))
+                .execute(conn)?;
+        }
+        Ok(())
+    })?;
+
+    tracing::info!("Computed the content hash of {} photos", hashes.len());
+    Ok(hashes.len())
+}
+
+/// Groups the byte-identical photos across all the directories of the catalog. The report is
+/// incomplete while some photos still wait for their content hash.
+pub fn get_duplicate_groups(conn: &mut DbPoolConn) -> Result<DuplicateReport> {
+    let duplicated_hashes: Vec<Option<String>> = photos_dsl
+        .filter(photos::is_missing.eq(false))
+        .filter(photos::content_hash.is_not_null())
+        .group_by(photos::content_hash)
+        .having(count_star().gt(1))
+        .select(photos::content_hash)
+        .load(conn)?;
+
+    let copies: Vec<DuplicatePhoto> = photos_dsl
+        .inner_join(directories::table)
+        .filter(photos::is_missing.eq(false))
+        .filter(photos::content_hash.eq_any(&duplicated_hashes))
+        .order((directories::path, photos::name))
+        .select(DuplicatePhoto::as_select())
+        .load(conn)?;
+
+    let mut groups: BTreeMap<String, Vec<DuplicatePhoto>> = BTreeMap::new();
+    for copy in copies {
+        if let Some(hash) = copy.content_hash.clone() {
+            groups.entry(hash).or_default().push(copy);
+        }
+    }
+
+    let groups = groups
+        .into_iter()
+        .map(|(content_hash, photos)| DuplicateGroup {
+            content_hash,
+            photos,
+        })
+        .collect();
+
+    let incomplete = select(exists(
+        photos_dsl
+            .filter(photos::content_hash.is_null())
+            .filter(photos::is_missing.eq(false)),
+    ))
+    .get_result(conn)?;
+
+    Ok(DuplicateReport { groups, incomplete })
+}
+
+/// Validates that every discarded photo is a byte-identical copy of the kept one and returns
+/// the copies that should be discarded.
+pub fn get_discardable_copies(
+    conn: &mut DbPoolConn,
+    keep_id: &Uuid,
+    discard_ids: &[Uuid],
+) -> Result<Vec<DuplicatePhoto>> {
+    if discard_ids.contains(keep_id) {
+        return Err(anyhow!(
+            "Photo {} can't be both kept and discarded",
+            keep_id
+        ));
+    }
+
+    let keep_hash: Option<String> = photos_dsl
+        .filter(photos::id.eq(keep_id))
+        .select(photos::content_hash)
+        .first(conn)?;
+    let keep_hash = keep_hash.ok_or_else(|| anyhow!("Photo {} has no content hash", keep_id))?;
+
+    let copies: Vec<DuplicatePhoto> = photos_dsl
+        .inner_join(directories::table)
+        .filter(photos::id.eq_any(discard_ids))
+        .select(DuplicatePhoto::as_select())
+        .load(conn)?;
+
+    let found: HashSet<Uuid> = copies.iter().map(|copy| copy.id).collect();
+    if let Some(unknown) = discard_ids.iter().find(|id| !found.contains(id)) {
+        return Err(anyhow!("No photo found with id: {}", unknown));
+    }
+
+    if let Some(copy) = copies
+        .iter()
+        .find(|copy| copy.content_hash.as_deref() != Some(keep_hash.as_str()))
+    {
+        return Err(anyhow!(
+            "{} is not a copy of the photo {}",
+            copy.name,
+            keep_id
+        ));
+    }
+
+    Ok(copies)
+}
+
+/// Removes the discarded copies from the catalog once their files are gone.
+pub fn delete_discarded_copies(conn: &mut DbPoolConn, copies: &[DuplicatePhoto]) -> Result<()> {
+    let ids: Vec<Uuid> = copies.iter().map(|copy| copy.id).collect();
+
+    diesel::delete(photos_dsl.filter(photos::id.eq_any(&ids))).execute(conn)?;
+
+    let dir_ids: HashSet<Uuid> = copies.iter().map(|copy| copy.directory_id).collect();
+    for dir_id in dir_ids {
+        update_photo_count_by_id(conn, &dir_id)?;
+    }
+
+    Ok(())
+}
@@ -1,8 +1,8 @@
-use crate::db::DbPoolConn;
+use crate::db::{DbPoolConn, insert_batch_size};
 use crate::schema::schema::face_embeddings;
 use crate::schema::schema::face_embeddings::dsl::face_embeddings as face_dsl;
 use crate::schema::{
-    FaceEmbedding, FaceEmbeddingClusterUpdate, FaceEmbeddingVec, NewCluster, Photo,
+    FaceEmbedding, FaceEmbeddingClusterUpdate, FaceEmbeddingVec, FaceRegion, NewCluster, Photo,
 };
 use anyhow::Result;
 use diesel::*;
@@ -10,30 +10,42 @@ use pgvector::Vector;
 use std::collections::{HashMap, HashSet};
 use uuid::Uuid;
 
-pub fn add_embeddings(
-    conn: &mut DbPoolConn,
-    embeddings: Vec<(&Photo, Vec<Uuid>, Vec<Vec<f32>>)>,
-) -> Result<()> {
+/// Rows of the 8 columns of a [`FaceEmbedding`] inserted at once.
+const EMBEDDING_BATCH_SIZE: usize = insert_batch_size(8);
+/// Rows of the 2 columns of a [`NewCluster`] inserted at once.
+const CLUSTER_BATCH_SIZE: usize = insert_batch_size(2);
+
+/// A photo together with the ids of the faces cropped from it, where they are and their
+/// embeddings.
+pub type PhotoFaceEmbeddings<'a> = (&'a Photo, Vec<Uuid>, Vec<FaceRegion>, Vec<Vec<f32>>);
+
+pub fn add_embeddings(conn: &mut DbPoolConn, embeddings: Vec<PhotoFaceEmbeddings>) -> Result<()> {
     // Map the incoming embeddings to our insertable struct
     let new_embeddings: Vec<FaceEmbedding> = embeddings
         .into_iter()
-        .flat_map(|(photo, ids, emb_list)| {
+        .flat_map(|(photo, ids, regions, emb_list)| {
             emb_list
                 .into_iter()
-                .zip(ids.into_iter())
-                .map(move |(emb, id)| FaceEmbedding {
+                .zip(ids)
+                .zip(regions)
+                .map(move |((emb, id), region)| FaceEmbedding {
                     id,
                     photo_id: photo.id,
                     embedding: Vector::from(emb),
                     cluster_id: None,
+                    region_x: Some(region.x),
+                    region_y: Some(region.y),
+                    region_width: Some(region.width),
+                    region_height: Some(region.height),
                 })
         })
         .collect();
 
-    // Insert all rows into the face_embeddings table
-    insert_into(face_embeddings::table)
-        .values(&new_embeddings)
-        .execute(conn)?;
+    for batch in new_embeddings.chunks(EMBEDDING_BATCH_SIZE) {
+        insert_into(face_embeddings::table)
+            .values(batch)
+            .execute(conn)?;
+    }
 
     Ok(())
 }
@@ -83,14 +95,15 @@ pub fn assign_clusters(
         });
     }
 
-    // Batch insert all new clusters at once.
-    insert_into(cl_dsl::clusters)
-        .values(&new_clusters)
-        .execute(conn)?;
+    // Batch insert the new clusters.
+    for batch in new_clusters.chunks(CLUSTER_BATCH_SIZE) {
+        insert_into(cl_dsl::clusters).values(batch).execute(conn)?;
+    }
 
     // Now update each face embedding with the corresponding cluster UUID.
     // Note: we assume that `embeddings` and `cluster_labels` are in the same order.
     for (face, label_option) in embeddings.into_iter().zip(cluster_labels.iter()) {
+        #[allow(clippy::manual_map)]
         let update_value = match label_option {
             // Map the DBSCAN label to the corresponding cluster UUID.
             Some(label) => Some(*cluster_id_mapping.get(label).expect("Cluster should exist")),
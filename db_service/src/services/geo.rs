@@ -0,0 +1,171 @@
+use crate::db::DbPoolConn;
+use crate::schema::schema::{exif_metadata, photos};
+use crate::schema::{GeoBounds, GeoPhoto, PhotoCluster};
+use anyhow::{Result, bail};
+use diesel::prelude::*;
+use diesel::sql_types::{Double, Integer};
+
+/// Mean radius of the Earth, used for great-circle distances.
+const EARTH_RADIUS_METERS: f64 = 6_371_008.8;
+
+/// Clusters are computed on cells of a quarter of a 256 pixel map tile.
+const CELLS_PER_TILE: i32 = 4;
+
+/// The zoom level of the most detailed tiles map views usually serve.
+pub const MAX_ZOOM: u8 = 22;
+
+/// Photos inside the given area, most recent first.
+pub fn get_photos_in_bounds(conn: &mut DbPoolConn, bounds: &GeoBounds) -> Result<Vec<GeoPhoto>> {
+    validate_bounds(bounds)?;
+
+    let mut query = photos::table
+        .inner_join(exif_metadata::table)
+        .filter(photos::is_missing.eq(false))
+        .filter(exif_metadata::latitude.between(bounds.south, bounds.north))
+        .order(exif_metadata::date_time_original.desc().nulls_last())
+        .select(GeoPhoto::as_select())
+        .into_boxed();
+
+    query = if bounds.west <= bounds.east {
+        query.filter(exif_metadata::longitude.between(bounds.west, bounds.east))
+    } else {
+        query.filter(
+            exif_metadata::longitude
+                .ge(bounds.west)
+                .or(exif_metadata::longitude.le(bounds.east)),
+        )
+    };
+
+    Ok(query.load(conn)?)
+}
+
+/// Photos taken within `radius_meters` of a point, nearest first.
+pub fn get_photos_within_radius(
+    conn: &mut DbPoolConn,
+    latitude: f64,
+    longitude: f64,
+    radius_meters: f64,
+) -> Result<Vec<GeoPhoto>> {
+    if !(radius_meters.is_finite() && radius_meters >= 0.0) {
+        bail!("Invalid radius: {}", radius_meters);
+    }
+
+    // The bounding box of the circle narrows the query down to the index, the exact distance is
+    // checked afterwards
+    let latitude_delta = (radius_meters / EARTH_RADIUS_METERS).to_degrees();
+    let north = latitude + latitude_delta;
+    let south = latitude - latitude_delta;
+    let longitude_delta = latitude_delta / latitude.to_radians().cos();
+
+    let bounds = if north >= 90.0 || south <= -90.0 || longitude_delta >= 180.0 {
+        GeoBounds {
+            north: north.min(90.0),
+            south: south.max(-90.0),
+            east: 180.0,
+            west: -180.0,
+        }
+    } else {
+        GeoBounds {
+            north,
+            south,
+            east: wrap_longitude(longitude + longitude_delta),
+            west: wrap_longitude(longitude - longitude_delta),
+        }
+    };
+
+    let mut nearby: Vec<(f64, GeoPhoto)> = get_photos_in_bounds(conn, &bounds)?
+        .into_iter()
+        .map(|photo| {
+            let distance = distance_meters(latitude, longitude, photo.latitude, photo.longitude);
+            (distance, photo)
+        })
+        .filter(|(distance, _)| *distance <= radius_meters)
+        .collect();
+
+    nearby.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+
+    Ok(nearby.into_iter().map(|(_, photo)| photo).collect())
+}
+
+/// Groups the photos inside the given area by cells of the Web Mercator grid of a zoom level,
+/// so a map view can draw one marker per cluster instead of one per photo.
+pub fn get_photo_clusters(
+    conn: &mut DbPoolConn,
+    bounds: &GeoBounds,
+    zoom: u8,
+) -> Result<Vec<PhotoCluster>> {
+    validate_bounds(bounds)?;
+
+    let cells = 2i32.pow(zoom.min(MAX_ZOOM) as u32) * CELLS_PER_TILE;
+
+    // Latitudes past ~85.05 degrees are outside of the Web Mercator projection, they fall in
+    // the first or last row
+    let clusters = diesel::sql_query(
+        "SELECT avg(e.latitude) AS latitude, \
+                avg(e.longitude) AS longitude, \
+                count(*) AS photo_count, \
+                (array_agg(e.photo_id ORDER BY e.date_time_original DESC NULLS LAST))[1] AS photo_id, \
+                max(e.latitude) AS north, \
+                min(e.latitude) AS south, \
+                max(e.longitude) AS east, \
+                min(e.longitude) AS west \
+         FROM exif_metadata e \
+         JOIN photos p ON p.id = e.photo_id \
+         WHERE NOT p.is_missing \
+           AND e.latitude BETWEEN $1 AND $2 \
+           AND CASE WHEN $3 <= $4 THEN e.longitude BETWEEN $3 AND $4 \
+                    ELSE e.longitude >= $3 OR e.longitude <= $4 END \
+         GROUP BY least(floor((e.longitude + 180) / 360 * $5), $5 - 1), \
+                  floor((1 - ln(tan(radians(least(greatest(e.latitude, -85.0511), 85.0511))) \
+                      + 1 / cos(radians(least(greatest(e.latitude, -85.0511), 85.0511)))) / pi()) \
+                      / 2 * $5) \
+         ORDER BY photo_count DESC",
+    )
+    .bind::<Double, _>(bounds.south)
+    .bind::<Double, _>(bounds.north)
+    .bind::<Double, _>(bounds.west)
+    .bind::<Double, _>(bounds.east)
+    .bind::<Integer, _>(cells)
+    .load::<PhotoCluster>(conn)?;
+
+    Ok(clusters)
+}
+
+fn validate_bounds(bounds: &GeoBounds) -> Result<()> {
+    let GeoBounds {
+        north,
+        south,
+        east,
+        west,
+    } = *bounds;
+
+    if !(-90.0..=90.0).contains(&south) || !(-90.0..=90.0).contains(&north) || south > north {
+        bail!("Invalid latitudes: south {}, north {}", south, north);
+    }
+    if !(-180.0..=180.0).contains(&west) || !(-180.0..=180.0).contains(&east) {
+        bail!("Invalid longitudes: west {}, east {}", west, east);
+    }
+
+    Ok(())
+}
+
+fn wrap_longitude(longitude: f64) -> f64 {
+    if longitude > 180.0 {
+        longitude - 360.0
+    } else if longitude < -180.0 {
+        longitude + 360.0
+    } else {
+        longitude
+    }
+}
+
+/// Great-circle distance between two points with the haversine formula.
+pub fn distance_meters(lat_a: f64, lon_a: f64, lat_b: f64, lon_b: f64) -> f64 {
+    let d_lat = (lat_b - lat_a).to_radians();
+    let d_lon = (lon_b - lon_a).to_radians();
+
+    let h = (d_lat / 2.0).sin().powi(2)
+        + lat_a.to_radians().cos() * lat_b.to_radians().cos() * (d_lon / 2.0).sin().powi(2);
+
+    2.0 * EARTH_RADIUS_METERS * h.sqrt().asin()
+}
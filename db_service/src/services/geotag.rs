@@ -0,0 +1,271 @@
+use crate::db::DbPoolConn;
+use crate::metadata_reader::{ExifToolWriter, format_gps_coordinate};
+use crate::schema::schema::{directories, exif_metadata, photos};
+use crate::schema::{GeotagMatch, GeotagOptions};
+use crate::services::metadata::MetadataFailure;
+use crate::services::places::{assign_places, place_index};
+use crate::track_log::Track;
+use anyhow::{Result, bail};
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, NaiveDateTime, TimeDelta, Utc};
+use diesel::prelude::*;
+use std::path::Path;
+use std::str::FromStr;
+use uuid::Uuid;
+
+/// Photos more than five minutes away from every track point are not matched by default.
+pub const DEFAULT_MAX_GAP_SECONDS: i64 = 300;
+
+/// Finds the position of the photos that have a capture time but no coordinates on a track.
+/// Nothing is stored, the matches are meant to be reviewed before `save_geotags`.
+pub fn match_photos_to_track(
+    conn: &mut DbPoolConn,
+    track: &Track,
+    options: &GeotagOptions,
+) -> Result<Vec<GeotagMatch>> {
+    if track.is_empty() {
+        bail!("The track logs contain no timed points");
+    }
+
+    let mut query = photos::table
+        .inner_join(exif_metadata::table)
+        .filter(photos::is_missing.eq(false))
+        .filter(exif_metadata::latitude.is_null())
+        .filter(exif_metadata::date_time_original.is_not_null())
+        .order(exif_metadata::date_time_original)
+        .select((
+            photos::id,
+            photos::name,
+            exif_metadata::date_time_original.assume_not_null(),
+            exif_metadata::utc_offset_minutes,
+        ))
+        .into_boxed();
+
+    if let Some(dir_id) = options.directory_id {
+        query = query.filter(photos::path.eq(dir_id));
+    }
+
+    let candidates: Vec<(Uuid, String, NaiveDateTime, Option<i32>)> = query.load(conn)?;
+
+    let max_gap = TimeDelta::seconds(
+        options
+            .max_gap_seconds
+            .unwrap_or(DEFAULT_MAX_GAP_SECONDS)
+            .max(0),
+    );
+
+    let matches = candidates
+        .into_iter()
+        .filter_map(|(photo_id, name, date_time_original, utc_offset_minutes)| {
+            let time = track_time(date_time_original, utc_offset_minutes, options);
+            let fix = track.locate(time, max_gap)?;
+
+            Some(GeotagMatch {
+                photo_id,
+                name,
+                date_time_original,
+                latitude: fix.latitude,
+                longitude: fix.longitude,
+                altitude: fix.elevation,
+                gap_seconds: fix.gap_seconds,
+            })
+        })
+        .collect();
+
+    Ok(matches)
+}
+
+/// The UTC time to look up on the track. Capture times are UTC once their offset is known, the
+/// local time of the camera otherwise, and the camera clock may be off by `camera_offset_seconds`.
+fn track_time(
+    date_time_original: NaiveDateTime,
+    utc_offset_minutes: Option<i32>,
+    options: &GeotagOptions,
+) -> DateTime<Utc> {
+    let camera_offset = TimeDelta::seconds(options.camera_offset_seconds);
+    let local_offset = TimeDelta::minutes(options.utc_offset_minutes as i64);
+
+    match utc_offset_minutes {
+        Some(_) => date_time_original - camera_offset,
+        None => date_time_original - local_offset - camera_offset,
+    }
+    .and_utc()
+}
+
+/// Stores the reviewed matches in `exif_metadata` and geocodes the photos.
+pub fn save_geotags(conn: &mut DbPoolConn, matches: &[GeotagMatch]) -> Result<usize> {
+    for entry in matches {
+        if entry.latitude.abs() > 90.0 || entry.longitude.abs() > 180.0 {
+            bail!(
+                "Invalid coordinates for {}: {}, {}",
+                entry.name,
+                entry.latitude,
+                entry.longitude
+            );
+        }
+    }
+
+    conn.transaction::<_, anyhow::Error, _>(|conn| {
+        for entry in matches {
+            diesel::update(exif_metadata::table.filter(exif_metadata::photo_id.eq(entry.photo_id)))
+                .set((
+                    exif_metadata::latitude.eq(entry.latitude),
+                    exif_metadata::longitude.eq(entry.longitude),
+                    exif_metadata::gps_latitude
+                        .eq(format_gps_coordinate(entry.latitude, ('N', 'S'))),
+                    exif_metadata::gps_longitude
+                        .eq(format_gps_coordinate(entry.longitude, ('E', 'W'))),
+                    exif_metadata::gps_altitude.eq(entry.altitude.and_then(|altitude| {
+                        BigDecimal::from_str(&format!("{:.1}", altitude)).ok()
+                    })),
+                ))
+                .execute(conn)?;
+        }
+        Ok(())
+    })?;
+
+    let photo_ids: Vec<Uuid> = matches.iter().map(|entry| entry.photo_id).collect();
+    let places = place_index(conn)?;
+    assign_places(conn, &places, &photo_ids)?;
+
+    Ok(matches.len())
+}
+
+/// Writes the matched positions into the photo files with ExifTool, so other applications see
+/// them too. Files that can't be written don't stop the others, they are returned.
+pub fn write_geotags_to_files(
+    conn: &mut DbPoolConn,
+    matches: &[GeotagMatch],
+) -> Result<Vec<MetadataFailure>> {
+    let photo_ids: Vec<Uuid> = matches.iter().map(|entry| entry.photo_id).collect();
+    let paths: Vec<(Uuid, String, String)> = photos::table
+        .inner_join(directories::table)
+        .filter(photos::id.eq_any(&photo_ids))
+        .select((photos::id, directories::path, photos::name))
+        .load(conn)?;
+
+    let mut writer = ExifToolWriter::new()?;
+    let mut failures = Vec::new();
+
+    for (photo_id, directory_path, name) in paths {
+        let Some(entry) = matches.iter().find(|entry| entry.photo_id == photo_id) else {
+            continue;
+        };
+
+        let file_path = Path::new(&directory_path).join(&name);
+        if let Err(err) =
+            writer.write_gps_position(&file_path, entry.latitude, entry.longitude, entry.altitude)
+        {
+            tracing::warn!("Cannot write the position of {}: {:#}", name, err);
+            failures.push(MetadataFailure {
+                photo_id,
+                name,
+                error: format!("{:#}", err),
+            });
+        }
+    }
+
+    Ok(failures)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::track_log::TrackPoint;
+    use chrono::{NaiveDate, TimeZone};
+
+    fn local(hour: u32, minute: u32, second: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 5, 1)
+            .unwrap()
+            .and_hms_opt(hour, minute, second)
+            .unwrap()
+    }
+
+    fn utc(hour: u32, minute: u32, second: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, 1, hour, minute, second)
+            .unwrap()
+    }
+
+    #[test]
+    fn known_offsets_are_already_utc() {
+        let options = GeotagOptions {
+            utc_offset_minutes: 120,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            track_time(local(10, 0, 0), Some(120), &options),
+            utc(10, 0, 0)
+        );
+    }
+
+    #[test]
+    fn unknown_offsets_use_the_camera_time_zone() {
+        let options = GeotagOptions {
+            utc_offset_minutes: 120,
+            ..Default::default()
+        };
+        assert_eq!(track_time(local(12, 0, 0), None, &options), utc(10, 0, 0));
+
+        let options = GeotagOptions {
+            utc_offset_minutes: -330,
+            ..Default::default()
+        };
+        assert_eq!(track_time(local(4, 30, 0), None, &options), utc(10, 0, 0));
+    }
+
+    #[test]
+    fn camera_clock_offset_is_removed() {
+        // The camera clock was 90 seconds ahead
+        let options = GeotagOptions {
+            camera_offset_seconds: 90,
+            utc_offset_minutes: 60,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            track_time(local(10, 1, 30), Some(0), &options),
+            utc(10, 0, 0)
+        );
+        assert_eq!(track_time(local(11, 1, 30), None, &options), utc(10, 0, 0));
+    }
+
+    #[test]
+    fn corrected_times_find_the_track_position() {
+        let track = Track::new(vec![
+            TrackPoint {
+                time: utc(10, 0, 0),
+                latitude: 48.0,
+                longitude: 2.0,
+                elevation: None,
+            },
+            TrackPoint {
+                time: utc(10, 2, 0),
+                latitude: 48.002,
+                longitude: 2.002,
+                elevation: None,
+            },
+        ]);
+        let options = GeotagOptions {
+            camera_offset_seconds: -30,
+            utc_offset_minutes: 120,
+            ..Default::default()
+        };
+        let max_gap = TimeDelta::seconds(DEFAULT_MAX_GAP_SECONDS);
+
+        // 12:00:30 on a clock 30 seconds late is 10:01:00 UTC, halfway between the points
+        let fix = track
+            .locate(track_time(local(12, 0, 30), None, &options), max_gap)
+            .unwrap();
+        assert!((fix.latitude - 48.001).abs() < 1e-9);
+        assert_eq!(fix.gap_seconds, 60);
+
+        // Read as UTC the same time is two hours after the end of the track
+        let options = GeotagOptions::default();
+        assert!(
+            track
+                .locate(track_time(local(12, 0, 30), None, &options), max_gap)
+                .is_none()
+        );
+    }
+}
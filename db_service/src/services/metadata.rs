@@ -1,128 +1,278 @@
-use crate::db::DbPoolConn;
-use crate::schema::schema::{exif_metadata, photos};
-use crate::schema::{Directory, ExifMetadata, Photo, PhotoSummary};
-use anyhow::Result;
-use bigdecimal::FromPrimitive;
+use crate::db::{DbPoolConn, insert_batch_size};
+use crate::metadata_reader::{
+    MAX_UTC_OFFSET_MINUTES, MetadataReader, PhotoMetadata, metadata_reader,
+};
+use crate::schema::schema::{directories, exif_metadata, photo_tags_mappings, photos};
+use crate::schema::types::{TagSource, TimeZoneSource};
+use crate::schema::{Directory, ExifMetadata, NewPhotoTagMapping, Photo, PhotoSummary};
+use crate::services::tags::{MAX_TAG_LENGTH, TAG_BATCH_SIZE};
+use anyhow::{Result, bail};
 use bigdecimal::{BigDecimal, ToPrimitive};
-use chrono::NaiveDateTime;
-use chrono::Utc;
+use chrono::{NaiveDateTime, TimeDelta};
+use diesel::dsl::IntervalDsl;
 use diesel::prelude::*;
-use exiftool::ExifTool;
+use diesel::sql_types::{Integer, Nullable, Text};
+use diesel::upsert::excluded;
 use rayon::prelude::*;
 use std::path::Path;
 use uuid::Uuid;
 
+/// Rows of the 56 columns of an [`ExifMetadata`] inserted at once.
+const EXIF_BATCH_SIZE: usize = insert_batch_size(56);
+
+/// A photo whose metadata couldn't be read.
+#[derive(Debug, Clone)]
+pub struct MetadataFailure {
+    pub photo_id: Uuid,
+    pub name: String,
+    pub error: String,
+}
+
+#[derive(Debug, Default)]
+pub struct MetadataReport {
+    pub saved: usize,
+    pub failures: Vec<MetadataFailure>,
+}
+
+/// Reads the metadata of the given photos with the configured reader and stores it, the
+/// embedded keywords become tags. Photos that can't be read don't stop the others, they are
+/// returned in the report.
 pub fn save_metadata_from_photos(
-    photo_entries: &Vec<Photo>,
+    photo_entries: &[Photo],
     dir: &Directory,
     conn: &mut DbPoolConn,
-) -> Result<usize> {
-    let exif_entries: Vec<ExifMetadata> = photo_entries
+) -> Result<MetadataReport> {
+    save_metadata_with_reader(metadata_reader(), photo_entries, dir, conn)
+}
+
+pub fn save_metadata_with_reader(
+    reader: &dyn MetadataReader,
+    photo_entries: &[Photo],
+    dir: &Directory,
+    conn: &mut DbPoolConn,
+) -> Result<MetadataReport> {
+    let results: Vec<(&Photo, Result<PhotoMetadata>)> = photo_entries
         .par_iter()
-        .filter_map(|photo| {
-            let mut exiftool = match ExifTool::new() {
-                Ok(tool) => tool,
-                Err(_) => return None,
-            };
+        .map(|photo| {
             let photo_path = Path::new(&dir.path).join(&photo.name);
-            let data = exiftool.json(&photo_path, &[]).ok()?;
-
-            macro_rules! get_str {
-                ($k:expr) => {
-                    data.get($k).and_then(|v| v.as_str()).map(|s| s.to_string())
-                };
-            }
-            macro_rules! get_i32 {
-                ($k:expr) => {
-                    data.get($k).and_then(|v| v.as_i64()).map(|i| i as i32)
-                };
-            }
-            macro_rules! get_bd {
-                ($k:expr) => {
-                    data.get($k)
-                        .and_then(|v| v.as_f64())
-                        .and_then(|f| BigDecimal::from_f64(f))
-                };
-            }
-            macro_rules! get_dt {
-                ($k:expr) => {
-                    data.get($k)
-                        .and_then(|v| v.as_str())
-                        .and_then(|s| NaiveDateTime::parse_from_str(s, "%Y:%m:%d %H:%M:%S").ok())
-                };
-            }
+            let outcome = reader.read(photo.id, &photo_path);
 
-            Some(ExifMetadata {
-                id: Uuid::new_v4(),
-                photo_id: photo.id,
-
-                make: get_str!("Make"),
-                model: get_str!("Model"),
-                orientation: get_i32!("Orientation"),
-                software: get_str!("Software"),
-
-                x_resolution: get_bd!("XResolution"),
-                y_resolution: get_bd!("YResolution"),
-                resolution_unit: get_i32!("ResolutionUnit"),
-                pixel_x_dimension: get_i32!("ImageWidth"),
-                pixel_y_dimension: get_i32!("ImageHeight"),
-
-                date_time: get_dt!("ModifyDate"),
-                date_time_original: get_dt!("DateTimeOriginal"),
-                date_time_digitized: get_dt!("CreateDate"),
-                subsec_time: get_str!("SubSecTime"),
-                subsec_time_original: get_str!("SubSecTimeOriginal"),
-                subsec_time_digitized: get_str!("SubSecTimeDigitized"),
-
-                exposure_time: get_str!("ExposureTime"),
-                f_number: get_bd!("FNumber"),
-                exposure_program: get_i32!("ExposureProgram"),
-                iso_speed: get_i32!("ISO"),
-                shutter_speed_value: get_bd!("ShutterSpeedValue"),
-                aperture_value: get_bd!("ApertureValue"),
-                brightness_value: get_bd!("BrightnessValue"),
-                exposure_bias: get_bd!("ExposureCompensation"),
-                max_aperture_value: get_bd!("MaxApertureValue"),
-                exposure_mode: get_i32!("ExposureMode"),
-
-                metering_mode: get_i32!("MeteringMode"),
-                light_source: get_i32!("LightSource"),
-                flash: get_i32!("Flash"),
-                focal_length: get_bd!("FocalLength"),
-                focal_length_in_35mm_film: get_i32!("FocalLengthIn35mmFormat"),
-
-                white_balance: get_i32!("WhiteBalance"),
-                scene_capture_type: get_i32!("SceneCaptureType"),
-                custom_rendered: get_i32!("CustomRendered"),
-                contrast: get_i32!("Contrast"),
-                saturation: get_i32!("Saturation"),
-                sharpness: get_i32!("Sharpness"),
-                gain_control: get_bd!("GainControl"),
-
-                exif_version: get_str!("ExifVersion"),
-                image_unique_id: get_str!("ImageUniqueID"),
-                components_configuration: get_str!("ComponentsConfiguration"),
-
-                gps_version_id: get_str!("GPSVersionID"),
-                gps_latitude: get_str!("GPSLatitude"),
-                gps_longitude: get_str!("GPSLongitude"),
-                gps_altitude: get_bd!("GPSAltitude"),
-                gps_timestamp: get_dt!("GPSTimeStamp"),
-                gps_processing_method: get_str!("GPSProcessingMethod"),
-                gps_date_stamp: get_str!("GPSDateStamp"),
-
-                created_at: Some(Utc::now().naive_utc()),
-            })
+            (photo, outcome)
         })
         .collect();
 
-    if !exif_entries.is_empty() {
+    let mut exif_entries: Vec<ExifMetadata> = Vec::new();
+    let mut captions: Vec<(Uuid, String)> = Vec::new();
+    let mut keyword_tags: Vec<NewPhotoTagMapping> = Vec::new();
+    let mut failures: Vec<MetadataFailure> = Vec::new();
+    for (photo, outcome) in results {
+        match outcome {
+            Ok(mut metadata) => {
+                apply_directory_offset(&mut metadata.exif, dir);
+                exif_entries.push(metadata.exif);
+                if let Some(caption) = metadata.caption {
+                    captions.push((photo.id, caption));
+                }
+                keyword_tags.extend(
+                    metadata
+                        .keywords
+                        .into_iter()
+                        .filter(|keyword| keyword.chars().count() <= MAX_TAG_LENGTH)
+                        .map(|tag| NewPhotoTagMapping {
+                            id: Uuid::new_v4(),
+                            photo_id: photo.id,
+                            tag,
+                            source: TagSource::Embedded,
+                        }),
+                );
+            }
+            Err(err) => {
+                tracing::warn!("Cannot read the metadata of {}: {:#}", photo.name, err);
+                failures.push(MetadataFailure {
+                    photo_id: photo.id,
+                    name: photo.name.clone(),
+                    error: format!("{:#}", err),
+                });
+            }
+        }
+    }
+
+    // A photo read again while its previous row is still there gets it replaced
+    for batch in exif_entries.chunks(EXIF_BATCH_SIZE) {
         diesel::insert_into(exif_metadata::table)
-            .values(&exif_entries)
+            .values(batch)
+            .on_conflict(exif_metadata::photo_id)
+            .do_update()
+            .set((
+                exif_metadata::make.eq(excluded(exif_metadata::make)),
+                exif_metadata::model.eq(excluded(exif_metadata::model)),
+                exif_metadata::orientation.eq(excluded(exif_metadata::orientation)),
+                exif_metadata::software.eq(excluded(exif_metadata::software)),
+                exif_metadata::x_resolution.eq(excluded(exif_metadata::x_resolution)),
+                exif_metadata::y_resolution.eq(excluded(exif_metadata::y_resolution)),
+                exif_metadata::resolution_unit.eq(excluded(exif_metadata::resolution_unit)),
+                exif_metadata::pixel_x_dimension.eq(excluded(exif_metadata::pixel_x_dimension)),
+                exif_metadata::pixel_y_dimension.eq(excluded(exif_metadata::pixel_y_dimension)),
+                exif_metadata::date_time.eq(excluded(exif_metadata::date_time)),
+                exif_metadata::date_time_original.eq(excluded(exif_metadata::date_time_original)),
+                exif_metadata::date_time_digitized.eq(excluded(exif_metadata::date_time_digitized)),
+                exif_metadata::subsec_time.eq(excluded(exif_metadata::subsec_time)),
+                exif_metadata::subsec_time_original
+                    .eq(excluded(exif_metadata::subsec_time_original)),
+                exif_metadata::subsec_time_digitized
+                    .eq(excluded(exif_metadata::subsec_time_digitized)),
+                exif_metadata::exposure_time.eq(excluded(exif_metadata::exposure_time)),
+                exif_metadata::f_number.eq(excluded(exif_metadata::f_number)),
+                exif_metadata::exposure_program.eq(excluded(exif_metadata::exposure_program)),
+                exif_metadata::iso_speed.eq(excluded(exif_metadata::iso_speed)),
+                exif_metadata::shutter_speed_value.eq(excluded(exif_metadata::shutter_speed_value)),
+                exif_metadata::aperture_value.eq(excluded(exif_metadata::aperture_value)),
+                exif_metadata::brightness_value.eq(excluded(exif_metadata::brightness_value)),
+                exif_metadata::exposure_bias.eq(excluded(exif_metadata::exposure_bias)),
+                exif_metadata::max_aperture_value.eq(excluded(exif_metadata::max_aperture_value)),
+                exif_metadata::exposure_mode.eq(excluded(exif_metadata::exposure_mode)),
+                exif_metadata::metering_mode.eq(excluded(exif_metadata::metering_mode)),
+                exif_metadata::light_source.eq(excluded(exif_metadata::light_source)),
+                exif_metadata::flash.eq(excluded(exif_metadata::flash)),
+                exif_metadata::focal_length.eq(excluded(exif_metadata::focal_length)),
+                exif_metadata::focal_length_in_35mm_film
+                    .eq(excluded(exif_metadata::focal_length_in_35mm_film)),
+                exif_metadata::white_balance.eq(excluded(exif_metadata::white_balance)),
+                exif_metadata::scene_capture_type.eq(excluded(exif_metadata::scene_capture_type)),
+                exif_metadata::custom_rendered.eq(excluded(exif_metadata::custom_rendered)),
+                exif_metadata::contrast.eq(excluded(exif_metadata::contrast)),
+                exif_metadata::saturation.eq(excluded(exif_metadata::saturation)),
+                exif_metadata::sharpness.eq(excluded(exif_metadata::sharpness)),
+                exif_metadata::gain_control.eq(excluded(exif_metadata::gain_control)),
+                exif_metadata::exif_version.eq(excluded(exif_metadata::exif_version)),
+                exif_metadata::image_unique_id.eq(excluded(exif_metadata::image_unique_id)),
+                exif_metadata::components_configuration
+                    .eq(excluded(exif_metadata::components_configuration)),
+                exif_metadata::gps_version_id.eq(excluded(exif_metadata::gps_version_id)),
+                exif_metadata::gps_latitude.eq(excluded(exif_metadata::gps_latitude)),
+                exif_metadata::gps_longitude.eq(excluded(exif_metadata::gps_longitude)),
+                exif_metadata::gps_altitude.eq(excluded(exif_metadata::gps_altitude)),
+                exif_metadata::gps_timestamp.eq(excluded(exif_metadata::gps_timestamp)),
+                exif_metadata::gps_processing_method
+                    .eq(excluded(exif_metadata::gps_processing_method)),
+                exif_metadata::gps_date_stamp.eq(excluded(exif_metadata::gps_date_stamp)),
+                exif_metadata::created_at.eq(excluded(exif_metadata::created_at)),
+                exif_metadata::latitude.eq(excluded(exif_metadata::latitude)),
+                exif_metadata::longitude.eq(excluded(exif_metadata::longitude)),
+                exif_metadata::utc_offset_minutes.eq(excluded(exif_metadata::utc_offset_minutes)),
+                exif_metadata::time_zone_source.eq(excluded(exif_metadata::time_zone_source)),
+                exif_metadata::lens_make.eq(excluded(exif_metadata::lens_make)),
+                exif_metadata::lens_model.eq(excluded(exif_metadata::lens_model)),
+            ))
             .execute(conn)?;
     }
 
-    Ok(exif_entries.len())
+    // A photo read again had its caption cleared with its other metadata
+    for (photo_id, caption) in &captions {
+        diesel::update(photos::table.find(photo_id))
+            .set(photos::caption.eq(caption))
+            .execute(conn)?;
+    }
+
+    for batch in keyword_tags.chunks(TAG_BATCH_SIZE) {
+        diesel::insert_into(photo_tags_mappings::table)
+            .values(batch)
+            .on_conflict_do_nothing()
+            .execute(conn)?;
+    }
+
+    Ok(MetadataReport {
+        saved: exif_entries.len(),
+        failures,
+    })
+}
+
+/// Cameras that recorded neither an offset nor a GPS fix take the one of their directory.
+fn apply_directory_offset(exif: &mut ExifMetadata, dir: &Directory) {
+    let (Some(date_time), None, Some(minutes)) = (
+        exif.date_time_original,
+        exif.utc_offset_minutes,
+        dir.utc_offset_minutes,
+    ) else {
+        return;
+    };
+
+    exif.date_time_original = Some(date_time - TimeDelta::minutes(minutes as i64));
+    exif.utc_offset_minutes = Some(minutes);
+    exif.time_zone_source = Some(TimeZoneSource::Directory);
+}
+
+/// Sets the offset of the camera clocks of a directory and moves the capture times of its
+/// photos that don't know their own. `None` goes back to the local time of the camera.
+/// Returns the number of photos whose capture time changed.
+pub fn set_directory_utc_offset(
+    conn: &mut DbPoolConn,
+    dir_id: &Uuid,
+    utc_offset_minutes: Option<i32>,
+) -> Result<usize> {
+    if let Some(minutes) = utc_offset_minutes.filter(|m| m.abs() > MAX_UTC_OFFSET_MINUTES) {
+        bail!("Invalid UTC offset: {} minutes", minutes);
+    }
+
+    conn.transaction::<_, anyhow::Error, _>(|conn| {
+        diesel::update(directories::table.filter(directories::id.eq(dir_id)))
+            .set(directories::utc_offset_minutes.eq(utc_offset_minutes))
+            .execute(conn)?;
+
+        // Back to the local time with the previous offset, then to UTC with the new one
+        let updated = diesel::sql_query(
+            "UPDATE exif_metadata e \
+             SET date_time_original = e.date_time_original \
+                     + make_interval(mins => coalesce(e.utc_offset_minutes, 0) - coalesce($2, 0)), \
+                 utc_offset_minutes = $2, \
+                 time_zone_source = CASE WHEN $2 IS NULL THEN NULL ELSE $3 END \
+             FROM photos p \
+             WHERE p.id = e.photo_id \
+               AND p.path = $1 \
+               AND e.date_time_original IS NOT NULL \
+               AND (e.time_zone_source IS NULL OR e.time_zone_source = $3)",
+        )
+        .bind::<diesel::sql_types::Uuid, _>(dir_id)
+        .bind::<Nullable<Integer>, _>(utc_offset_minutes)
+        .bind::<Text, _>(TimeZoneSource::Directory)
+        .execute(conn)?;
+
+        Ok(updated)
+    })
+}
+
+/// Moves the capture times of photos taken with a camera whose clock was wrong, by a number
+/// of seconds that can be negative. Returns the number of photos moved.
+pub fn shift_capture_times(
+    conn: &mut DbPoolConn,
+    photo_ids: &[Uuid],
+    seconds: i64,
+) -> Result<usize> {
+    let shifted = diesel::update(
+        exif_metadata::table
+            .filter(exif_metadata::photo_id.eq_any(photo_ids))
+            .filter(exif_metadata::date_time_original.is_not_null()),
+    )
+    .set(
+        exif_metadata::date_time_original.eq(exif_metadata::date_time_original + seconds.seconds()),
+    )
+    .execute(conn)?;
+
+    Ok(shifted)
+}
+
+/// The photos of a directory that have no metadata stored yet.
+pub fn get_photos_without_metadata(conn: &mut DbPoolConn, dir_id: &Uuid) -> Result<Vec<Photo>> {
+    let results = photos::table
+        .left_join(exif_metadata::table)
+        .filter(photos::path.eq(dir_id))
+        .filter(photos::is_missing.eq(false))
+        .filter(exif_metadata::photo_id.nullable().is_null())
+        .order(photos::name)
+        .select(Photo::as_select())
+        .load(conn)?;
+
+    Ok(results)
 }
 
 pub fn get_basic_metadata_for_photos(
@@ -140,6 +290,7 @@ pub fn get_basic_metadata_for_photos(
             exif_dsl::make.nullable(),
             exif_dsl::model.nullable(),
             exif_dsl::date_time_original.nullable(),
+            exif_dsl::utc_offset_minutes.nullable(),
             exif_dsl::pixel_x_dimension.nullable(),
             exif_dsl::pixel_y_dimension.nullable(),
             exif_dsl::iso_speed.nullable(),
@@ -148,6 +299,7 @@ pub fn get_basic_metadata_for_photos(
             exif_dsl::focal_length.nullable(),
             exif_dsl::gps_latitude.nullable(),
             exif_dsl::gps_longitude.nullable(),
+            photos_dsl::caption,
         ))
         .load::<(
             Uuid,
@@ -157,11 +309,13 @@ pub fn get_basic_metadata_for_photos(
             Option<i32>,
             Option<i32>,
             Option<i32>,
+            Option<i32>,
             Option<BigDecimal>,
             Option<String>,
             Option<BigDecimal>,
             Option<String>,
             Option<String>,
+            Option<String>,
         )>(conn)?
         .into_iter()
         .map(
@@ -170,6 +324,7 @@ pub fn get_basic_metadata_for_photos(
                 make,
                 model,
                 date_time_original,
+                utc_offset_minutes,
                 width,
                 height,
                 iso_speed,
@@ -178,12 +333,18 @@ pub fn get_basic_metadata_for_photos(
                 focal_length,
                 gps_latitude,
                 gps_longitude,
+                caption,
             )| {
                 PhotoSummary {
                     id,
                     make,
                     model,
-                    date_time_original,
+                    // Stored as UTC when the offset is known
+                    date_time_original: date_time_original.map(|date_time| {
+                        date_time
+                            + TimeDelta::minutes(utc_offset_minutes.unwrap_or_default() as i64)
+                    }),
+                    utc_offset_minutes,
                     width,
                     height,
                     iso_speed,
@@ -192,6 +353,7 @@ pub fn get_basic_metadata_for_photos(
                     focal_length: focal_length.and_then(|bd| bd.to_f64()),
                     gps_latitude,
                     gps_longitude,
+                    caption,
                 }
             },
         )
@@ -199,3 +361,63 @@ pub fn get_basic_metadata_for_photos(
 
     Ok(results)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::tests::{fixture_directory, test_connection};
+    use crate::metadata_reader::NativeReader;
+    use crate::schema::PhotoPageRequest;
+    use crate::services::photo::{PhotoQuery, count_photos_filtered, get_photos_filtered};
+
+    #[test]
+    fn keeps_one_metadata_row_per_photo() {
+        let Some(mut conn) = test_connection() else {
+            eprintln!("No database to test against, skipping");
+            return;
+        };
+        let conn = &mut conn;
+
+        let dir = fixture_directory(conn);
+        let photos = get_photos_without_metadata(conn, &dir.id).unwrap();
+        assert_eq!(photos.len(), 2);
+
+        // Read twice, e.g. when a retry runs after a read that was stored
+        for _ in 0..2 {
+            let report = save_metadata_with_reader(&NativeReader, &photos, &dir, conn).unwrap();
+            assert!(report.failures.is_empty());
+        }
+
+        let photo_ids: Vec<Uuid> = photos.iter().map(|photo| photo.id).collect();
+        let rows: i64 = exif_metadata::table
+            .filter(exif_metadata::photo_id.eq_any(&photo_ids))
+            .count()
+            .get_result(conn)
+            .unwrap();
+        assert_eq!(rows, 2);
+
+        // A second row for the same photo is refused
+        let duplicate = conn.transaction(|conn| {
+            diesel::insert_into(exif_metadata::table)
+                .values((
+                    exif_metadata::id.eq(Uuid::new_v4()),
+                    exif_metadata::photo_id.eq(photos[0].id),
+                ))
+                .execute(conn)
+        });
+        assert!(duplicate.is_err());
+
+        // Each photo is listed and counted once
+        let query = PhotoQuery {
+            directory_id: Some(dir.id),
+            ..Default::default()
+        };
+        let page = get_photos_filtered(conn, &query, &PhotoPageRequest::default()).unwrap();
+        let mut listed: Vec<Uuid> = page.photos.iter().map(|photo| photo.id).collect();
+        listed.sort();
+        listed.dedup();
+        assert_eq!(page.photos.len(), 2);
+        assert_eq!(listed.len(), 2);
+        assert_eq!(count_photos_filtered(conn, &query).unwrap(), 2);
+    }
+}
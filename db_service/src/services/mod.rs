@@ -1,6 +1,18 @@
+pub mod albums;
+pub mod culling;
 pub mod directory;
+pub mod duplicates;
 pub mod embeddings;
 pub mod faces;
+pub mod geo;
+pub mod geotag;
 pub mod metadata;
 pub mod photo;
+pub mod places;
+pub mod processing;
+pub mod progress;
+pub mod sidecars;
+pub mod similarity;
+pub mod smart_albums;
 pub mod tags;
+pub mod tasks;
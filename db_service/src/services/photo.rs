@@ -1,58 +1,509 @@
-use crate::db::DbPoolConn;
+use crate::db::{DbPoolConn, insert_batch_size};
 use crate::schema::schema::photos::dsl::photos as photos_dsl;
-use crate::schema::schema::{directories, photos};
-use crate::schema::{Directory, Photo};
+use crate::schema::schema::{
+    directories, exif_metadata, face_embeddings, photo_places, photo_tags_mappings, photos, places,
+};
+use crate::schema::types::{ColorLabel, PhotoFlag, TagSource};
+use crate::schema::{
+    Directory, FolderNode, NewPhoto, Photo, PhotoCursor, PhotoFileState, PhotoPage,
+    PhotoPageRequest, PhotoSort, PlaceFilter, RescanSummary,
+};
+use crate::services::duplicates::hash_file;
 use crate::services::metadata::save_metadata_from_photos;
+use crate::services::processing::enqueue_photos;
+use crate::services::sidecars::import_sidecars;
+use crate::tag_query::TagQuery;
 use anyhow::Result;
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, NaiveDateTime, SubsecRound, Utc};
+use diesel::dsl::{Eq, IntoBoxed, LeftJoinOn, LeftJoinQuerySource, not};
+use diesel::pg::Pg;
 use diesel::prelude::*;
+use diesel::sql_types::{Bool, Text};
 use diesel::update;
 use image::ImageFormat;
-use std::path::Path;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::{self, Metadata};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use uuid::Uuid;
 use walkdir::WalkDir;
 
-fn is_photo(file_path: &Path) -> bool {
+/// Rows of the 6 columns of a [`NewPhoto`] inserted at once.
+const PHOTO_BATCH_SIZE: usize = insert_batch_size(6);
+
+pub fn is_photo(file_path: &Path) -> bool {
     file_path
         .extension()
-        .and_then(|ext| ImageFormat::from_extension(&ext.to_string_lossy().to_lowercase()))
+        .and_then(|ext| ImageFormat::from_extension(ext.to_string_lossy().to_lowercase()))
         .is_some()
 }
 
-pub fn insert_photos_from_directory(conn: &mut DbPoolConn, dir: &Directory) -> Result<usize> {
-    let photo_entries: Vec<Photo> = WalkDir::new(&dir.path)
-        .into_iter()
-        .filter_map(|entry| entry.ok()) // Ignore errors
-        .filter(|e| e.file_type().is_file() && is_photo(e.path())) // Only take files
-        .map(|entry| Photo {
+/// A photo file found on disk while walking a directory.
+struct ScannedFile {
+    name: String,
+    file_size: Option<i64>,
+    modified_time: Option<NaiveDateTime>,
+    content_hash: Option<String>,
+}
+
+impl ScannedFile {
+    fn new(name: String, metadata: Option<Metadata>) -> Self {
+        ScannedFile {
+            name,
+            file_size: metadata.as_ref().map(|m| m.len() as i64),
+            // Postgres only keeps microseconds, so truncate to be able to compare later
+            modified_time: metadata
+                .and_then(|m| m.modified().ok())
+                .map(|t| DateTime::<Utc>::from(t).naive_utc().trunc_subsecs(6)),
+            content_hash: None,
+        }
+    }
+
+    /// Reads the whole file to compute its content hash, only done for new or changed files.
+    fn with_content_hash(mut self, dir: &Directory) -> Self {
+        let file_path = Path::new(&dir.path).join(&self.name);
+
+        self.content_hash = match hash_file(&file_path) {
+            Ok(hash) => Some(hash),
+            Err(e) => {
+                tracing::error!("Failed to hash {:?}: {}", file_path, e);
+                None
+            }
+        };
+        self
+    }
+
+    fn into_new_photo(self, dir: &Directory) -> NewPhoto {
+        NewPhoto {
             id: Uuid::new_v4(),
             path: dir.id,
-            name: entry.file_name().to_string_lossy().to_string(),
+            name: self.name,
+            file_size: self.file_size,
+            modified_time: self.modified_time,
+            content_hash: self.content_hash,
+        }
+    }
+}
+
+/// The name under which a file of the directory is stored in the `photos` table: its path
+/// relative to the directory root, always using `/` as separator.
+pub fn photo_name(dir: &Directory, file_path: &Path) -> Option<String> {
+    let relative = file_path.strip_prefix(&dir.path).ok()?;
+
+    let parts: Vec<String> = relative
+        .components()
+        .map(|part| part.as_os_str().to_string_lossy().to_string())
+        .collect();
+
+    if parts.is_empty() {
+        return None;
+    }
+
+    Some(parts.join("/"))
+}
+
+/// Photos imported before their names were relative to the directory root are stored under
+/// their bare file name. Gives them back their relative name when a scanned file in a sub-folder
+/// is theirs, instead of losing their tags, faces and albums to a missing photo and a new one.
+///
+/// A photo takes the only unknown file with its name. When several sub-folders have one, the
+/// size and modification time, then the content hash, have to match.
+fn relocate_bare_names(
+    conn: &mut DbPoolConn,
+    dir: &Directory,
+    on_disk: &HashMap<String, ScannedFile>,
+) -> Result<usize> {
+    let mut nested: HashMap<&str, Vec<&ScannedFile>> = HashMap::new();
+    for file in on_disk.values() {
+        if let Some((_, file_name)) = file.name.rsplit_once('/') {
+            nested.entry(file_name).or_default().push(file);
+        }
+    }
+    if nested.is_empty() {
+        return Ok(0);
+    }
+
+    let file_names: Vec<&str> = nested.keys().copied().collect();
+    let bare: Vec<PhotoFileState> = photos_dsl
+        .filter(photos::path.eq(dir.id))
+        .filter(photos::name.eq_any(&file_names))
+        .select(PhotoFileState::as_select())
+        .load(conn)?;
+    // A file still at the root is the photo itself
+    let bare: Vec<PhotoFileState> = bare
+        .into_iter()
+        .filter(|photo| {
+            !on_disk.contains_key(&photo.name) && !Path::new(&dir.path).join(&photo.name).is_file()
         })
         .collect();
+    if bare.is_empty() {
+        return Ok(0);
+    }
 
-    diesel::insert_into(photos::table)
-        .values(&photo_entries)
-        .execute(conn)?;
+    let candidates: Vec<&str> = bare
+        .iter()
+        .flat_map(|photo| {
+            nested[photo.name.as_str()]
+                .iter()
+                .map(|file| file.name.as_str())
+        })
+        .collect();
+    let mut known: HashSet<String> = photos_dsl
+        .filter(photos::path.eq(dir.id))
+        .filter(photos::name.eq_any(&candidates))
+        .select(photos::name)
+        .load::<String>(conn)?
+        .into_iter()
+        .collect();
+
+    let mut relocated = 0;
+    for photo in bare {
+        let mut matches: Vec<&ScannedFile> = nested[photo.name.as_str()]
+            .iter()
+            .filter(|file| !known.contains(&file.name))
+            .copied()
+            .collect();
+        if matches.len() > 1 {
+            matches.retain(|file| {
+                file.file_size == photo.file_size && file.modified_time == photo.modified_time
+            });
+        }
+        if matches.len() > 1 {
+            let content_hash: Option<String> = photos_dsl
+                .find(photo.id)
+                .select(photos::content_hash)
+                .first(conn)?;
+            if content_hash.is_some() {
+                matches.retain(|file| {
+                    hash_file(&Path::new(&dir.path).join(&file.name)).ok() == content_hash
+                });
+            }
+        }
+        let [file] = matches[..] else {
+            continue;
+        };
+
+        update(photos_dsl.find(photo.id))
+            .set(photos::name.eq(&file.name))
+            .execute(conn)?;
+        known.insert(file.name.clone());
+        relocated += 1;
+    }
+
+    if relocated > 0 {
+        tracing::info!(
+            "Moved {} photos of {} to their sub-folders",
+            relocated,
+            dir.path
+        );
+    }
+    Ok(relocated)
+}
+
+/// Escapes the wildcards of a `LIKE` pattern.
+fn escape_like(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
 
-    let exif_entries_len = save_metadata_from_photos(&photo_entries, dir, conn)?;
+fn scan_photo_files(dir: &Directory) -> Vec<ScannedFile> {
+    WalkDir::new(&dir.path)
+        .into_iter()
+        .filter_map(|entry| entry.ok()) // Ignore errors
+        .filter(|e| e.file_type().is_file() && is_photo(e.path())) // Only take files
+        .filter_map(|entry| {
+            photo_name(dir, entry.path()).map(|name| ScannedFile::new(name, entry.metadata().ok()))
+        })
+        .collect()
+}
 
+fn update_photo_count(conn: &mut DbPoolConn, dir: &Directory) -> Result<i32> {
+    update_photo_count_by_id(conn, &dir.id)
+}
+
+pub fn update_photo_count_by_id(conn: &mut DbPoolConn, dir_id: &Uuid) -> Result<i32> {
     use crate::schema::schema::directories::dsl::directories as directories_dsl;
 
-    update(directories_dsl.filter(directories::id.eq(dir.id)))
-        .set(directories::photo_count.eq(photo_entries.len() as i32))
+    let count: i64 = photos_dsl
+        .filter(photos::path.eq(dir_id))
+        .filter(photos::is_missing.eq(false))
+        .count()
+        .get_result(conn)?;
+
+    update(directories_dsl.filter(directories::id.eq(dir_id)))
+        .set(directories::photo_count.eq(count as i32))
         .execute(conn)?;
 
+    Ok(count as i32)
+}
+
+/// Walks a newly added directory and inserts the photos found on disk. Their metadata is read
+/// afterwards, see [`get_photos_without_metadata`](crate::services::metadata::get_photos_without_metadata).
+pub fn insert_photos_from_directory(conn: &mut DbPoolConn, dir: &Directory) -> Result<usize> {
+    let new_photos: Vec<NewPhoto> = scan_photo_files(dir)
+        .into_par_iter()
+        .map(|file| file.with_content_hash(dir).into_new_photo(dir))
+        .collect();
+
+    // Only keep the rows that were actually inserted, so a conflict doesn't abort the whole import
+    let mut photo_entries: Vec<Photo> = Vec::with_capacity(new_photos.len());
+    for batch in new_photos.chunks(PHOTO_BATCH_SIZE) {
+        let inserted = diesel::insert_into(photos::table)
+            .values(batch)
+            .on_conflict((photos::path, photos::name))
+            .do_nothing()
+            .returning(Photo::as_returning())
+            .get_results(conn)?;
+        photo_entries.extend(inserted);
+    }
+
+    let photo_ids: Vec<Uuid> = photo_entries.iter().map(|photo| photo.id).collect();
+    enqueue_photos(conn, &photo_ids)?;
+    import_sidecars(conn, dir, &photo_entries)?;
+
+    update_photo_count(conn, dir)?;
+
     tracing::info!(
-        "Inserted {} photos and {} metadata entries into the database.",
+        "Inserted {} photos of {} into the database.",
         photo_entries.len(),
-        exif_entries_len
+        dir.path
     );
     Ok(photo_entries.len())
 }
 
+/// Diffs the files on disk against the `photos` table for a directory that was already imported.
+///
+/// New files are inserted, files that disappeared are marked as missing, and files whose size or
+/// modification time changed get their metadata, caption and embedded keywords re-read. Detected
+/// tags and face embeddings are dropped only for the added and changed photos, so the tagging
+/// service processes just the delta.
+pub fn rescan_directory(conn: &mut DbPoolConn, dir: &Directory) -> Result<RescanSummary> {
+    let on_disk: HashMap<String, ScannedFile> = scan_photo_files(dir)
+        .into_iter()
+        .map(|file| (file.name.clone(), file))
+        .collect();
+
+    relocate_bare_names(conn, dir, &on_disk)?;
+
+    let known: Vec<PhotoFileState> = photos_dsl
+        .filter(photos::path.eq(dir.id))
+        .select(PhotoFileState::as_select())
+        .load(conn)?;
+
+    let summary = apply_scanned_files(conn, dir, on_disk, known)?;
+
+    tracing::info!(
+        "Rescanned {}: {} added, {} updated, {} missing",
+        dir.path,
+        summary.added.len(),
+        summary.updated.len(),
+        summary.missing.len()
+    );
+    Ok(summary)
+}
+
+/// Inserts or refreshes only the given files of a directory, e.g. the ones reported by the
+/// filesystem watcher. Paths that don't exist anymore or aren't photos are ignored.
+pub fn insert_photos_from_paths(
+    conn: &mut DbPoolConn,
+    dir: &Directory,
+    paths: &[PathBuf],
+) -> Result<RescanSummary> {
+    let on_disk: HashMap<String, ScannedFile> = paths
+        .iter()
+        .filter(|file_path| file_path.is_file() && is_photo(file_path))
+        .filter_map(|file_path| {
+            photo_name(dir, file_path)
+                .map(|name| ScannedFile::new(name, fs::metadata(file_path).ok()))
+        })
+        .map(|file| (file.name.clone(), file))
+        .collect();
+
+    if on_disk.is_empty() {
+        return Ok(RescanSummary::default());
+    }
+
+    relocate_bare_names(conn, dir, &on_disk)?;
+
+    let names: Vec<&String> = on_disk.keys().collect();
+    let known: Vec<PhotoFileState> = photos_dsl
+        .filter(photos::path.eq(dir.id))
+        .filter(photos::name.eq_any(names))
+        .select(PhotoFileState::as_select())
+        .load(conn)?;
+
+    apply_scanned_files(conn, dir, on_disk, known)
+}
+
+/// Marks the photos of the given files of a directory as missing, like a rescan does, and
+/// returns their ids. Their ratings, tags and albums are kept for when the files come back. A
+/// path can also be a removed sub-folder, in which case all the photos inside it are missing.
+pub fn remove_photos_from_paths(
+    conn: &mut DbPoolConn,
+    dir: &Directory,
+    paths: &[PathBuf],
+) -> Result<Vec<Uuid>> {
+    let names: Vec<String> = paths
+        .iter()
+        .filter_map(|file_path| photo_name(dir, file_path))
+        .collect();
+
+    let mut removed: Vec<Uuid> = update(
+        photos_dsl
+            .filter(photos::path.eq(dir.id))
+            .filter(photos::is_missing.eq(false))
+            .filter(photos::name.eq_any(&names)),
+    )
+    .set(photos::is_missing.eq(true))
+    .returning(photos::id)
+    .get_results(conn)?;
+
+    for name in &names {
+        let nested: Vec<Uuid> = update(
+            photos_dsl
+                .filter(photos::path.eq(dir.id))
+                .filter(photos::is_missing.eq(false))
+                .filter(photos::name.like(format!("{}/%", escape_like(name)))),
+        )
+        .set(photos::is_missing.eq(true))
+        .returning(photos::id)
+        .get_results(conn)?;
+
+        removed.extend(nested);
+    }
+
+    update_photo_count(conn, dir)?;
+
+    Ok(removed)
+}
+
+/// Applies the difference between the scanned files and the known photos to the database.
+/// Known photos that weren't scanned are marked as missing.
+fn apply_scanned_files(
+    conn: &mut DbPoolConn,
+    dir: &Directory,
+    mut on_disk: HashMap<String, ScannedFile>,
+    known: Vec<PhotoFileState>,
+) -> Result<RescanSummary> {
+    let mut summary = RescanSummary::default();
+    let mut changed: Vec<(Photo, ScannedFile)> = Vec::new();
+
+    for photo in known {
+        match on_disk.remove(&photo.name) {
+            None => {
+                if !photo.is_missing {
+                    summary.missing.push(photo.id);
+                }
+            }
+            Some(file) => {
+                if photo.is_missing
+                    || photo.file_size != file.file_size
+                    || photo.modified_time != file.modified_time
+                {
+                    let entry = Photo {
+                        id: photo.id,
+                        path: dir.id,
+                        name: photo.name,
+                    };
+                    changed.push((entry, file));
+                }
+            }
+        }
+    }
+
+    let new_photos: Vec<NewPhoto> = on_disk
+        .into_values()
+        .collect::<Vec<_>>()
+        .into_par_iter()
+        .map(|file| file.with_content_hash(dir).into_new_photo(dir))
+        .collect();
+
+    let changed: Vec<(Photo, ScannedFile)> = changed
+        .into_par_iter()
+        .map(|(photo, file)| (photo, file.with_content_hash(dir)))
+        .collect();
+
+    conn.transaction::<_, anyhow::Error, _>(|conn| {
+        for batch in new_photos.chunks(PHOTO_BATCH_SIZE) {
+            diesel::insert_into(photos::table)
+                .values(batch)
+                .execute(conn)?;
+        }
+
+        if !summary.missing.is_empty() {
+            update(photos_dsl.filter(photos::id.eq_any(&summary.missing)))
+                .set(photos::is_missing.eq(true))
+                .execute(conn)?;
+        }
+
+        for (photo, file) in &changed {
+            update(photos_dsl.filter(photos::id.eq(photo.id)))
+                .set((
+                    photos::file_size.eq(file.file_size),
+                    photos::modified_time.eq(file.modified_time),
+                    photos::content_hash.eq(&file.content_hash),
+                    photos::is_missing.eq(false),
+                    photos::perceptual_hash.eq(None::<i64>),
+                    photos::caption.eq(None::<String>),
+                ))
+                .execute(conn)?;
+        }
+
+        let changed_ids: Vec<Uuid> = changed.iter().map(|(photo, _)| photo.id).collect();
+        let new_ids: Vec<Uuid> = new_photos.iter().map(|photo| photo.id).collect();
+        enqueue_photos(conn, &[new_ids, changed_ids.clone()].concat())?;
+
+        if !changed_ids.is_empty() {
+            diesel::delete(
+                exif_metadata::table.filter(exif_metadata::photo_id.eq_any(&changed_ids)),
+            )
+            .execute(conn)?;
+            diesel::delete(
+                photo_tags_mappings::table
+                    .filter(photo_tags_mappings::photo_id.eq_any(&changed_ids))
+                    .filter(
+                        photo_tags_mappings::source
+                            .eq_any([TagSource::Detection, TagSource::Embedded]),
+                    ),
+            )
+            .execute(conn)?;
+            diesel::delete(
+                face_embeddings::table.filter(face_embeddings::photo_id.eq_any(&changed_ids)),
+            )
+            .execute(conn)?;
+        }
+
+        Ok(())
+    })?;
+
+    summary.added = new_photos.iter().map(|photo| photo.id).collect();
+    summary.updated = changed.iter().map(|(photo, _)| photo.id).collect();
+
+    // The catalog is ahead of the sidecars of the photos it already knows
+    let added: Vec<Photo> = new_photos.iter().map(Photo::from).collect();
+    import_sidecars(conn, dir, &added)?;
+
+    let to_read: Vec<Photo> = added
+        .into_iter()
+        .chain(changed.into_iter().map(|(photo, _)| photo))
+        .collect();
+    save_metadata_from_photos(&to_read, dir, conn)?;
+
+    summary.photo_count = update_photo_count(conn, dir)?;
+
+    Ok(summary)
+}
+
 pub fn get_photos_from_directory(conn: &mut DbPoolConn, path_uuid: Uuid) -> Vec<Photo> {
     photos_dsl
         .filter(photos::path.eq(&path_uuid))
+        .filter(photos::is_missing.eq(false))
+        .select(Photo::as_select())
         .load::<Photo>(conn)
         .unwrap_or_else(|err| {
             tracing::error!("Error retrieving photos: {:?}", err);
@@ -60,34 +511,554 @@ pub fn get_photos_from_directory(conn: &mut DbPoolConn, path_uuid: Uuid) -> Vec<
         })
 }
 
-pub fn get_photos_filtered(
-    conn: &mut DbPoolConn,
-    path_uuid: Option<Uuid>,
-    tag_filters: Vec<String>,
-) -> QueryResult<Vec<Photo>> {
-    use crate::schema::schema::photo_tags_mappings;
+#[derive(Default)]
+struct FolderBuilder {
+    photo_count: i32,
+    children: BTreeMap<String, FolderBuilder>,
+}
+
+impl FolderBuilder {
+    fn into_node(self, name: String, path: String) -> FolderNode {
+        let children = self
+            .children
+            .into_iter()
+            .map(|(child_name, child)| {
+                let child_path = if path.is_empty() {
+                    child_name.clone()
+                } else {
+                    format!("{}/{}", path, child_name)
+                };
+                child.into_node(child_name, child_path)
+            })
+            .collect();
+
+        FolderNode {
+            name,
+            path,
+            photo_count: self.photo_count,
+            children,
+        }
+    }
+}
+
+/// Builds the tree of sub-folders of an imported directory from the relative names of its photos.
+/// Every node counts the photos it contains, including the ones in nested folders.
+pub fn get_folder_tree(conn: &mut DbPoolConn, dir: &Directory) -> Result<FolderNode> {
+    let names: Vec<String> = photos_dsl
+        .filter(photos::path.eq(dir.id))
+        .filter(photos::is_missing.eq(false))
+        .select(photos::name)
+        .load(conn)?;
+
+    let mut root = FolderBuilder::default();
+
+    for name in names {
+        let mut node = &mut root;
+        node.photo_count += 1;
+
+        // The last part is the file itself
+        let mut parts: Vec<&str> = name.split('/').collect();
+        parts.pop();
+
+        for part in parts {
+            node = node.children.entry(part.to_string()).or_default();
+            node.photo_count += 1;
+        }
+    }
+
+    Ok(root.into_node(dir.path.clone(), String::new()))
+}
+
+/// Photos in a page when the caller doesn't ask for a size, and the most it can ask for.
+pub const DEFAULT_PAGE_SIZE: i64 = 200;
+pub const MAX_PAGE_SIZE: i64 = 1000;
+
+type FilteredPhotos<'a> = IntoBoxed<
+    'a,
+    LeftJoinOn<photos::table, exif_metadata::table, Eq<exif_metadata::photo_id, photos::id>>,
+    Pg,
+>;
+
+type PhotoCondition = Box<
+    dyn BoxableExpression<
+            LeftJoinQuerySource<
+                photos::table,
+                exif_metadata::table,
+                Eq<exif_metadata::photo_id, photos::id>,
+            >,
+            Pg,
+            SqlType = Bool,
+        >,
+>;
+
+define_sql_function!(fn lower(value: Text) -> Text);
+
+/// Every tag becomes a subquery over `photo_tags_mappings`, combined like the query. Tags are
+/// compared lowercased, keywords keep the case they were written with and the tags picked in
+/// the sidebar are passed as they are listed.
+fn tag_condition(query: &TagQuery) -> PhotoCondition {
+    match query {
+        TagQuery::Tag(tag) => Box::new(
+            photos::id.eq_any(
+                photo_tags_mappings::table
+                    .filter(lower(photo_tags_mappings::tag).eq(tag.to_lowercase()))
+                    .select(photo_tags_mappings::photo_id),
+            ),
+        ),
+        TagQuery::Not(term) => Box::new(not(tag_condition(term))),
+        TagQuery::And(terms) => terms
+            .iter()
+            .map(tag_condition)
+            .reduce(|all, term| Box::new(all.and(term)))
+            .unwrap_or_else(|| Box::new(true.into_sql::<Bool>())),
+        TagQuery::Or(terms) => terms
+            .iter()
+            .map(tag_condition)
+            .reduce(|any, term| Box::new(any.or(term)))
+            .unwrap_or_else(|| Box::new(false.into_sql::<Bool>())),
+    }
+}
 
+/// Landscape or portrait as the photo is shown, once its Exif orientation is applied.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum PhotoOrientation {
+    Landscape,
+    Portrait,
+    Square,
+}
+
+/// Which photos to keep by their pick or reject flag.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum FlagFilter {
+    Picked,
+    Rejected,
+    Unflagged,
+    /// Picks and unflagged photos, what is left once the rejects are culled.
+    NotRejected,
+}
+
+/// Everything photos can be searched by, the criteria left empty match every photo. Ranges are
+/// inclusive and can be open on either side.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PhotoQuery {
+    pub directory_id: Option<Uuid>,
+    /// Relative to the directory, the photos of nested folders are included.
+    pub sub_folder: Option<String>,
+    pub tags: Option<TagQuery>,
+    pub place: Option<PlaceFilter>,
+    /// Photos showing a face of every one of these clusters.
+    pub face_clusters: Vec<Uuid>,
+
+    /// Matched anywhere in the value and ignoring case, `canon` finds `Canon EOS R5`.
+    pub make: Option<String>,
+    pub model: Option<String>,
+    pub lens: Option<String>,
+
+    pub min_iso: Option<i32>,
+    pub max_iso: Option<i32>,
+    /// F-numbers, e.g. from `1.8` to `4`.
+    pub min_aperture: Option<f64>,
+    pub max_aperture: Option<f64>,
+    /// In millimeters as recorded, not the 35 mm equivalent.
+    pub min_focal_length: Option<f64>,
+    pub max_focal_length: Option<f64>,
+
+    /// Compared to `date_time_original`, which is UTC for the photos whose offset is known.
+    pub taken_after: Option<NaiveDateTime>,
+    pub taken_before: Option<NaiveDateTime>,
+
+    pub orientation: Option<PhotoOrientation>,
+    pub flash_fired: Option<bool>,
+
+    #[serde(flatten)]
+    pub culling: CullingFilter,
+}
+
+/// What was decided about the photos while culling, the criteria left empty match every photo.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CullingFilter {
+    /// Stars from 0, unrated, to 5.
+    pub min_rating: Option<i16>,
+    pub max_rating: Option<i16>,
+    pub flag: Option<FlagFilter>,
+    /// Photos with any of these labels.
+    pub color_labels: Vec<ColorLabel>,
+}
+
+/// Exif orientations turning the photo by a quarter, its width and height are swapped on screen.
+const ROTATED_ORIENTATIONS: [i32; 4] = [5, 6, 7, 8];
+
+/// The lowest bit of the Exif flash value tells whether the flash fired.
+fn flash_fired_values() -> Vec<i32> {
+    (1..=127).step_by(2).collect()
+}
+
+/// Goes through the shortest decimal representation, so that `1.8` still matches f/1.8.
+fn to_decimal(value: f64) -> Option<BigDecimal> {
+    BigDecimal::from_str(&value.to_string()).ok()
+}
+
+/// The photos matching the query that aren't missing, joined to their metadata for sorting.
+fn filtered_photos<'a>(photo_query: &PhotoQuery) -> FilteredPhotos<'a> {
     let mut query = photos::table
-        .left_outer_join(
-            photo_tags_mappings::table.on(photo_tags_mappings::photo_id.eq(photos::id)),
-        )
+        .left_join(exif_metadata::table.on(exif_metadata::photo_id.eq(photos::id)))
+        .filter(photos::is_missing.eq(false))
         .into_boxed();
 
-    // Filter by directory if path_uuid is provided
-    if let Some(path_id) = path_uuid {
+    // Filter by directory if one is provided
+    if let Some(path_id) = photo_query.directory_id {
         query = query.filter(photos::path.eq(path_id));
     }
 
-    // Only add tag filters if they are provided.
-    if !tag_filters.is_empty() {
-        query = query.filter(photo_tags_mappings::tag.eq_any(&tag_filters));
+    // Restrict to a sub-folder of the directory, including its nested folders
+    if let Some(folder) = photo_query
+        .sub_folder
+        .as_deref()
+        .filter(|folder| !folder.is_empty())
+    {
+        query = query.filter(photos::name.like(format!("{}/%", escape_like(folder))));
+    }
+
+    if let Some(tag_query) = &photo_query.tags {
+        query = query.filter(tag_condition(tag_query));
+    }
+
+    // Photos geocoded to the country, region or city
+    if let Some(place) = &photo_query.place {
+        let mut located = photo_places::table
+            .inner_join(places::table)
+            .filter(places::country_code.eq(place.country_code.clone()))
+            .select(photo_places::photo_id)
+            .into_boxed();
+
+        if let Some(region) = &place.region {
+            located = located.filter(places::region.eq(region.clone()));
+        }
+        if let Some(place_id) = place.place_id {
+            located = located.filter(places::id.eq(place_id));
+        }
+
+        query = query.filter(photos::id.eq_any(located));
+    }
+
+    for cluster_id in &photo_query.face_clusters {
+        let with_face = face_embeddings::table
+            .filter(face_embeddings::cluster_id.eq(*cluster_id))
+            .select(face_embeddings::photo_id);
+
+        query = query.filter(photos::id.eq_any(with_face));
+    }
+
+    // Camera and lens
+    if let Some(make) = photo_query.make.as_deref().filter(|make| !make.is_empty()) {
+        query = query.filter(exif_metadata::make.ilike(format!("%{}%", escape_like(make))));
+    }
+    if let Some(model) = photo_query
+        .model
+        .as_deref()
+        .filter(|model| !model.is_empty())
+    {
+        query = query.filter(exif_metadata::model.ilike(format!("%{}%", escape_like(model))));
+    }
+    if let Some(lens) = photo_query.lens.as_deref().filter(|lens| !lens.is_empty()) {
+        query = query.filter(exif_metadata::lens_model.ilike(format!("%{}%", escape_like(lens))));
+    }
+
+    // Exposure
+    if let Some(min_iso) = photo_query.min_iso {
+        query = query.filter(exif_metadata::iso_speed.ge(min_iso));
+    }
+    if let Some(max_iso) = photo_query.max_iso {
+        query = query.filter(exif_metadata::iso_speed.le(max_iso));
+    }
+    if let Some(min_aperture) = photo_query.min_aperture.and_then(to_decimal) {
+        query = query.filter(exif_metadata::f_number.ge(min_aperture));
+    }
+    if let Some(max_aperture) = photo_query.max_aperture.and_then(to_decimal) {
+        query = query.filter(exif_metadata::f_number.le(max_aperture));
+    }
+    if let Some(min_focal_length) = photo_query.min_focal_length.and_then(to_decimal) {
+        query = query.filter(exif_metadata::focal_length.ge(min_focal_length));
+    }
+    if let Some(max_focal_length) = photo_query.max_focal_length.and_then(to_decimal) {
+        query = query.filter(exif_metadata::focal_length.le(max_focal_length));
+    }
+    match photo_query.flash_fired {
+        Some(true) => query = query.filter(exif_metadata::flash.eq_any(flash_fired_values())),
+        Some(false) => query = query.filter(exif_metadata::flash.ne_all(flash_fired_values())),
+        None => {}
     }
 
-    let results = query
-        .select(photos::all_columns)
-        .order(photos::name)
-        .distinct()
-        .load::<Photo>(conn)?;
+    if let Some(taken_after) = photo_query.taken_after {
+        query = query.filter(exif_metadata::date_time_original.ge(taken_after));
+    }
+    if let Some(taken_before) = photo_query.taken_before {
+        query = query.filter(exif_metadata::date_time_original.le(taken_before));
+    }
+
+    if let Some(orientation) = photo_query.orientation {
+        let width = exif_metadata::pixel_x_dimension;
+        let height = exif_metadata::pixel_y_dimension;
+        let upright = exif_metadata::orientation
+            .is_null()
+            .or(exif_metadata::orientation.ne_all(ROTATED_ORIENTATIONS.to_vec()));
+        let rotated = exif_metadata::orientation.eq_any(ROTATED_ORIENTATIONS.to_vec());
+
+        query = match orientation {
+            PhotoOrientation::Landscape => query.filter(
+                upright
+                    .and(width.gt(height))
+                    .or(rotated.and(height.gt(width))),
+            ),
+            PhotoOrientation::Portrait => query.filter(
+                upright
+                    .and(height.gt(width))
+                    .or(rotated.and(width.gt(height))),
+            ),
+            PhotoOrientation::Square => query.filter(width.eq(height)),
+        };
+    }
+
+    // Culling
+    let culling = &photo_query.culling;
+    if let Some(min_rating) = culling.min_rating {
+        query = query.filter(photos::rating.ge(min_rating));
+    }
+    if let Some(max_rating) = culling.max_rating {
+        query = query.filter(photos::rating.le(max_rating));
+    }
+    if let Some(flag) = culling.flag {
+        query = match flag {
+            FlagFilter::Picked => query.filter(photos::flag.eq(PhotoFlag::Pick)),
+            FlagFilter::Rejected => query.filter(photos::flag.eq(PhotoFlag::Reject)),
+            FlagFilter::Unflagged => query.filter(photos::flag.is_null()),
+            FlagFilter::NotRejected => query.filter(
+                photos::flag
+                    .is_null()
+                    .or(photos::flag.ne(PhotoFlag::Reject)),
+            ),
+        };
+    }
+    if !culling.color_labels.is_empty() {
+        query = query.filter(photos::color_label.eq_any(culling.color_labels.clone()));
+    }
+
+    query
+}
+
+/// Orders `$query` by `$key` then by id, and keeps the photos after the key and id of the
+/// cursor. Photos without a key come last, so once the cursor has none only they are left.
+macro_rules! sort_after {
+    ($query:expr, $key:expr, $descending:expr, $cursor:expr) => {{
+        let key = $key.nullable();
+        let mut query = $query;
 
-    Ok(results)
+        if let Some((value, id)) = $cursor {
+            query = match (value, $descending) {
+                (Some(value), false) => query.filter(
+                    key.gt(value.clone())
+                        .or(key.eq(value).and(photos::id.gt(id)))
+                        .or(key.is_null()),
+                ),
+                (Some(value), true) => query.filter(
+                    key.lt(value.clone())
+                        .or(key.eq(value).and(photos::id.gt(id)))
+                        .or(key.is_null()),
+                ),
+                (None, _) => query.filter(key.is_null().and(photos::id.gt(id))),
+            };
+        }
+
+        if $descending {
+            query.order((key.desc().nulls_last(), photos::id))
+        } else {
+            query.order((key.asc().nulls_last(), photos::id))
+        }
+    }};
+}
+
+/// A page of the photos matching the filters, in the order of the request. Pages are read
+/// with a cursor rather than an offset, so the photos added while scrolling aren't shown twice.
+pub fn get_photos_filtered(
+    conn: &mut DbPoolConn,
+    photo_query: &PhotoQuery,
+    page: &PhotoPageRequest,
+) -> QueryResult<PhotoPage> {
+    let query = filtered_photos(photo_query);
+    let after = page.after.as_ref();
+
+    let query = match page.sort {
+        PhotoSort::CaptureDate => sort_after!(
+            query,
+            exif_metadata::date_time_original,
+            page.descending,
+            after.map(|cursor| (cursor.date_time_original, cursor.id))
+        ),
+        PhotoSort::ImportDate => sort_after!(
+            query,
+            photos::imported_at,
+            page.descending,
+            after.map(|cursor| (Some(cursor.imported_at), cursor.id))
+        ),
+        PhotoSort::Name => sort_after!(
+            query,
+            photos::name,
+            page.descending,
+            after.map(|cursor| (Some(cursor.name.clone()), cursor.id))
+        ),
+        PhotoSort::FileSize => sort_after!(
+            query,
+            photos::file_size,
+            page.descending,
+            after.map(|cursor| (cursor.file_size, cursor.id))
+        ),
+        PhotoSort::Rating => sort_after!(
+            query,
+            photos::rating,
+            page.descending,
+            after.map(|cursor| (Some(cursor.rating), cursor.id))
+        ),
+    };
+
+    let limit = page
+        .limit
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+
+    // One more photo than asked tells whether there is another page
+    let mut rows: Vec<(Photo, PhotoCursor)> = query
+        .select((
+            Photo::as_select(),
+            (
+                photos::id,
+                photos::name,
+                exif_metadata::date_time_original.nullable(),
+                photos::imported_at,
+                photos::file_size,
+                photos::rating,
+            ),
+        ))
+        .limit(limit + 1)
+        .load(conn)?;
+
+    let has_more = rows.len() as i64 > limit;
+    rows.truncate(limit as usize);
+
+    let next_cursor = if has_more {
+        rows.last().map(|(_, cursor)| cursor.clone())
+    } else {
+        None
+    };
+
+    Ok(PhotoPage {
+        photos: rows.into_iter().map(|(photo, _)| photo).collect(),
+        next_cursor,
+    })
+}
+
+/// The number of photos matching the filters, for sizing the grid before its pages are read.
+pub fn count_photos_filtered(conn: &mut DbPoolConn, photo_query: &PhotoQuery) -> QueryResult<i64> {
+    filtered_photos(photo_query).count().get_result(conn)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::tests::{fixture_directory, test_connection};
+    use crate::schema::NewPhotoTagMapping;
+
+    #[test]
+    fn tag_chips_match_keywords_of_any_case() {
+        let Some(mut conn) = test_connection() else {
+            eprintln!("No database to test against, skipping");
+            return;
+        };
+        let conn = &mut conn;
+
+        let dir = fixture_directory(conn);
+        let photos = get_photos_from_directory(conn, dir.id);
+        let tagged = photos
+            .iter()
+            .find(|photo| photo.name == "exif.jpg")
+            .unwrap();
+        diesel::insert_into(photo_tags_mappings::table)
+            .values(NewPhotoTagMapping {
+                id: Uuid::new_v4(),
+                photo_id: tagged.id,
+                tag: "Paris".to_string(),
+                source: TagSource::Embedded,
+            })
+            .execute(conn)
+            .unwrap();
+
+        let count = |conn: &mut DbPoolConn, tags: Option<TagQuery>| {
+            let query = PhotoQuery {
+                directory_id: Some(dir.id),
+                tags,
+                ..Default::default()
+            };
+            count_photos_filtered(conn, &query).unwrap()
+        };
+
+        // Chips are listed as the keywords were written, typed queries are lowercased
+        assert_eq!(count(conn, TagQuery::any_of(vec!["Paris".to_string()])), 1);
+        assert_eq!(count(conn, TagQuery::any_of(vec!["PARIS".to_string()])), 1);
+        assert_eq!(count(conn, Some(TagQuery::parse("paris").unwrap())), 1);
+        assert_eq!(count(conn, Some(TagQuery::parse("NOT Paris").unwrap())), 1);
+        assert_eq!(count(conn, TagQuery::any_of(vec!["London".to_string()])), 0);
+    }
+
+    #[test]
+    fn deleted_files_keep_their_photos() {
+        let Some(mut conn) = test_connection() else {
+            eprintln!("No database to test against, skipping");
+            return;
+        };
+        let conn = &mut conn;
+
+        let dir = fixture_directory(conn);
+        let paths = [Path::new(&dir.path).join("exif.jpg")];
+        let photo = get_photos_from_directory(conn, dir.id)
+            .into_iter()
+            .find(|photo| photo.name == "exif.jpg")
+            .unwrap();
+        update(photos_dsl.find(photo.id))
+            .set(photos::rating.eq(4))
+            .execute(conn)
+            .unwrap();
+
+        let removed = remove_photos_from_paths(conn, &dir, &paths).unwrap();
+        assert_eq!(removed, [photo.id]);
+        let (is_missing, rating): (bool, i16) = photos_dsl
+            .find(photo.id)
+            .select((photos::is_missing, photos::rating))
+            .first(conn)
+            .unwrap();
+        assert!(is_missing);
+        assert_eq!(rating, 4);
+        assert_eq!(update_photo_count(conn, &dir).unwrap(), 1);
+
+        // Reported twice, e.g. for the file and then its folder
+        assert!(
+            remove_photos_from_paths(conn, &dir, &paths)
+                .unwrap()
+                .is_empty()
+        );
+
+        // The file is back, with the same photo
+        let summary = insert_photos_from_paths(conn, &dir, &paths).unwrap();
+        assert!(summary.added.is_empty());
+        assert_eq!(summary.updated, [photo.id]);
+        let (is_missing, rating): (bool, i16) = photos_dsl
+            .find(photo.id)
+            .select((photos::is_missing, photos::rating))
+            .first(conn)
+            .unwrap();
+        assert!(!is_missing);
+        assert_eq!(rating, 4);
+    }
 }
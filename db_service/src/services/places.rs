@@ -0,0 +1,214 @@
+use crate::db::{DbPoolConn, insert_batch_size};
+use crate::geocoder::PlaceIndex;
+use crate::schema::schema::{exif_metadata, photo_places, photos, places};
+use crate::schema::{PhotoPlace, Place, PlaceFilter, PlaceNode};
+use anyhow::Result;
+use diesel::prelude::*;
+use diesel::upsert::excluded;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+/// Photos geocoded per query when catching up on the whole catalog.
+const ASSIGN_BATCH_SIZE: i64 = 1000;
+/// Rows of the 3 columns of a [`PhotoPlace`] inserted at once.
+const PLACE_BATCH_SIZE: usize = insert_batch_size(3);
+
+/// The cities of a country by region, `None` for the ones without a region.
+type RegionCities = BTreeMap<Option<String>, Vec<PlaceNode>>;
+
+static PLACE_INDEX: Mutex<Option<Arc<PlaceIndex>>> = Mutex::new(None);
+
+/// The index of every place, loaded once per process. An empty one isn't kept, so places
+/// loaded after the first import are picked up.
+pub fn place_index(conn: &mut DbPoolConn) -> Result<Arc<PlaceIndex>> {
+    let mut cached = PLACE_INDEX.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(index) = cached.as_ref() {
+        return Ok(index.clone());
+    }
+
+    let entries = places::table.select(Place::as_select()).load(conn)?;
+    let index = Arc::new(PlaceIndex::new(entries));
+    if !index.is_empty() {
+        *cached = Some(index.clone());
+    }
+
+    Ok(index)
+}
+
+/// Stores the nearest city of the given photos. Photos without coordinates, or too far from
+/// every city, lose the place they may have had before their metadata was read again.
+pub fn assign_places(
+    conn: &mut DbPoolConn,
+    index: &PlaceIndex,
+    photo_ids: &[Uuid],
+) -> Result<usize> {
+    if index.is_empty() || photo_ids.is_empty() {
+        return Ok(0);
+    }
+
+    let coordinates: Vec<(Uuid, Option<f64>, Option<f64>)> = exif_metadata::table
+        .filter(exif_metadata::photo_id.eq_any(photo_ids))
+        .select((
+            exif_metadata::photo_id,
+            exif_metadata::latitude,
+            exif_metadata::longitude,
+        ))
+        .load(conn)?;
+
+    let entries: Vec<PhotoPlace> = coordinates
+        .into_iter()
+        .filter_map(|(photo_id, latitude, longitude)| {
+            let (place, distance) = index.nearest(latitude?, longitude?)?;
+            Some(PhotoPlace {
+                photo_id,
+                place_id: place.id,
+                distance_meters: distance,
+            })
+        })
+        .collect();
+
+    let located: Vec<Uuid> = entries.iter().map(|entry| entry.photo_id).collect();
+
+    conn.transaction::<_, anyhow::Error, _>(|conn| {
+        diesel::delete(
+            photo_places::table
+                .filter(photo_places::photo_id.eq_any(photo_ids))
+                .filter(photo_places::photo_id.ne_all(&located)),
+        )
+        .execute(conn)?;
+
+        for batch in entries.chunks(PLACE_BATCH_SIZE) {
+            diesel::insert_into(photo_places::table)
+                .values(batch)
+                .on_conflict(photo_places::photo_id)
+                .do_update()
+                .set((
+                    photo_places::place_id.eq(excluded(photo_places::place_id)),
+                    photo_places::distance_meters.eq(excluded(photo_places::distance_meters)),
+                ))
+                .execute(conn)?;
+        }
+        Ok(())
+    })?;
+
+    Ok(entries.len())
+}
+
+/// Geocodes the photos that have coordinates but no place yet, e.g. the ones imported before
+/// places were loaded. Photos too far from every city are looked up again on each call.
+pub fn assign_missing_places(conn: &mut DbPoolConn, index: &PlaceIndex) -> Result<usize> {
+    if index.is_empty() {
+        return Ok(0);
+    }
+
+    let mut assigned = 0;
+    let mut after: Option<Uuid> = None;
+    loop {
+        let mut query = exif_metadata::table
+            .left_join(photo_places::table.on(photo_places::photo_id.eq(exif_metadata::photo_id)))
+            .filter(exif_metadata::latitude.is_not_null())
+            .filter(photo_places::photo_id.nullable().is_null())
+            .select(exif_metadata::photo_id)
+            .order(exif_metadata::photo_id)
+            .limit(ASSIGN_BATCH_SIZE)
+            .into_boxed();
+
+        if let Some(last) = after {
+            query = query.filter(exif_metadata::photo_id.gt(last));
+        }
+
+        let photo_ids: Vec<Uuid> = query.load(conn)?;
+        let Some(&last) = photo_ids.last() else {
+            break;
+        };
+
+        assigned += assign_places(conn, index, &photo_ids)?;
+        after = Some(last);
+    }
+
+    Ok(assigned)
+}
+
+/// Countries, their regions and cities with the number of photos taken there, optionally
+/// restricted to one directory. Every level is sorted by name.
+pub fn get_place_tree(conn: &mut DbPoolConn, path_uuid: Option<Uuid>) -> Result<Vec<PlaceNode>> {
+    let mut query = photo_places::table
+        .inner_join(places::table)
+        .inner_join(photos::table)
+        .filter(photos::is_missing.eq(false))
+        .group_by(places::id)
+        .select((
+            Place::as_select(),
+            diesel::dsl::count(photo_places::photo_id),
+        ))
+        .into_boxed();
+
+    if let Some(path_id) = path_uuid {
+        query = query.filter(photos::path.eq(path_id));
+    }
+
+    let counts: Vec<(Place, i64)> = query.load(conn)?;
+
+    // By country code, along with the name of the country
+    let mut countries: BTreeMap<String, (String, RegionCities)> = BTreeMap::new();
+
+    for (place, photo_count) in counts {
+        let (_, regions) = countries
+            .entry(place.country_code.clone())
+            .or_insert_with(|| (place.country.clone(), BTreeMap::new()));
+
+        regions
+            .entry(place.region.clone())
+            .or_default()
+            .push(PlaceNode {
+                name: place.name,
+                filter: PlaceFilter {
+                    country_code: place.country_code,
+                    region: place.region,
+                    place_id: Some(place.id),
+                },
+                photo_count,
+                children: Vec::new(),
+            });
+    }
+
+    let mut tree: Vec<PlaceNode> = countries
+        .into_iter()
+        .map(|(country_code, (country, regions))| {
+            let mut children: Vec<PlaceNode> = Vec::new();
+            for (region, mut cities) in regions {
+                cities.sort_by(|a, b| a.name.cmp(&b.name));
+                match region {
+                    Some(region) => children.push(PlaceNode {
+                        name: region.clone(),
+                        filter: PlaceFilter {
+                            country_code: country_code.clone(),
+                            region: Some(region),
+                            place_id: None,
+                        },
+                        photo_count: cities.iter().map(|city| city.photo_count).sum(),
+                        children: cities,
+                    }),
+                    None => children.extend(cities),
+                }
+            }
+            children.sort_by(|a, b| a.name.cmp(&b.name));
+
+            PlaceNode {
+                name: country,
+                photo_count: children.iter().map(|child| child.photo_count).sum(),
+                filter: PlaceFilter {
+                    country_code,
+                    region: None,
+                    place_id: None,
+                },
+                children,
+            }
+        })
+        .collect();
+
+    tree.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(tree)
+}
@@ -0,0 +1,325 @@
+use crate::db::{DbPoolConn, insert_batch_size};
+use crate::schema::schema::photo_processing::dsl::photo_processing as processing_dsl;
+use crate::schema::schema::{directories, photo_processing, photos};
+use crate::schema::types::{ProcessingStage, ProcessingStatus};
+use crate::schema::{Directory, Photo};
+use anyhow::Result;
+use diesel::dsl::{IntervalDsl, exists, now, select};
+use diesel::pg::Pg;
+use diesel::prelude::*;
+use diesel::sql_types::Bool;
+use diesel::update;
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+/// A failed photo is claimed again until it has been attempted this many times.
+pub const MAX_ATTEMPTS: i32 = 3;
+
+/// Minutes a failed photo waits before it is claimed again, so a worker going through its
+/// queue doesn't retry the same photo right away.
+const RETRY_DELAY_MINUTES: i32 = 10;
+
+diesel::alias!(photo_processing as preview_processing: PreviewProcessing);
+
+type ClaimableFilter = Box<dyn BoxableExpression<photo_processing::table, Pg, SqlType = Bool>>;
+
+/// Rows of a photo and a stage inserted at once.
+const ENQUEUE_BATCH_SIZE: usize = insert_batch_size(2);
+
+/// Marks every stage of the given photos as pending, e.g. once they are inserted or their
+/// file changed on disk. Previous attempts and errors are forgotten.
+pub fn enqueue_photos(conn: &mut DbPoolConn, photo_ids: &[Uuid]) -> Result<()> {
+    if photo_ids.is_empty() {
+        return Ok(());
+    }
+
+    let rows: Vec<_> = photo_ids
+        .iter()
+        .flat_map(|photo_id| {
+            ProcessingStage::ALL.iter().map(move |stage| {
+                (
+                    photo_processing::photo_id.eq(*photo_id),
+                    photo_processing::stage.eq(*stage),
+                )
+            })
+        })
+        .collect();
+
+    conn.transaction::<_, anyhow::Error, _>(|conn| {
+        for batch in rows.chunks(ENQUEUE_BATCH_SIZE) {
+            diesel::insert_into(photo_processing::table)
+                .values(batch)
+                .on_conflict((photo_processing::photo_id, photo_processing::stage))
+                .do_update()
+                .set((
+                    photo_processing::status.eq(ProcessingStatus::Pending),
+                    photo_processing::attempts.eq(0),
+                    photo_processing::error.eq(None::<String>),
+                    photo_processing::model_version.eq(None::<String>),
+                    photo_processing::updated_at.eq(now),
+                ))
+                .execute(conn)?;
+        }
+
+        Ok(())
+    })
+}
+
+/// Marks `stage` as pending again for every photo of a directory, e.g. to run it with a new
+/// model. Returns the number of photos that will be processed again.
+pub fn requeue_directory_stage(
+    conn: &mut DbPoolConn,
+    dir_id: &Uuid,
+    stage: ProcessingStage,
+) -> Result<usize> {
+    let photo_ids = photos::table
+        .filter(photos::path.eq(dir_id))
+        .select(photos::id);
+
+    let requeued = update(
+        processing_dsl
+            .filter(photo_processing::stage.eq(stage))
+            .filter(photo_processing::photo_id.eq_any(photo_ids)),
+    )
+    .set((
+        photo_processing::status.eq(ProcessingStatus::Pending),
+        photo_processing::attempts.eq(0),
+        photo_processing::error.eq(None::<String>),
+        photo_processing::model_version.eq(None::<String>),
+        photo_processing::updated_at.eq(now),
+    ))
+    .execute(conn)?;
+
+    Ok(requeued)
+}
+
+/// Puts back the photos left running by a worker that stopped in the middle of a stage.
+/// Meant to be called by the owner of the stages before it starts claiming work.
+pub fn release_interrupted(conn: &mut DbPoolConn, stages: &[ProcessingStage]) -> Result<usize> {
+    let released = update(
+        processing_dsl
+            .filter(photo_processing::stage.eq_any(stages))
+            .filter(photo_processing::status.eq(ProcessingStatus::Running)),
+    )
+    .set((
+        photo_processing::status.eq(ProcessingStatus::Pending),
+        photo_processing::updated_at.eq(now),
+    ))
+    .execute(conn)?;
+
+    Ok(released)
+}
+
+/// The rows of `stage` that can be claimed: pending, or failed with attempts left and not
+/// retried too recently. The stages after the preview also wait for the preview of the photo,
+/// they work from it.
+fn claimable(stage: ProcessingStage) -> ClaimableFilter {
+    let ready = photo_processing::stage.eq(stage).and(
+        photo_processing::status
+            .eq(ProcessingStatus::Pending)
+            .or(photo_processing::status
+                .eq(ProcessingStatus::Failed)
+                .and(photo_processing::attempts.lt(MAX_ATTEMPTS))
+                .and(photo_processing::updated_at.lt(now - RETRY_DELAY_MINUTES.minutes()))),
+    );
+
+    if stage == ProcessingStage::Preview {
+        return Box::new(ready);
+    }
+
+    let preview_done = exists(
+        preview_processing
+            .filter(
+                preview_processing
+                    .field(photo_processing::photo_id)
+                    .eq(photo_processing::photo_id),
+            )
+            .filter(
+                preview_processing
+                    .field(photo_processing::stage)
+                    .eq(ProcessingStage::Preview),
+            )
+            .filter(
+                preview_processing
+                    .field(photo_processing::status)
+                    .eq(ProcessingStatus::Done),
+            ),
+    );
+    Box::new(ready.and(preview_done))
+}
+
+/// The directories with photos waiting for `stage`.
+pub fn get_directories_with_claimable(
+    conn: &mut DbPoolConn,
+    stage: ProcessingStage,
+) -> Result<Vec<Directory>> {
+    let dirs: Vec<Directory> = directories::table
+        .select(Directory::as_select())
+        .order(directories::id)
+        .load(conn)?;
+
+    let mut claimable_dirs = Vec::new();
+    for dir in dirs {
+        let dir_photos = photos::table
+            .filter(photos::path.eq(dir.id))
+            .filter(photos::is_missing.eq(false))
+            .select(photos::id);
+
+        let has_work: bool = select(exists(
+            processing_dsl
+                .filter(claimable(stage))
+                .filter(photo_processing::photo_id.eq_any(dir_photos)),
+        ))
+        .get_result(conn)?;
+
+        if has_work {
+            claimable_dirs.push(dir);
+        }
+    }
+
+    Ok(claimable_dirs)
+}
+
+/// Fingerprint of the work that can be claimed for the given stages, which changes when photos
+/// become ready for one of them, e.g. once their preview is created.
+pub fn hash_claimable_work(conn: &mut DbPoolConn, stages: &[ProcessingStage]) -> Result<String> {
+    let mut hasher = Sha256::new();
+
+    for stage in stages {
+        hasher.update(stage.as_str());
+        for dir in get_directories_with_claimable(conn, *stage)? {
+            hasher.update(dir.id.as_bytes());
+        }
+    }
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Claims up to `limit` photos of a directory that still have to go through `stage`, marking
+/// them as running. Rows locked by another worker are skipped instead of waited on.
+pub fn claim_photos(
+    conn: &mut DbPoolConn,
+    dir_id: Uuid,
+    stage: ProcessingStage,
+    limit: i64,
+) -> Result<Vec<Photo>> {
+    conn.transaction::<_, anyhow::Error, _>(|conn| {
+        let dir_photos = photos::table
+            .filter(photos::path.eq(dir_id))
+            .filter(photos::is_missing.eq(false))
+            .select(photos::id);
+
+        let claimed: Vec<Uuid> = processing_dsl
+            .filter(claimable(stage))
+            .filter(photo_processing::photo_id.eq_any(dir_photos))
+            .select(photo_processing::photo_id)
+            .limit(limit)
+            .for_update()
+            .skip_locked()
+            .load(conn)?;
+
+        if claimed.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        update(
+            processing_dsl
+                .filter(photo_processing::stage.eq(stage))
+                .filter(photo_processing::photo_id.eq_any(&claimed)),
+        )
+        .set((
+            photo_processing::status.eq(ProcessingStatus::Running),
+            photo_processing::attempts.eq(photo_processing::attempts + 1),
+            photo_processing::updated_at.eq(now),
+        ))
+        .execute(conn)?;
+
+        let photos = photos::table
+            .filter(photos::id.eq_any(&claimed))
+            .select(Photo::as_select())
+            .load(conn)?;
+
+        Ok(photos)
+    })
+}
+
+/// Records the outcome of the claimed photos: the successful ones are done, the others failed
+/// with their error message and will be retried until they reach `MAX_ATTEMPTS`.
+pub fn finish_photos(
+    conn: &mut DbPoolConn,
+    stage: ProcessingStage,
+    model_version: Option<&str>,
+    outcomes: Vec<(Uuid, Result<()>)>,
+) -> Result<()> {
+    let mut done: Vec<Uuid> = Vec::new();
+    let mut failed: Vec<(Uuid, String)> = Vec::new();
+
+    for (photo_id, outcome) in outcomes {
+        match outcome {
+            Ok(_) => done.push(photo_id),
+            Err(err) => {
+                tracing::error!("Stage {} failed for photo {}: {:#}", stage, photo_id, err);
+                failed.push((photo_id, format!("{:#}", err)));
+            }
+        }
+    }
+
+    conn.transaction::<_, anyhow::Error, _>(|conn| {
+        if !done.is_empty() {
+            update(
+                processing_dsl
+                    .filter(photo_processing::stage.eq(stage))
+                    .filter(photo_processing::photo_id.eq_any(&done)),
+            )
+            .set((
+                photo_processing::status.eq(ProcessingStatus::Done),
+                photo_processing::error.eq(None::<String>),
+                photo_processing::model_version.eq(model_version),
+                photo_processing::updated_at.eq(now),
+            ))
+            .execute(conn)?;
+        }
+
+        for (photo_id, error) in &failed {
+            update(
+                processing_dsl
+                    .filter(photo_processing::stage.eq(stage))
+                    .filter(photo_processing::photo_id.eq(photo_id)),
+            )
+            .set((
+                photo_processing::status.eq(ProcessingStatus::Failed),
+                photo_processing::error.eq(error),
+                photo_processing::model_version.eq(model_version),
+                photo_processing::updated_at.eq(now),
+            ))
+            .execute(conn)?;
+        }
+
+        Ok(())
+    })
+}
+
+/// Counts the photos of a directory for which `stage` isn't settled yet: pending, running, or
+/// failed with attempts left.
+pub fn count_unfinished(
+    conn: &mut DbPoolConn,
+    dir_id: Uuid,
+    stage: ProcessingStage,
+) -> Result<i64> {
+    let count = processing_dsl
+        .inner_join(photos::table)
+        .filter(photos::path.eq(dir_id))
+        .filter(photos::is_missing.eq(false))
+        .filter(photo_processing::stage.eq(stage))
+        .filter(
+            photo_processing::status
+                .eq_any([ProcessingStatus::Pending, ProcessingStatus::Running])
+                .or(photo_processing::status
+                    .eq(ProcessingStatus::Failed)
+                    .and(photo_processing::attempts.lt(MAX_ATTEMPTS))),
+        )
+        .count()
+        .get_result(conn)?;
+
+    Ok(count)
+}
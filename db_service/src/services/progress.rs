@@ -0,0 +1,110 @@
+use crate::db::DbPoolConn;
+use crate::schema::ProcessingProgress;
+use crate::schema::schema::processing_progress;
+use crate::schema::schema::processing_progress::dsl::processing_progress as progress_dsl;
+use crate::schema::types::ProcessingStage;
+use anyhow::Result;
+use diesel::dsl::now;
+use diesel::prelude::*;
+use diesel::update;
+use uuid::Uuid;
+
+/// Starts reporting a stage for a directory, forgetting the progress of any previous run.
+pub fn start_progress(
+    conn: &mut DbPoolConn,
+    dir_id: &Uuid,
+    stage: ProcessingStage,
+    total: i64,
+) -> Result<()> {
+    diesel::insert_into(processing_progress::table)
+        .values((
+            processing_progress::directory_id.eq(dir_id),
+            processing_progress::stage.eq(stage),
+            processing_progress::total.eq(total as i32),
+        ))
+        .on_conflict((
+            processing_progress::directory_id,
+            processing_progress::stage,
+        ))
+        .do_update()
+        .set((
+            processing_progress::done.eq(0),
+            processing_progress::total.eq(total as i32),
+            processing_progress::errors.eq(0),
+            processing_progress::current_file.eq(None::<String>),
+            processing_progress::last_error.eq(None::<String>),
+            processing_progress::finished.eq(false),
+            processing_progress::started_at.eq(now),
+            processing_progress::updated_at.eq(now),
+        ))
+        .execute(conn)?;
+
+    Ok(())
+}
+
+/// Adds a processed batch to the progress of a stage. `done` includes the failed photos.
+pub fn record_progress(
+    conn: &mut DbPoolConn,
+    dir_id: &Uuid,
+    stage: ProcessingStage,
+    done: usize,
+    errors: usize,
+    current_file: Option<&str>,
+    last_error: Option<&str>,
+) -> Result<()> {
+    let target = progress_dsl
+        .filter(processing_progress::directory_id.eq(dir_id))
+        .filter(processing_progress::stage.eq(stage));
+
+    // A single transaction, so listeners get one notification for the batch
+    conn.transaction::<_, anyhow::Error, _>(|conn| {
+        update(target)
+            .set((
+                processing_progress::done.eq(processing_progress::done + done as i32),
+                processing_progress::errors.eq(processing_progress::errors + errors as i32),
+                processing_progress::current_file.eq(current_file),
+                processing_progress::updated_at.eq(now),
+            ))
+            .execute(conn)?;
+
+        // Keep the previous error around until a newer one comes in
+        if let Some(error) = last_error {
+            update(target)
+                .set(processing_progress::last_error.eq(error))
+                .execute(conn)?;
+        }
+
+        Ok(())
+    })
+}
+
+pub fn finish_progress(conn: &mut DbPoolConn, dir_id: &Uuid, stage: ProcessingStage) -> Result<()> {
+    update(
+        progress_dsl
+            .filter(processing_progress::directory_id.eq(dir_id))
+            .filter(processing_progress::stage.eq(stage)),
+    )
+    .set((
+        processing_progress::finished.eq(true),
+        processing_progress::current_file.eq(None::<String>),
+        processing_progress::updated_at.eq(now),
+    ))
+    .execute(conn)?;
+
+    Ok(())
+}
+
+pub fn get_progress(
+    conn: &mut DbPoolConn,
+    dir_id: &Uuid,
+    stage: ProcessingStage,
+) -> Result<Option<ProcessingProgress>> {
+    let progress = progress_dsl
+        .filter(processing_progress::directory_id.eq(dir_id))
+        .filter(processing_progress::stage.eq(stage))
+        .select(ProcessingProgress::as_select())
+        .first(conn)
+        .optional()?;
+
+    Ok(progress)
+}
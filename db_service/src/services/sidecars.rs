@@ -0,0 +1,313 @@
+use crate::db::DbPoolConn;
+use crate::schema::schema::{
+    clusters, directories, exif_metadata, face_embeddings, photo_tags_mappings, photos,
+};
+use crate::schema::types::TagSource;
+use crate::schema::{
+    Directory, FaceRegion, NewPhotoTagMapping, Photo, PhotoCulling, PhotoCullingUpdate,
+};
+use crate::services::metadata::{MetadataFailure, MetadataReport};
+use crate::services::tags::{MAX_TAG_LENGTH, TAG_BATCH_SIZE};
+use crate::xmp::{
+    XmpFace, XmpSidecar, XmpUpdate, find_sidecar, read_sidecar, sidecar_paths, write_sidecar,
+};
+use anyhow::{Context, Result};
+use diesel::prelude::*;
+use rayon::prelude::*;
+use std::collections::HashMap;
+use std::path::Path;
+use uuid::Uuid;
+
+/// The width and height of a photo, then its latitude and longitude.
+type ExifGeometry = (Option<i32>, Option<i32>, Option<f64>, Option<f64>);
+
+/// Reads the sidecars of newly found photos into the catalog: their rating, reject flag and
+/// color label, and their keywords as tags. Faces are only detected later and can't be matched
+/// to the regions of a sidecar, so the people named in them become tags of their own source,
+/// which aren't written back as keywords. Sidecars that can't be read are skipped. Returns the
+/// number of photos that had one.
+pub fn import_sidecars(conn: &mut DbPoolConn, dir: &Directory, photos: &[Photo]) -> Result<usize> {
+    let sidecars: Vec<(Uuid, XmpSidecar)> = photos
+        .par_iter()
+        .filter_map(|photo| {
+            let sidecar_path = find_sidecar(&Path::new(&dir.path).join(&photo.name))?;
+
+            match read_sidecar(&sidecar_path) {
+                Ok(sidecar) => Some((photo.id, sidecar)),
+                Err(err) => {
+                    tracing::warn!("Skipping a sidecar: {:#}", err);
+                    None
+                }
+            }
+        })
+        .collect();
+
+    conn.transaction::<_, anyhow::Error, _>(|conn| {
+        let mut tags = Vec::new();
+
+        for (photo_id, sidecar) in &sidecars {
+            if sidecar.rating.is_some() || sidecar.flag.is_some() || sidecar.color_label.is_some() {
+                diesel::update(photos::table.find(photo_id))
+                    .set(&PhotoCullingUpdate {
+                        rating: sidecar.rating,
+                        flag: sidecar.flag,
+                        color_label: sidecar.color_label,
+                    })
+                    .execute(conn)?;
+            }
+
+            let keywords = sidecar.keywords.iter().map(|tag| (tag, TagSource::Sidecar));
+            let face_names = sidecar.face_names.iter().map(|tag| (tag, TagSource::Face));
+
+            tags.extend(
+                keywords
+                    .chain(face_names)
+                    .filter(|(tag, _)| tag.chars().count() <= MAX_TAG_LENGTH)
+                    .map(|(tag, source)| NewPhotoTagMapping {
+                        id: Uuid::new_v4(),
+                        tag: tag.clone(),
+                        photo_id: *photo_id,
+                        source,
+                    }),
+            );
+        }
+
+        for batch in tags.chunks(TAG_BATCH_SIZE) {
+            diesel::insert_into(photo_tags_mappings::table)
+                .values(batch)
+                .on_conflict_do_nothing()
+                .execute(conn)?;
+        }
+
+        Ok(())
+    })?;
+
+    tracing::info!("Imported the sidecars of {} photos", sidecars.len());
+    Ok(sidecars.len())
+}
+
+/// Writes the rating, flag, color label and keywords of the photos into their sidecars. Named
+/// faces and the position are only written when the catalog knows them, so the ones set in
+/// another application are kept. Detected objects and the names of imported face regions stay
+/// in the catalog, they aren't keywords.
+///
+/// Photos without a sidecar get one named like darktable does, `IMG_1.CR2.xmp`, so a raw file
+/// and its JPEG never share it. Sidecars that can't be written don't stop the others, they are
+/// returned in the report.
+pub fn write_sidecars(conn: &mut DbPoolConn, photo_ids: &[Uuid]) -> Result<MetadataReport> {
+    let photos: Vec<(String, String, PhotoCulling)> = photos::table
+        .inner_join(directories::table)
+        .filter(photos::id.eq_any(photo_ids))
+        .filter(photos::is_missing.eq(false))
+        .select((directories::path, photos::name, PhotoCulling::as_select()))
+        .load(conn)?;
+
+    let exif: HashMap<Uuid, ExifGeometry> = exif_metadata::table
+        .filter(exif_metadata::photo_id.eq_any(photo_ids))
+        .select((
+            exif_metadata::photo_id,
+            (
+                exif_metadata::pixel_x_dimension,
+                exif_metadata::pixel_y_dimension,
+                exif_metadata::latitude,
+                exif_metadata::longitude,
+            ),
+        ))
+        .load::<(Uuid, ExifGeometry)>(conn)?
+        .into_iter()
+        .collect();
+
+    let mut keywords: HashMap<Uuid, Vec<String>> = HashMap::new();
+    let tags: Vec<(Uuid, String)> = photo_tags_mappings::table
+        .filter(photo_tags_mappings::photo_id.eq_any(photo_ids))
+        .filter(photo_tags_mappings::source.ne_all([TagSource::Detection, TagSource::Face]))
+        .select((photo_tags_mappings::photo_id, photo_tags_mappings::tag))
+        .distinct()
+        .order((photo_tags_mappings::photo_id, photo_tags_mappings::tag))
+        .load(conn)?;
+    for (photo_id, tag) in tags {
+        keywords.entry(photo_id).or_default().push(tag);
+    }
+
+    let mut faces: HashMap<Uuid, Vec<XmpFace>> = HashMap::new();
+    let named_faces: Vec<(Uuid, String, f32, f32, f32, f32)> = face_embeddings::table
+        .inner_join(clusters::table.on(face_embeddings::cluster_id.eq(clusters::id.nullable())))
+        .filter(face_embeddings::photo_id.eq_any(photo_ids))
+        .filter(clusters::name.is_not_null())
+        .filter(face_embeddings::region_x.is_not_null())
+        .select((
+            face_embeddings::photo_id,
+            clusters::name.assume_not_null(),
+            face_embeddings::region_x.assume_not_null(),
+            face_embeddings::region_y.assume_not_null(),
+            face_embeddings::region_width.assume_not_null(),
+            face_embeddings::region_height.assume_not_null(),
+        ))
+        .load(conn)?;
+    for (photo_id, name, x, y, width, height) in named_faces {
+        faces.entry(photo_id).or_default().push(XmpFace {
+            name,
+            region: FaceRegion {
+                x,
+                y,
+                width,
+                height,
+            },
+        });
+    }
+
+    let mut report = MetadataReport::default();
+
+    for (directory_path, name, culling) in photos {
+        let (width, height, latitude, longitude) =
+            exif.get(&culling.photo_id).copied().unwrap_or_default();
+
+        let update = XmpUpdate {
+            rating: culling.rating,
+            flag: culling.flag,
+            color_label: culling.color_label,
+            keywords: Some(keywords.remove(&culling.photo_id).unwrap_or_default()),
+            faces: faces.remove(&culling.photo_id),
+            dimensions: width.zip(height),
+            position: latitude.zip(longitude),
+        };
+
+        let photo_path = Path::new(&directory_path).join(&name);
+        let [darktable_path, _] = sidecar_paths(&photo_path);
+        let sidecar_path = find_sidecar(&photo_path).unwrap_or(darktable_path);
+
+        match write_sidecar(&sidecar_path, &update) {
+            Ok(()) => report.saved += 1,
+            Err(err) => {
+                tracing::warn!("Cannot write the sidecar of {}: {:#}", name, err);
+                report.failures.push(MetadataFailure {
+                    photo_id: culling.photo_id,
+                    name,
+                    error: format!("{:#}", err),
+                });
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+/// Writes the sidecars of the photos that are in a directory kept in sync, see
+/// [`set_xmp_sync`]. Meant to be called after every change made in the catalog.
+pub fn sync_sidecars(conn: &mut DbPoolConn, photo_ids: &[Uuid]) -> Result<MetadataReport> {
+    let synced: Vec<Uuid> = photos::table
+        .inner_join(directories::table)
+        .filter(photos::id.eq_any(photo_ids))
+        .filter(directories::xmp_sync.eq(true))
+        .select(photos::id)
+        .load(conn)?;
+
+    if synced.is_empty() {
+        return Ok(MetadataReport::default());
+    }
+
+    write_sidecars(conn, &synced)
+}
+
+/// Writes the sidecars of every photo of a directory.
+pub fn write_directory_sidecars(conn: &mut DbPoolConn, dir_id: &Uuid) -> Result<MetadataReport> {
+    let photo_ids: Vec<Uuid> = photos::table
+        .filter(photos::path.eq(dir_id))
+        .filter(photos::is_missing.eq(false))
+        .select(photos::id)
+        .load(conn)?;
+
+    write_sidecars(conn, &photo_ids)
+}
+
+/// Starts or stops writing the changes made to the photos of a directory into their sidecars.
+/// Nothing is written yet, see [`write_directory_sidecars`].
+pub fn set_xmp_sync(conn: &mut DbPoolConn, dir_id: &Uuid, xmp_sync: bool) -> Result<Directory> {
+    diesel::update(directories::table.find(dir_id))
+        .set(directories::xmp_sync.eq(xmp_sync))
+        .returning(Directory::as_returning())
+        .get_result(conn)
+        .optional()?
+        .with_context(|| format!("No directory with id {}", dir_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::tests::test_connection;
+    use crate::schema::NewDirectory;
+    use crate::services::directory::insert_directory;
+    use crate::services::photo::insert_photos_from_directory;
+    use crate::xmp::XmpFace;
+    use std::{env, fs};
+
+    #[test]
+    fn face_names_are_not_written_as_keywords() {
+        let Some(mut conn) = test_connection() else {
+            eprintln!("No database to test against, skipping");
+            return;
+        };
+        let conn = &mut conn;
+
+        let folder = env::temp_dir().join(Uuid::new_v4().to_string());
+        fs::create_dir(&folder).unwrap();
+        let photo_path = folder.join("exif.jpg");
+        fs::copy(
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/exif.jpg"),
+            &photo_path,
+        )
+        .unwrap();
+        let [sidecar_path, _] = sidecar_paths(&photo_path);
+        let face = XmpFace {
+            name: "Alice".to_string(),
+            region: FaceRegion {
+                x: 0.25,
+                y: 0.5,
+                width: 0.1,
+                height: 0.2,
+            },
+        };
+        let update = XmpUpdate {
+            keywords: Some(vec!["Paris".to_string()]),
+            faces: Some(vec![face]),
+            dimensions: Some((100, 100)),
+            ..XmpUpdate::default()
+        };
+        write_sidecar(&sidecar_path, &update).unwrap();
+
+        let dir = insert_directory(
+            conn,
+            NewDirectory {
+                id: Uuid::new_v4(),
+                path: folder.to_string_lossy().into_owned(),
+                photo_count: 0,
+            },
+        )
+        .unwrap();
+        insert_photos_from_directory(conn, &dir).unwrap();
+
+        let tags: Vec<(String, TagSource)> = photo_tags_mappings::table
+            .inner_join(photos::table)
+            .filter(photos::path.eq(dir.id))
+            .select((photo_tags_mappings::tag, photo_tags_mappings::source))
+            .order(photo_tags_mappings::tag)
+            .load(conn)
+            .unwrap();
+        assert_eq!(
+            tags,
+            [
+                ("Alice".to_string(), TagSource::Face),
+                ("Paris".to_string(), TagSource::Sidecar)
+            ]
+        );
+
+        let report = write_directory_sidecars(conn, &dir.id).unwrap();
+        let sidecar = read_sidecar(&sidecar_path).unwrap();
+        fs::remove_dir_all(&folder).unwrap();
+
+        assert_eq!(report.saved, 1);
+        assert_eq!(sidecar.keywords, ["Paris"]);
+        // The region the name came from is left alone
+        assert_eq!(sidecar.face_names, ["Alice"]);
+    }
+}
@@ -0,0 +1,196 @@
+use crate::db::DbPoolConn;
+use crate::schema::schema::photos::dsl::photos as photos_dsl;
+use crate::schema::schema::{exif_metadata, photo_processing, photos};
+use crate::schema::types::{ProcessingStage, ProcessingStatus};
+use crate::schema::{Photo, SimilarPhoto};
+use anyhow::Result;
+use diesel::prelude::*;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// Default Hamming distance under which two perceptual hashes are considered similar.
+pub const DEFAULT_MAX_DISTANCE: u32 = 10;
+
+/// The photos of a directory that have a preview, the hash is computed from it, but no
+/// perceptual hash yet.
+pub fn get_photos_without_perceptual_hash(
+    conn: &mut DbPoolConn,
+    path_uuid: Uuid,
+) -> Result<Vec<Photo>> {
+    let with_preview = photo_processing::table
+        .filter(photo_processing::stage.eq(ProcessingStage::Preview))
+        .filter(photo_processing::status.eq(ProcessingStatus::Done))
+        .select(photo_processing::photo_id);
+
+    let results = photos_dsl
+        .filter(photos::path.eq(path_uuid))
+        .filter(photos::is_missing.eq(false))
+        .filter(photos::perceptual_hash.is_null())
+        .filter(photos::id.eq_any(with_preview))
+        .select(Photo::as_select())
+        .load(conn)?;
+
+    Ok(results)
+}
+
+pub fn set_perceptual_hashes(conn: &mut DbPoolConn, hashes: &[(Uuid, u64)]) -> Result<()> {
+    conn.transaction::<_, anyhow::Error, _>(|conn| {
+        for (photo_id, hash) in hashes {
+            // Stored as the same 64 bits, Postgres has no unsigned integers
+            diesel::update(photos_dsl.filter(photos::id.eq(photo_id)))
+                .set(photos::perceptual_hash.eq(*hash as i64))
+                .execute(conn)?;
+        }
+        Ok(())
+    })
+}
+
+fn hamming_distance(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+/// A BK-tree over the Hamming distance, so finding the neighbours of a hash doesn't require
+/// comparing it with every other photo of the catalog.
+struct BkTree {
+    nodes: Vec<BkNode>,
+}
+
+struct BkNode {
+    hash: u64,
+    index: usize,
+    children: HashMap<u32, usize>,
+}
+
+impl BkTree {
+    fn new() -> Self {
+        BkTree { nodes: Vec::new() }
+    }
+
+    fn insert(&mut self, hash: u64, index: usize) {
+        let new_node = BkNode {
+            hash,
+            index,
+            children: HashMap::new(),
+        };
+
+        if self.nodes.is_empty() {
+            self.nodes.push(new_node);
+            return;
+        }
+
+        let mut current = 0;
+        loop {
+            let distance = hamming_distance(self.nodes[current].hash, hash);
+            match self.nodes[current].children.get(&distance) {
+                Some(&child) => current = child,
+                None => {
+                    let new_index = self.nodes.len();
+                    self.nodes.push(new_node);
+                    self.nodes[current].children.insert(distance, new_index);
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Indexes of all the inserted hashes within `max_distance` of `hash`.
+    fn find(&self, hash: u64, max_distance: u32) -> Vec<usize> {
+        let mut found = Vec::new();
+        if self.nodes.is_empty() {
+            return found;
+        }
+
+        let mut stack = vec![0];
+        while let Some(current) = stack.pop() {
+            let node = &self.nodes[current];
+            let distance = hamming_distance(node.hash, hash);
+
+            if distance <= max_distance {
+                found.push(node.index);
+            }
+
+            // Triangle inequality: only these subtrees can contain matches
+            let low = distance.saturating_sub(max_distance);
+            let high = distance + max_distance;
+            stack.extend(
+                node.children
+                    .iter()
+                    .filter(|(child_distance, _)| (low..=high).contains(*child_distance))
+                    .map(|(_, &child)| child),
+            );
+        }
+
+        found
+    }
+}
+
+fn find_root(parents: &mut [usize], mut index: usize) -> usize {
+    while parents[index] != index {
+        parents[index] = parents[parents[index]];
+        index = parents[index];
+    }
+    index
+}
+
+/// Groups photos whose perceptual hashes are within `max_distance` of each other, transitively,
+/// so a burst ends up in a single group. Photos in a group are ordered by capture time, and
+/// groups with a single photo are left out.
+pub fn get_similar_photo_groups(
+    conn: &mut DbPoolConn,
+    path_uuid: Option<Uuid>,
+    max_distance: u32,
+) -> Result<Vec<Vec<SimilarPhoto>>> {
+    let mut query = photos_dsl
+        .left_join(exif_metadata::table)
+        .filter(photos::is_missing.eq(false))
+        .filter(photos::perceptual_hash.is_not_null())
+        .select(SimilarPhoto::as_select())
+        .into_boxed();
+
+    if let Some(path_id) = path_uuid {
+        query = query.filter(photos::path.eq(path_id));
+    }
+
+    let candidates: Vec<SimilarPhoto> = query.load(conn)?;
+    let hashes: Vec<u64> = candidates
+        .iter()
+        .map(|photo| photo.perceptual_hash.unwrap_or_default() as u64)
+        .collect();
+
+    let mut tree = BkTree::new();
+    let mut parents: Vec<usize> = (0..candidates.len()).collect();
+
+    for (index, &hash) in hashes.iter().enumerate() {
+        for neighbour in tree.find(hash, max_distance) {
+            let a = find_root(&mut parents, index);
+            let b = find_root(&mut parents, neighbour);
+            if a != b {
+                parents[a] = b;
+            }
+        }
+        tree.insert(hash, index);
+    }
+
+    let mut grouped: HashMap<usize, Vec<SimilarPhoto>> = HashMap::new();
+    for (index, photo) in candidates.into_iter().enumerate() {
+        let root = find_root(&mut parents, index);
+        grouped.entry(root).or_default().push(photo);
+    }
+
+    let mut groups: Vec<Vec<SimilarPhoto>> = grouped
+        .into_values()
+        .filter(|group| group.len() > 1)
+        .map(|mut group| {
+            group.sort_by(|a, b| {
+                (a.date_time_original, &a.name).cmp(&(b.date_time_original, &b.name))
+            });
+            group
+        })
+        .collect();
+
+    groups.sort_by(|a, b| {
+        (a[0].date_time_original, &a[0].name).cmp(&(b[0].date_time_original, &b[0].name))
+    });
+
+    Ok(groups)
+}
@@ -0,0 +1,115 @@
+use crate::db::DbPoolConn;
+use crate::schema::schema::smart_albums;
+use crate::schema::{SmartAlbum, SmartAlbumRow};
+use crate::services::photo::{PhotoQuery, count_photos_filtered};
+use anyhow::{Context, Result, bail};
+use chrono::Utc;
+use diesel::prelude::*;
+use uuid::Uuid;
+
+/// Longest name the `smart_albums` table accepts.
+const MAX_NAME_LENGTH: usize = 255;
+
+fn validate_name(name: &str) -> Result<&str> {
+    let name = name.trim();
+    if name.is_empty() {
+        bail!("A smart album needs a name");
+    }
+    if name.chars().count() > MAX_NAME_LENGTH {
+        bail!(
+            "Smart album names are limited to {} characters",
+            MAX_NAME_LENGTH
+        );
+    }
+
+    Ok(name)
+}
+
+/// Queries are read back with the current `PhotoQuery`, criteria added since an album was saved
+/// are left empty.
+fn album_query(row: &SmartAlbumRow) -> Result<PhotoQuery> {
+    serde_json::from_value(row.query.clone())
+        .with_context(|| format!("Invalid query in smart album {}", row.name))
+}
+
+fn with_count(conn: &mut DbPoolConn, row: SmartAlbumRow) -> Result<SmartAlbum> {
+    let query = album_query(&row)?;
+    let photo_count = count_photos_filtered(conn, &query)?;
+
+    Ok(SmartAlbum {
+        id: row.id,
+        name: row.name,
+        query,
+        photo_count,
+    })
+}
+
+pub fn create_smart_album(
+    conn: &mut DbPoolConn,
+    name: &str,
+    query: &PhotoQuery,
+) -> Result<SmartAlbum> {
+    let now = Utc::now().naive_utc();
+    let row = diesel::insert_into(smart_albums::table)
+        .values(&SmartAlbumRow {
+            id: Uuid::new_v4(),
+            name: validate_name(name)?.to_string(),
+            query: serde_json::to_value(query)?,
+            created_at: now,
+            updated_at: now,
+        })
+        .returning(SmartAlbumRow::as_returning())
+        .get_result(conn)?;
+
+    with_count(conn, row)
+}
+
+/// Renames an album and replaces its query.
+pub fn update_smart_album(
+    conn: &mut DbPoolConn,
+    album_id: &Uuid,
+    name: &str,
+    query: &PhotoQuery,
+) -> Result<SmartAlbum> {
+    let row = diesel::update(smart_albums::table.find(album_id))
+        .set((
+            smart_albums::name.eq(validate_name(name)?),
+            smart_albums::query.eq(serde_json::to_value(query)?),
+            smart_albums::updated_at.eq(Utc::now().naive_utc()),
+        ))
+        .returning(SmartAlbumRow::as_returning())
+        .get_result(conn)
+        .optional()?
+        .with_context(|| format!("No smart album with id {}", album_id))?;
+
+    with_count(conn, row)
+}
+
+pub fn delete_smart_album(conn: &mut DbPoolConn, album_id: &Uuid) -> Result<usize> {
+    let deleted = diesel::delete(smart_albums::table.find(album_id)).execute(conn)?;
+
+    Ok(deleted)
+}
+
+/// Every album by name, with the number of photos matching it right now.
+pub fn get_smart_albums(conn: &mut DbPoolConn) -> Result<Vec<SmartAlbum>> {
+    let rows = smart_albums::table
+        .select(SmartAlbumRow::as_select())
+        .order(smart_albums::name)
+        .load(conn)?;
+
+    rows.into_iter().map(|row| with_count(conn, row)).collect()
+}
+
+/// The saved query of an album, evaluated like any search with
+/// [`get_photos_filtered`](crate::services::photo::get_photos_filtered).
+pub fn get_smart_album_query(conn: &mut DbPoolConn, album_id: &Uuid) -> Result<PhotoQuery> {
+    let row = smart_albums::table
+        .find(album_id)
+        .select(SmartAlbumRow::as_select())
+        .first(conn)
+        .optional()?
+        .with_context(|| format!("No smart album with id {}", album_id))?;
+
+    album_query(&row)
+}
@@ -1,10 +1,17 @@
-use crate::db::DbPoolConn;
+use crate::db::{DbPoolConn, insert_batch_size};
+use crate::schema::types::TagSource;
 use crate::schema::{NewPhotoTagMapping, Photo};
-use anyhow::Result;
+use anyhow::{Result, bail};
 use diesel::prelude::*;
 use std::collections::HashSet;
 use uuid::Uuid;
 
+/// Longest tag the `photo_tags_mappings` table accepts.
+pub const MAX_TAG_LENGTH: usize = 255;
+
+/// Rows of the 4 columns of a [`NewPhotoTagMapping`] inserted at once.
+pub(crate) const TAG_BATCH_SIZE: usize = insert_batch_size(4);
+
 /// A simple detection structure.
 #[derive(Debug, PartialEq, Eq, Hash)]
 pub struct Detection {
@@ -30,13 +37,80 @@ pub fn insert_photo_tags_mappings(
                 id: Uuid::new_v4(),
                 tag: detection.label,
                 photo_id: photo.id,
+                source: TagSource::Detection,
             });
         }
     }
 
-    diesel::insert_into(photo_tags_mappings::table)
-        .values(&new_mappings)
-        .execute(conn)
+    let mut inserted = 0;
+    for batch in new_mappings.chunks(TAG_BATCH_SIZE) {
+        inserted += diesel::insert_into(photo_tags_mappings::table)
+            .values(batch)
+            .execute(conn)?;
+    }
+
+    Ok(inserted)
+}
+
+/// Tags every photo by hand, the tags a photo already has are skipped. Returns the number of
+/// tags added.
+pub fn add_photo_tags(conn: &mut DbPoolConn, photo_ids: &[Uuid], tags: &[String]) -> Result<usize> {
+    use crate::schema::schema::photo_tags_mappings;
+
+    let tags: Vec<&str> = tags
+        .iter()
+        .map(|tag| tag.trim())
+        .filter(|tag| !tag.is_empty())
+        .collect();
+    if tags.is_empty() {
+        bail!("No tag to add");
+    }
+    if let Some(tag) = tags.iter().find(|tag| tag.chars().count() > MAX_TAG_LENGTH) {
+        bail!("Tags are limited to {} characters: {}", MAX_TAG_LENGTH, tag);
+    }
+
+    let new_mappings: Vec<NewPhotoTagMapping> = photo_ids
+        .iter()
+        .flat_map(|photo_id| {
+            tags.iter().map(|tag| NewPhotoTagMapping {
+                id: Uuid::new_v4(),
+                tag: tag.to_string(),
+                photo_id: *photo_id,
+                source: TagSource::Manual,
+            })
+        })
+        .collect();
+
+    conn.transaction::<_, anyhow::Error, _>(|conn| {
+        let mut added = 0;
+        for batch in new_mappings.chunks(TAG_BATCH_SIZE) {
+            added += diesel::insert_into(photo_tags_mappings::table)
+                .values(batch)
+                .on_conflict_do_nothing()
+                .execute(conn)?;
+        }
+
+        Ok(added)
+    })
+}
+
+/// Removes tags from every photo, whatever their source. Detected ones come back when the
+/// directory is tagged again. Returns the number of tags removed.
+pub fn remove_photo_tags(
+    conn: &mut DbPoolConn,
+    photo_ids: &[Uuid],
+    tags: &[String],
+) -> Result<usize> {
+    use crate::schema::schema::photo_tags_mappings;
+
+    let removed = diesel::delete(
+        photo_tags_mappings::table
+            .filter(photo_tags_mappings::photo_id.eq_any(photo_ids))
+            .filter(photo_tags_mappings::tag.eq_any(tags)),
+    )
+    .execute(conn)?;
+
+    Ok(removed)
 }
 
 pub fn get_unique_filters(conn: &mut DbPoolConn, path_uuid: Option<Uuid>) -> Result<Vec<String>> {
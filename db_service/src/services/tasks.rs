@@ -0,0 +1,181 @@
+use crate::db::DbPoolConn;
+use crate::schema::schema::tasks;
+use crate::schema::schema::tasks::dsl::tasks as tasks_dsl;
+use crate::schema::types::TaskStatus;
+use crate::schema::{NewTask, QueuedTask};
+use anyhow::Result;
+use diesel::dsl::{exists, now};
+use diesel::prelude::*;
+use diesel::{select, update};
+use uuid::Uuid;
+
+/// Priority of the tasks queued by the app on its own.
+pub const PRIORITY_BACKGROUND: i32 = 0;
+/// Priority of the tasks of the directory the user is looking at.
+pub const PRIORITY_VIEWED: i32 = 100;
+
+/// Queues a task. An identical task that is still waiting is reused instead, keeping the
+/// highest of the two priorities.
+pub fn enqueue_task(conn: &mut DbPoolConn, task: NewTask) -> Result<Uuid> {
+    conn.transaction::<_, anyhow::Error, _>(|conn| {
+        let existing: Option<(Uuid, i32)> = tasks_dsl
+            .filter(tasks::status.eq(TaskStatus::Queued))
+            .filter(tasks::kind.eq(&task.kind))
+            .filter(tasks::directory_id.is_not_distinct_from(task.directory_id))
+            .filter(tasks::payload.eq(&task.payload))
+            .select((tasks::id, tasks::priority))
+            .first(conn)
+            .optional()?;
+
+        if let Some((task_id, priority)) = existing {
+            if priority < task.priority {
+                update(tasks_dsl.filter(tasks::id.eq(task_id)))
+                    .set(tasks::priority.eq(task.priority))
+                    .execute(conn)?;
+            }
+            return Ok(task_id);
+        }
+
+        diesel::insert_into(tasks::table)
+            .values(&task)
+            .execute(conn)?;
+
+        Ok(task.id)
+    })
+}
+
+/// Takes the most urgent queued task and marks it as running. Tasks of the same priority run
+/// in the order they were queued.
+pub fn claim_next_task(conn: &mut DbPoolConn) -> Result<Option<QueuedTask>> {
+    conn.transaction::<_, anyhow::Error, _>(|conn| {
+        let next: Option<QueuedTask> = tasks_dsl
+            .filter(tasks::status.eq(TaskStatus::Queued))
+            .order((tasks::priority.desc(), tasks::created_at.asc()))
+            .select(QueuedTask::as_select())
+            .for_update()
+            .skip_locked()
+            .first(conn)
+            .optional()?;
+
+        if let Some(task) = &next {
+            update(tasks_dsl.filter(tasks::id.eq(task.id)))
+                .set((
+                    tasks::status.eq(TaskStatus::Running),
+                    tasks::updated_at.eq(now),
+                ))
+                .execute(conn)?;
+        }
+
+        Ok(next)
+    })
+}
+
+/// A finished task is removed from the queue.
+pub fn complete_task(conn: &mut DbPoolConn, task_id: &Uuid) -> Result<()> {
+    diesel::delete(tasks_dsl.filter(tasks::id.eq(task_id))).execute(conn)?;
+
+    Ok(())
+}
+
+/// Failed tasks are kept with their error, they aren't retried automatically.
+pub fn fail_task(conn: &mut DbPoolConn, task_id: &Uuid, error: &str) -> Result<()> {
+    update(tasks_dsl.filter(tasks::id.eq(task_id)))
+        .set((
+            tasks::status.eq(TaskStatus::Failed),
+            tasks::error.eq(error),
+            tasks::updated_at.eq(now),
+        ))
+        .execute(conn)?;
+
+    Ok(())
+}
+
+/// Puts a running task back in the queue, e.g. to let a more urgent one go first.
+pub fn requeue_task(conn: &mut DbPoolConn, task_id: &Uuid) -> Result<()> {
+    update(tasks_dsl.filter(tasks::id.eq(task_id)))
+        .set((
+            tasks::status.eq(TaskStatus::Queued),
+            tasks::updated_at.eq(now),
+        ))
+        .execute(conn)?;
+
+    Ok(())
+}
+
+/// Puts back the tasks that were running when the app was closed, returning how many there were.
+pub fn requeue_interrupted_tasks(conn: &mut DbPoolConn) -> Result<usize> {
+    let requeued = update(tasks_dsl.filter(tasks::status.eq(TaskStatus::Running)))
+        .set((
+            tasks::status.eq(TaskStatus::Queued),
+            tasks::updated_at.eq(now),
+        ))
+        .execute(conn)?;
+
+    Ok(requeued)
+}
+
+/// Whether the task is still running, it is gone once cancelled.
+pub fn is_task_running(conn: &mut DbPoolConn, task_id: &Uuid) -> Result<bool> {
+    let running = select(exists(
+        tasks_dsl
+            .filter(tasks::id.eq(task_id))
+            .filter(tasks::status.eq(TaskStatus::Running)),
+    ))
+    .get_result(conn)?;
+
+    Ok(running)
+}
+
+/// Whether a task more urgent than the given running one is waiting.
+pub fn has_more_urgent_task(conn: &mut DbPoolConn, task_id: &Uuid) -> Result<bool> {
+    let priority: Option<i32> = tasks_dsl
+        .filter(tasks::id.eq(task_id))
+        .select(tasks::priority)
+        .first(conn)
+        .optional()?;
+
+    let Some(priority) = priority else {
+        return Ok(false);
+    };
+
+    let waiting = select(exists(
+        tasks_dsl
+            .filter(tasks::status.eq(TaskStatus::Queued))
+            .filter(tasks::priority.gt(priority)),
+    ))
+    .get_result(conn)?;
+
+    Ok(waiting)
+}
+
+/// Moves the tasks of a directory ahead of the others, and the previously viewed directory
+/// back to the background.
+pub fn prioritize_directory_tasks(conn: &mut DbPoolConn, dir_id: &Uuid) -> Result<()> {
+    conn.transaction::<_, anyhow::Error, _>(|conn| {
+        update(
+            tasks_dsl
+                .filter(tasks::priority.eq(PRIORITY_VIEWED))
+                .filter(tasks::directory_id.ne(dir_id)),
+        )
+        .set(tasks::priority.eq(PRIORITY_BACKGROUND))
+        .execute(conn)?;
+
+        update(
+            tasks_dsl
+                .filter(tasks::directory_id.eq(dir_id))
+                .filter(tasks::priority.lt(PRIORITY_VIEWED)),
+        )
+        .set(tasks::priority.eq(PRIORITY_VIEWED))
+        .execute(conn)?;
+
+        Ok(())
+    })
+}
+
+/// Removes all the tasks of a directory, a running one stops at its next check.
+pub fn cancel_directory_tasks(conn: &mut DbPoolConn, dir_id: &Uuid) -> Result<usize> {
+    let cancelled =
+        diesel::delete(tasks_dsl.filter(tasks::directory_id.eq(dir_id))).execute(conn)?;
+
+    Ok(cancelled)
+}
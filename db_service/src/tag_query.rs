@@ -0,0 +1,491 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// A boolean expression over the tags of a photo, e.g. `person AND (dog OR cat) NOT car`.
+///
+/// Terms next to each other are combined with `AND`, so `person NOT car` is the same as
+/// `person AND NOT car`. Operators and tags are case-insensitive, tags are lowercased like the
+/// class names of the detection dataset. Tags containing spaces or named like an operator are
+/// written between double quotes: `"traffic light"`.
+///
+/// Serialized as the text of the query, so saved searches stay readable.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(try_from = "String", into = "String")]
+pub enum TagQuery {
+    Tag(String),
+    Not(Box<TagQuery>),
+    And(Vec<TagQuery>),
+    Or(Vec<TagQuery>),
+}
+
+impl TagQuery {
+    pub fn parse(input: &str) -> Result<TagQuery, TagQueryError> {
+        let tokens = tokenize(input)?;
+        let mut parser = Parser {
+            tokens: &tokens,
+            next: 0,
+            end: input.chars().count(),
+        };
+
+        let query = parser.or()?;
+        match parser.peek() {
+            Some(token) => Err(TagQueryError::new(token.position, "Unexpected ')'")),
+            None => Ok(query),
+        }
+    }
+
+    /// Photos with at least one of the tags, `None` when there are none.
+    pub fn any_of(tags: Vec<String>) -> Option<TagQuery> {
+        let mut tags: Vec<TagQuery> = tags.into_iter().map(TagQuery::Tag).collect();
+
+        match tags.len() {
+            0 => None,
+            1 => tags.pop(),
+            _ => Some(TagQuery::Or(tags)),
+        }
+    }
+
+    pub fn and(self, other: TagQuery) -> TagQuery {
+        match self {
+            TagQuery::And(mut terms) => {
+                terms.push(other);
+                TagQuery::And(terms)
+            }
+            term => TagQuery::And(vec![term, other]),
+        }
+    }
+}
+
+impl FromStr for TagQuery {
+    type Err = TagQueryError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        TagQuery::parse(input)
+    }
+}
+
+impl TryFrom<String> for TagQuery {
+    type Error = TagQueryError;
+
+    fn try_from(input: String) -> Result<Self, Self::Error> {
+        TagQuery::parse(&input)
+    }
+}
+
+impl From<TagQuery> for String {
+    fn from(query: TagQuery) -> Self {
+        query.to_string()
+    }
+}
+
+/// The query as it would be typed, parentheses are only added where they are needed.
+impl fmt::Display for TagQuery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TagQuery::Tag(tag) => {
+                let is_word = tag
+                    .chars()
+                    .all(|c| !c.is_whitespace() && !"()\"".contains(c));
+                let is_operator = matches!(tag.to_ascii_uppercase().as_str(), "AND" | "OR" | "NOT");
+
+                if is_word && !is_operator {
+                    write!(f, "{}", tag)
+                } else {
+                    write!(f, "\"{}\"", tag)
+                }
+            }
+            TagQuery::Not(term) => match term.as_ref() {
+                TagQuery::And(_) | TagQuery::Or(_) => write!(f, "NOT ({})", term),
+                _ => write!(f, "NOT {}", term),
+            },
+            TagQuery::And(terms) => {
+                for (i, term) in terms.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " AND ")?;
+                    }
+                    match term {
+                        TagQuery::Or(_) => write!(f, "({})", term)?,
+                        _ => write!(f, "{}", term)?,
+                    }
+                }
+                Ok(())
+            }
+            TagQuery::Or(terms) => {
+                for (i, term) in terms.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " OR ")?;
+                    }
+                    write!(f, "{}", term)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Why a query can't be parsed, `position` is the index of the offending character.
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct TagQueryError {
+    pub position: usize,
+    pub message: String,
+}
+
+impl TagQueryError {
+    fn new(position: usize, message: impl Into<String>) -> Self {
+        TagQueryError {
+            position,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for TagQueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at position {}", self.message, self.position)
+    }
+}
+
+impl std::error::Error for TagQueryError {}
+
+enum TokenKind {
+    Tag(String),
+    And,
+    Or,
+    Not,
+    Open,
+    Close,
+}
+
+struct Token {
+    kind: TokenKind,
+    position: usize,
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, TagQueryError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let start = i;
+        let kind = match chars[i] {
+            c if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            '(' => {
+                i += 1;
+                TokenKind::Open
+            }
+            ')' => {
+                i += 1;
+                TokenKind::Close
+            }
+            '"' => {
+                let Some(length) = chars[i + 1..].iter().position(|c| *c == '"') else {
+                    return Err(TagQueryError::new(start, "Unterminated quote"));
+                };
+                let tag: String = chars[i + 1..i + 1 + length].iter().collect();
+                i += length + 2;
+
+                if tag.trim().is_empty() {
+                    return Err(TagQueryError::new(start, "Empty tag"));
+                }
+                TokenKind::Tag(tag.trim().to_lowercase())
+            }
+            _ => {
+                while i < chars.len() && !chars[i].is_whitespace() && !"()\"".contains(chars[i]) {
+                    i += 1;
+                }
+                let word: String = chars[start..i].iter().collect();
+
+                match word.to_ascii_uppercase().as_str() {
+                    "AND" => TokenKind::And,
+                    "OR" => TokenKind::Or,
+                    "NOT" => TokenKind::Not,
+                    _ => TokenKind::Tag(word.to_lowercase()),
+                }
+            }
+        };
+
+        tokens.push(Token {
+            kind,
+            position: start,
+        });
+    }
+
+    Ok(tokens)
+}
+
+/// Recursive descent, `OR` binds looser than `AND` which binds looser than `NOT`.
+struct Parser<'a> {
+    tokens: &'a [Token],
+    next: usize,
+    end: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&'a Token> {
+        self.tokens.get(self.next)
+    }
+
+    fn advance(&mut self) -> Option<&'a Token> {
+        let token = self.tokens.get(self.next);
+        self.next += 1;
+        token
+    }
+
+    fn or(&mut self) -> Result<TagQuery, TagQueryError> {
+        let mut terms = vec![self.and()?];
+
+        while let Some(TokenKind::Or) = self.peek().map(|token| &token.kind) {
+            self.advance();
+            terms.push(self.and()?);
+        }
+
+        Ok(if terms.len() == 1 {
+            terms.remove(0)
+        } else {
+            TagQuery::Or(terms)
+        })
+    }
+
+    fn and(&mut self) -> Result<TagQuery, TagQueryError> {
+        let mut terms = vec![self.unary()?];
+
+        loop {
+            match self.peek().map(|token| &token.kind) {
+                Some(TokenKind::And) => {
+                    self.advance();
+                }
+                // Terms next to each other
+                Some(TokenKind::Tag(_) | TokenKind::Not | TokenKind::Open) => {}
+                _ => break,
+            }
+            terms.push(self.unary()?);
+        }
+
+        Ok(if terms.len() == 1 {
+            terms.remove(0)
+        } else {
+            TagQuery::And(terms)
+        })
+    }
+
+    fn unary(&mut self) -> Result<TagQuery, TagQueryError> {
+        if let Some(TokenKind::Not) = self.peek().map(|token| &token.kind) {
+            self.advance();
+            return Ok(TagQuery::Not(Box::new(self.unary()?)));
+        }
+
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<TagQuery, TagQueryError> {
+        let Some(token) = self.advance() else {
+            return Err(TagQueryError::new(self.end, "Expected a tag"));
+        };
+        let position = token.position;
+
+        match &token.kind {
+            TokenKind::Tag(tag) => Ok(TagQuery::Tag(tag.clone())),
+            TokenKind::Open => {
+                let query = self.or()?;
+                match self.advance() {
+                    Some(Token {
+                        kind: TokenKind::Close,
+                        ..
+                    }) => Ok(query),
+                    _ => Err(TagQueryError::new(position, "Missing closing parenthesis")),
+                }
+            }
+            TokenKind::Close => Err(TagQueryError::new(position, "Expected a tag before ')'")),
+            TokenKind::And => Err(TagQueryError::new(position, "Expected a tag before AND")),
+            TokenKind::Or => Err(TagQueryError::new(position, "Expected a tag before OR")),
+            TokenKind::Not => unreachable!("NOT is handled as a unary operator"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tag(name: &str) -> TagQuery {
+        TagQuery::Tag(name.to_string())
+    }
+
+    fn not(term: TagQuery) -> TagQuery {
+        TagQuery::Not(Box::new(term))
+    }
+
+    fn parse(input: &str) -> TagQuery {
+        TagQuery::parse(input).unwrap()
+    }
+
+    fn error(input: &str) -> (usize, String) {
+        let error = TagQuery::parse(input).unwrap_err();
+        (error.position, error.message)
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        assert_eq!(
+            parse("a OR b AND c"),
+            TagQuery::Or(vec![tag("a"), TagQuery::And(vec![tag("b"), tag("c")])])
+        );
+        assert_eq!(
+            parse("a AND b OR c"),
+            TagQuery::Or(vec![TagQuery::And(vec![tag("a"), tag("b")]), tag("c")])
+        );
+        assert_eq!(
+            parse("a OR b OR c"),
+            TagQuery::Or(vec![tag("a"), tag("b"), tag("c")])
+        );
+    }
+
+    #[test]
+    fn not_binds_tighter_than_and() {
+        assert_eq!(
+            parse("NOT a AND b"),
+            TagQuery::And(vec![not(tag("a")), tag("b")])
+        );
+        assert_eq!(parse("NOT NOT a"), not(not(tag("a"))));
+        assert_eq!(
+            parse("NOT a OR b"),
+            TagQuery::Or(vec![not(tag("a")), tag("b")])
+        );
+    }
+
+    #[test]
+    fn parentheses_group_terms() {
+        assert_eq!(
+            parse("person AND (dog OR cat)"),
+            TagQuery::And(vec![
+                tag("person"),
+                TagQuery::Or(vec![tag("dog"), tag("cat")])
+            ])
+        );
+        assert_eq!(
+            parse("NOT (a OR b)"),
+            not(TagQuery::Or(vec![tag("a"), tag("b")]))
+        );
+        assert_eq!(parse("((a))"), tag("a"));
+    }
+
+    #[test]
+    fn adjacent_terms_are_combined_with_and() {
+        assert_eq!(parse("person NOT car"), parse("person AND NOT car"));
+        assert_eq!(
+            parse("person NOT car"),
+            TagQuery::And(vec![tag("person"), not(tag("car"))])
+        );
+        assert_eq!(
+            parse("a b OR c"),
+            TagQuery::Or(vec![TagQuery::And(vec![tag("a"), tag("b")]), tag("c")])
+        );
+        assert_eq!(
+            parse("a (b OR c)"),
+            TagQuery::And(vec![tag("a"), TagQuery::Or(vec![tag("b"), tag("c")])])
+        );
+    }
+
+    #[test]
+    fn operators_and_tags_ignore_case() {
+        assert_eq!(
+            parse("Dog or CAT and not Bird"),
+            TagQuery::Or(vec![
+                tag("dog"),
+                TagQuery::And(vec![tag("cat"), not(tag("bird"))])
+            ])
+        );
+    }
+
+    #[test]
+    fn quoted_tags() {
+        assert_eq!(
+            parse("\"Traffic Light\" OR \"and\""),
+            TagQuery::Or(vec![tag("traffic light"), tag("and")])
+        );
+        assert_eq!(parse("\"  stop sign \""), tag("stop sign"));
+    }
+
+    #[test]
+    fn unbalanced_parentheses() {
+        assert_eq!(error("(a OR b"), (0, "Missing closing parenthesis".into()));
+        assert_eq!(
+            error("a AND ((b)"),
+            (6, "Missing closing parenthesis".into())
+        );
+        assert_eq!(error("a OR b)"), (6, "Unexpected ')'".into()));
+        assert_eq!(error("()"), (1, "Expected a tag before ')'".into()));
+    }
+
+    #[test]
+    fn empty_input() {
+        assert_eq!(error(""), (0, "Expected a tag".into()));
+        assert_eq!(error("   "), (3, "Expected a tag".into()));
+        assert_eq!(error("\"  \""), (0, "Empty tag".into()));
+    }
+
+    #[test]
+    fn misplaced_operators() {
+        assert_eq!(error("a AND"), (5, "Expected a tag".into()));
+        assert_eq!(error("NOT"), (3, "Expected a tag".into()));
+        assert_eq!(error("OR a"), (0, "Expected a tag before OR".into()));
+        assert_eq!(
+            error("a AND AND b"),
+            (6, "Expected a tag before AND".into())
+        );
+        assert_eq!(error("a \"b"), (2, "Unterminated quote".into()));
+    }
+
+    #[test]
+    fn positions_count_characters() {
+        assert_eq!(error("été OR"), (6, "Expected a tag".into()));
+        assert_eq!(error("café )"), (5, "Unexpected ')'".into()));
+    }
+
+    #[test]
+    fn displays_as_parsable_text() {
+        for input in [
+            "person AND (dog OR cat) AND NOT car",
+            "a OR b AND c",
+            "NOT (a OR b) OR \"traffic light\"",
+            "NOT (a AND b)",
+            "\"not\" AND \"(x)\"",
+        ] {
+            let query = parse(input);
+            assert_eq!(query.to_string(), input);
+            assert_eq!(parse(&query.to_string()), query);
+        }
+
+        assert_eq!(parse("a b NOT c").to_string(), "a AND b AND NOT c");
+    }
+
+    #[test]
+    fn serializes_as_text() {
+        let query = parse("person (dog OR cat)");
+        let json = serde_json::to_string(&query).unwrap();
+        assert_eq!(json, "\"person AND (dog OR cat)\"");
+        assert_eq!(serde_json::from_str::<TagQuery>(&json).unwrap(), query);
+
+        assert!(serde_json::from_str::<TagQuery>("\"a AND\"").is_err());
+    }
+
+    #[test]
+    fn any_of_tags() {
+        assert_eq!(TagQuery::any_of(Vec::new()), None);
+        assert_eq!(TagQuery::any_of(vec!["dog".into()]), Some(tag("dog")));
+        assert_eq!(
+            TagQuery::any_of(vec!["dog".into(), "cat".into()]),
+            Some(TagQuery::Or(vec![tag("dog"), tag("cat")]))
+        );
+        assert_eq!(
+            tag("a").and(tag("b")).and(tag("c")),
+            TagQuery::And(vec![tag("a"), tag("b"), tag("c")])
+        );
+    }
+}
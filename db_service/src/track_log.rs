@@ -0,0 +1,637 @@
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, NaiveDateTime, TimeDelta, Utc};
+use std::fs;
+use std::path::Path;
+
+/// A recorded position of a GPX or KML track.
+#[derive(Clone, Debug)]
+pub struct TrackPoint {
+    pub time: DateTime<Utc>,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub elevation: Option<f64>,
+}
+
+/// A position located on a track, `gap_seconds` is the time to the closest recorded point.
+#[derive(Clone, Debug)]
+pub struct TrackFix {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub elevation: Option<f64>,
+    pub gap_seconds: i64,
+}
+
+/// The points of one or more track logs, ordered by time.
+pub struct Track {
+    points: Vec<TrackPoint>,
+}
+
+impl Track {
+    pub fn new(mut points: Vec<TrackPoint>) -> Self {
+        points.sort_by_key(|point| point.time);
+        Track { points }
+    }
+
+    /// Reads every file and merges their points, the format is picked from the extension.
+    pub fn from_files<P: AsRef<Path>>(paths: &[P]) -> Result<Self> {
+        let mut points = Vec::new();
+        for path in paths {
+            points.extend(read_track_file(path.as_ref())?);
+        }
+
+        Ok(Track::new(points))
+    }
+
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// Where the track was at `time`. Between two points less than `max_gap` apart the
+    /// position is interpolated, otherwise the closest point is used if it is within `max_gap`.
+    pub fn locate(&self, time: DateTime<Utc>, max_gap: TimeDelta) -> Option<TrackFix> {
+        let next_index = self.points.partition_point(|point| point.time <= time);
+        let previous = next_index
+            .checked_sub(1)
+            .and_then(|index| self.points.get(index));
+        let next = self.points.get(next_index);
+
+        if let (Some(previous), Some(next)) = (previous, next) {
+            let span = next.time - previous.time;
+            if span <= max_gap {
+                let fraction = (time - previous.time).num_milliseconds() as f64
+                    / span.num_milliseconds().max(1) as f64;
+                let gap = (time - previous.time).min(next.time - time);
+
+                return Some(TrackFix {
+                    latitude: lerp(previous.latitude, next.latitude, fraction),
+                    longitude: lerp(previous.longitude, next.longitude, fraction),
+                    elevation: match (previous.elevation, next.elevation) {
+                        (Some(a), Some(b)) => Some(lerp(a, b, fraction)),
+                        (a, b) => a.or(b),
+                    },
+                    gap_seconds: gap.num_seconds(),
+                });
+            }
+        }
+
+        // Before the first point, after the last one, or in a hole of the recording
+        [previous, next]
+            .into_iter()
+            .flatten()
+            .map(|point| (point, (point.time - time).abs()))
+            .filter(|(_, gap)| *gap <= max_gap)
+            .min_by_key(|(_, gap)| *gap)
+            .map(|(point, gap)| TrackFix {
+                latitude: point.latitude,
+                longitude: point.longitude,
+                elevation: point.elevation,
+                gap_seconds: gap.num_seconds(),
+            })
+    }
+}
+
+/// Latitude, longitude and elevation of a point being parsed.
+type Position = (f64, f64, Option<f64>);
+
+fn lerp(a: f64, b: f64, fraction: f64) -> f64 {
+    a + (b - a) * fraction
+}
+
+/// Reads the timed points of a `.gpx` or `.kml` file. Points without a time are skipped, they
+/// can't be matched with photos.
+pub fn read_track_file(path: &Path) -> Result<Vec<TrackPoint>> {
+    let content = fs::read_to_string(path).with_context(|| format!("Cannot read {:?}", path))?;
+    let extension = path
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase());
+
+    let points = match extension.as_deref() {
+        Some("gpx") => parse_gpx(&content),
+        Some("kml") => parse_kml(&content),
+        _ => bail!(
+            "Unsupported track log {:?}, expected a GPX or KML file",
+            path
+        ),
+    }
+    .with_context(|| format!("Cannot parse {:?}", path))?;
+
+    tracing::info!("Read {} track points from {:?}", points.len(), path);
+
+    Ok(points)
+}
+
+/// `<trkpt lat=".." lon=".."><ele>..</ele><time>..</time></trkpt>`, route and way points have
+/// no time and are ignored.
+pub fn parse_gpx(content: &str) -> Result<Vec<TrackPoint>> {
+    let mut points = Vec::new();
+    let mut current: Option<(Position, Option<DateTime<Utc>>)> = None;
+    let mut element = String::new();
+
+    for event in XmlEvents::new(content) {
+        match event? {
+            XmlEvent::Start { name, attributes } => {
+                if name == "trkpt" {
+                    let attribute = |key: &str| {
+                        attributes
+                            .iter()
+                            .find(|(name, _)| name == key)
+                            .and_then(|(_, value)| value.trim().parse::<f64>().ok())
+                    };
+                    current = attribute("lat")
+                        .zip(attribute("lon"))
+                        .map(|(lat, lon)| ((lat, lon, None), None));
+                }
+                element = name;
+            }
+            XmlEvent::Text(text) => {
+                if let Some(((_, _, elevation), time)) = current.as_mut() {
+                    match element.as_str() {
+                        "ele" => *elevation = text.trim().parse().ok(),
+                        "time" => *time = parse_time(&text),
+                        _ => {}
+                    }
+                }
+            }
+            XmlEvent::End { name } if name == "trkpt" => {
+                if let Some(((latitude, longitude, elevation), Some(time))) = current.take() {
+                    points.push(TrackPoint {
+                        time,
+                        latitude,
+                        longitude,
+                        elevation,
+                    });
+                }
+                element.clear();
+            }
+            XmlEvent::End { .. } => element.clear(),
+        }
+    }
+
+    Ok(points)
+}
+
+/// Google's `<gx:Track>`, where `<when>` and `<gx:coord>` elements are paired by position, and
+/// placemarks with a `<TimeStamp>` and a `<Point>`.
+pub fn parse_kml(content: &str) -> Result<Vec<TrackPoint>> {
+    let mut points = Vec::new();
+    let mut element = String::new();
+
+    let mut in_track = false;
+    let mut track_times: Vec<Option<DateTime<Utc>>> = Vec::new();
+    let mut track_coordinates: Vec<Option<Position>> = Vec::new();
+
+    let mut in_placemark = false;
+    let mut placemark_time: Option<DateTime<Utc>> = None;
+    let mut placemark_coordinates: Option<Position> = None;
+
+    for event in XmlEvents::new(content) {
+        match event? {
+            XmlEvent::Start { name, .. } => {
+                match name.as_str() {
+                    "Track" => {
+                        in_track = true;
+                        track_times.clear();
+                        track_coordinates.clear();
+                    }
+                    "Placemark" => {
+                        in_placemark = true;
+                        placemark_time = None;
+                        placemark_coordinates = None;
+                    }
+                    _ => {}
+                }
+                element = name;
+            }
+            XmlEvent::Text(text) => match element.as_str() {
+                "when" if in_track => track_times.push(parse_time(&text)),
+                // Space separated: longitude latitude altitude
+                "coord" if in_track => {
+                    track_coordinates.push(parse_kml_coordinates(text.split_whitespace()))
+                }
+                "when" if in_placemark => placemark_time = parse_time(&text),
+                // Comma separated, the first tuple is the point
+                "coordinates" if in_placemark && !in_track => {
+                    placemark_coordinates = text
+                        .split_whitespace()
+                        .next()
+                        .and_then(|tuple| parse_kml_coordinates(tuple.split(',')));
+                }
+                _ => {}
+            },
+            XmlEvent::End { name } => {
+                match name.as_str() {
+                    "Track" => {
+                        in_track = false;
+                        points.extend(
+                            track_times
+                                .drain(..)
+                                .zip(track_coordinates.drain(..))
+                                .filter_map(|(time, coordinates)| {
+                                    let (latitude, longitude, elevation) = coordinates?;
+                                    Some(TrackPoint {
+                                        time: time?,
+                                        latitude,
+                                        longitude,
+                                        elevation,
+                                    })
+                                }),
+                        );
+                    }
+                    "Placemark" => {
+                        in_placemark = false;
+                        if let (Some(time), Some((latitude, longitude, elevation))) =
+                            (placemark_time.take(), placemark_coordinates.take())
+                        {
+                            points.push(TrackPoint {
+                                time,
+                                latitude,
+                                longitude,
+                                elevation,
+                            });
+                        }
+                    }
+                    _ => {}
+                }
+                element.clear();
+            }
+        }
+    }
+
+    Ok(points)
+}
+
+/// KML lists the longitude first.
+fn parse_kml_coordinates<'a>(mut parts: impl Iterator<Item = &'a str>) -> Option<Position> {
+    let longitude = parts.next()?.trim().parse().ok()?;
+    let latitude = parts.next()?.trim().parse().ok()?;
+    let elevation = parts.next().and_then(|part| part.trim().parse().ok());
+
+    Some((latitude, longitude, elevation))
+}
+
+/// ISO 8601 times, the ones without an offset are UTC as both formats require.
+fn parse_time(text: &str) -> Option<DateTime<Utc>> {
+    let text = text.trim();
+
+    DateTime::parse_from_rfc3339(text)
+        .map(|time| time.with_timezone(&Utc))
+        .ok()
+        .or_else(|| {
+            NaiveDateTime::parse_from_str(text, "%Y-%m-%dT%H:%M:%S%.f")
+                .ok()
+                .map(|time| time.and_utc())
+        })
+}
+
+enum XmlEvent {
+    Start {
+        name: String,
+        attributes: Vec<(String, String)>,
+    },
+    End {
+        name: String,
+    },
+    Text(String),
+}
+
+/// Just enough of an XML reader for track logs: elements, attributes and text, with namespace
+/// prefixes dropped. Comments, processing instructions and doctypes are skipped.
+struct XmlEvents<'a> {
+    rest: &'a str,
+    pending_end: Option<String>,
+}
+
+impl<'a> XmlEvents<'a> {
+    fn new(content: &'a str) -> Self {
+        XmlEvents {
+            rest: content,
+            pending_end: None,
+        }
+    }
+
+    fn skip_past(&mut self, terminator: &str) -> Result<()> {
+        match self.rest.find(terminator) {
+            Some(end) => {
+                self.rest = &self.rest[end + terminator.len()..];
+                Ok(())
+            }
+            None => bail!("Unterminated markup, expected {:?}", terminator),
+        }
+    }
+}
+
+impl Iterator for XmlEvents<'_> {
+    type Item = Result<XmlEvent>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(name) = self.pending_end.take() {
+            return Some(Ok(XmlEvent::End { name }));
+        }
+
+        loop {
+            if self.rest.is_empty() {
+                return None;
+            }
+
+            if !self.rest.starts_with('<') {
+                let end = self.rest.find('<').unwrap_or(self.rest.len());
+                let text = &self.rest[..end];
+                self.rest = &self.rest[end..];
+                if text.trim().is_empty() {
+                    continue;
+                }
+                return Some(Ok(XmlEvent::Text(unescape(text))));
+            }
+
+            if let Some(cdata) = self.rest.strip_prefix("<![CDATA[") {
+                let Some(end) = cdata.find("]]>") else {
+                    return Some(Err(anyhow::anyhow!("Unterminated CDATA section")));
+                };
+                let text = cdata[..end].to_string();
+                self.rest = &cdata[end + 3..];
+                return Some(Ok(XmlEvent::Text(text)));
+            }
+
+            let skipped = if self.rest.starts_with("<!--") {
+                Some("-->")
+            } else if self.rest.starts_with("<?") {
+                Some("?>")
+            } else if self.rest.starts_with("<!") {
+                Some(">")
+            } else {
+                None
+            };
+            if let Some(terminator) = skipped {
+                if let Err(e) = self.skip_past(terminator) {
+                    return Some(Err(e));
+                }
+                continue;
+            }
+
+            let Some(end) = self.rest.find('>') else {
+                return Some(Err(anyhow::anyhow!("Unterminated element")));
+            };
+            let tag = &self.rest[1..end];
+            self.rest = &self.rest[end + 1..];
+
+            if let Some(name) = tag.strip_prefix('/') {
+                return Some(Ok(XmlEvent::End {
+                    name: local_name(name.trim()).to_string(),
+                }));
+            }
+
+            let (tag, self_closing) = match tag.strip_suffix('/') {
+                Some(tag) => (tag, true),
+                None => (tag, false),
+            };
+            let name_end = tag.find(char::is_whitespace).unwrap_or(tag.len());
+            let name = local_name(&tag[..name_end]).to_string();
+
+            if self_closing {
+                self.pending_end = Some(name.clone());
+            }
+
+            return Some(Ok(XmlEvent::Start {
+                attributes: parse_attributes(&tag[name_end..]),
+                name,
+            }));
+        }
+    }
+}
+
+fn local_name(name: &str) -> &str {
+    name.rsplit(':').next().unwrap_or(name)
+}
+
+fn parse_attributes(mut rest: &str) -> Vec<(String, String)> {
+    let mut attributes = Vec::new();
+
+    while let Some(equals) = rest.find('=') {
+        let name = local_name(rest[..equals].trim()).to_string();
+        let value_start = rest[equals + 1..].trim_start();
+
+        let Some(quote) = value_start
+            .chars()
+            .next()
+            .filter(|c| *c == '"' || *c == '\'')
+        else {
+            break;
+        };
+        let Some(value_end) = value_start[1..].find(quote) else {
+            break;
+        };
+
+        attributes.push((name, unescape(&value_start[1..value_end + 1])));
+        rest = &value_start[value_end + 2..];
+    }
+
+    attributes
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    const GPX: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<gpx version="1.1" creator="test" xmlns="http://www.topografix.com/GPX/1/1">
+  <!-- A comment <trkpt lat="0" lon="0"> -->
+  <wpt lat="1.0" lon="1.0"><name>No time</name></wpt>
+  <trk>
+    <name>Walk &amp; talk</name>
+    <trkseg>
+      <trkpt lat="48.0000" lon="2.0000"><ele>100.0</ele><time>2024-05-01T10:00:00Z</time></trkpt>
+      <trkpt lat="48.0010" lon="2.0020"><ele>110.0</ele><time>2024-05-01T12:01:40+02:00</time></trkpt>
+      <trkpt lat="48.0020" lon="2.0040"><time>2024-05-01T10:03:20.500</time></trkpt>
+      <trkpt lat="49.0" lon="3.0"><ele>120</ele></trkpt>
+      <trkpt lat="48.0030" lon="2.0060"/>
+    </trkseg>
+  </trk>
+</gpx>"#;
+
+    const KML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<kml xmlns="http://www.opengis.net/kml/2.2" xmlns:gx="http://www.google.com/kml/ext/2.2">
+  <Document>
+    <Placemark>
+      <TimeStamp><when>2024-05-01T09:00:00Z</when></TimeStamp>
+      <Point><coordinates>2.5,48.5,35</coordinates></Point>
+    </Placemark>
+    <Placemark>
+      <name>Without a time</name>
+      <Point><coordinates>2.6,48.6</coordinates></Point>
+    </Placemark>
+    <Placemark>
+      <gx:Track>
+        <when>2024-05-01T10:00:00Z</when>
+        <when>2024-05-01T10:01:00Z</when>
+        <when>not a time</when>
+        <gx:coord>2.0 48.0 100</gx:coord>
+        <gx:coord>2.1 48.1</gx:coord>
+        <gx:coord>2.2 48.2 120</gx:coord>
+      </gx:Track>
+    </Placemark>
+  </Document>
+</kml>"#;
+
+    fn utc(hour: u32, minute: u32, second: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, 1, hour, minute, second)
+            .unwrap()
+    }
+
+    fn point(time: DateTime<Utc>, latitude: f64, longitude: f64) -> TrackPoint {
+        TrackPoint {
+            time,
+            latitude,
+            longitude,
+            elevation: None,
+        }
+    }
+
+    #[test]
+    fn parses_time_formats() {
+        assert_eq!(parse_time("2024-05-01T10:00:00Z"), Some(utc(10, 0, 0)));
+        assert_eq!(
+            parse_time(" 2024-05-01T12:00:00+02:00 "),
+            Some(utc(10, 0, 0))
+        );
+        assert_eq!(parse_time("2024-05-01T06:30:00-03:30"), Some(utc(10, 0, 0)));
+        // Without an offset the time is UTC
+        assert_eq!(parse_time("2024-05-01T10:00:00"), Some(utc(10, 0, 0)));
+        assert_eq!(
+            parse_time("2024-05-01T10:00:00.250Z"),
+            Some(utc(10, 0, 0) + TimeDelta::milliseconds(250))
+        );
+        assert_eq!(parse_time("2024-05-01"), None);
+        assert_eq!(parse_time("yesterday"), None);
+    }
+
+    #[test]
+    fn reads_gpx_track_points() {
+        let points = parse_gpx(GPX).unwrap();
+
+        // The way point and the track points without a time are skipped
+        assert_eq!(points.len(), 3);
+        assert_eq!(points[0].time, utc(10, 0, 0));
+        assert_eq!((points[0].latitude, points[0].longitude), (48.0, 2.0));
+        assert_eq!(points[0].elevation, Some(100.0));
+        assert_eq!(points[1].time, utc(10, 1, 40));
+        assert_eq!(points[1].elevation, Some(110.0));
+        assert_eq!(
+            points[2].time,
+            utc(10, 3, 20) + TimeDelta::milliseconds(500)
+        );
+        assert_eq!(points[2].elevation, None);
+    }
+
+    #[test]
+    fn reads_kml_tracks_and_placemarks() {
+        let mut points = parse_kml(KML).unwrap();
+        points.sort_by_key(|point| point.time);
+
+        // The placemark without a time and the track point with an invalid one are skipped
+        assert_eq!(points.len(), 3);
+        assert_eq!(points[0].time, utc(9, 0, 0));
+        assert_eq!((points[0].latitude, points[0].longitude), (48.5, 2.5));
+        assert_eq!(points[0].elevation, Some(35.0));
+        assert_eq!(points[1].time, utc(10, 0, 0));
+        assert_eq!((points[1].latitude, points[1].longitude), (48.0, 2.0));
+        assert_eq!(points[1].elevation, Some(100.0));
+        assert_eq!(points[2].time, utc(10, 1, 0));
+        assert_eq!((points[2].latitude, points[2].longitude), (48.1, 2.1));
+        assert_eq!(points[2].elevation, None);
+    }
+
+    #[test]
+    fn rejects_truncated_markup() {
+        assert!(parse_gpx("<gpx><trk><trkpt lat=\"1\" lon=\"2\"").is_err());
+        assert!(parse_kml("<kml><!-- never closed").is_err());
+    }
+
+    #[test]
+    fn interpolates_between_points() {
+        let track = Track::new(parse_gpx(GPX).unwrap());
+        let fix = track.locate(utc(10, 0, 25), TimeDelta::minutes(5)).unwrap();
+
+        assert!((fix.latitude - 48.00025).abs() < 1e-9);
+        assert!((fix.longitude - 2.0005).abs() < 1e-9);
+        assert!((fix.elevation.unwrap() - 102.5).abs() < 1e-9);
+        assert_eq!(fix.gap_seconds, 25);
+
+        // Only one of the points has an elevation
+        let fix = track.locate(utc(10, 2, 30), TimeDelta::minutes(5)).unwrap();
+        assert_eq!(fix.elevation, Some(110.0));
+        assert_eq!(fix.gap_seconds, 50);
+    }
+
+    #[test]
+    fn uses_the_exact_point() {
+        let track = Track::new(parse_gpx(GPX).unwrap());
+        let fix = track.locate(utc(10, 1, 40), TimeDelta::zero()).unwrap();
+
+        assert_eq!((fix.latitude, fix.longitude), (48.001, 2.002));
+        assert_eq!(fix.gap_seconds, 0);
+    }
+
+    #[test]
+    fn sorts_merged_points() {
+        let track = Track::new(vec![
+            point(utc(10, 2, 0), 2.0, 2.0),
+            point(utc(10, 0, 0), 0.0, 0.0),
+        ]);
+        let fix = track.locate(utc(10, 1, 0), TimeDelta::minutes(5)).unwrap();
+
+        assert_eq!((fix.latitude, fix.longitude), (1.0, 1.0));
+    }
+
+    #[test]
+    fn uses_the_closest_point_across_a_hole() {
+        let track = Track::new(vec![
+            point(utc(10, 0, 0), 0.0, 0.0),
+            point(utc(11, 0, 0), 1.0, 1.0),
+        ]);
+        let max_gap = TimeDelta::minutes(5);
+
+        let fix = track.locate(utc(10, 3, 0), max_gap).unwrap();
+        assert_eq!((fix.latitude, fix.gap_seconds), (0.0, 180));
+        let fix = track.locate(utc(10, 56, 0), max_gap).unwrap();
+        assert_eq!((fix.latitude, fix.gap_seconds), (1.0, 240));
+        assert!(track.locate(utc(10, 30, 0), max_gap).is_none());
+    }
+
+    #[test]
+    fn stops_at_the_maximum_gap() {
+        let track = Track::new(parse_gpx(GPX).unwrap());
+        let max_gap = TimeDelta::minutes(5);
+
+        let fix = track.locate(utc(9, 55, 0), max_gap).unwrap();
+        assert_eq!((fix.latitude, fix.gap_seconds), (48.0, 300));
+        assert!(track.locate(utc(9, 54, 59), max_gap).is_none());
+
+        let last = utc(10, 3, 20) + TimeDelta::milliseconds(500);
+        let fix = track.locate(last + max_gap, max_gap).unwrap();
+        assert_eq!((fix.latitude, fix.gap_seconds), (48.002, 300));
+        assert!(
+            track
+                .locate(last + max_gap + TimeDelta::seconds(1), max_gap)
+                .is_none()
+        );
+
+        assert!(
+            Track::new(Vec::new())
+                .locate(utc(10, 0, 0), max_gap)
+                .is_none()
+        );
+    }
+}
@@ -0,0 +1,1005 @@
+//! XMP sidecars, the `.xmp` files darktable, Lightroom and most raw editors keep next to the
+//! photos so that the photos themselves are never rewritten.
+//!
+//! Reading picks up what the catalog knows about: the rating, the reject flag, the color label,
+//! the keywords, the caption and the names of the face regions. The XMP packets embedded in the
+//! photo files are read the same way. Writing only replaces the properties the catalog is the
+//! source of truth for, everything else in a sidecar is kept as it is.
+
+use crate::schema::FaceRegion;
+use crate::schema::types::{ColorLabel, PhotoFlag};
+use crate::services::culling::MAX_RATING;
+use anyhow::{Context, Result, bail};
+use quick_xml::escape::escape;
+use quick_xml::events::{BytesEnd, Event};
+use quick_xml::name::{LocalName, Namespace, QName, ResolveResult};
+use quick_xml::reader::NsReader;
+use quick_xml::writer::Writer;
+use std::fs;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+const RDF: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#";
+const XMP: &str = "http://ns.adobe.com/xap/1.0/";
+const DC: &str = "http://purl.org/dc/elements/1.1/";
+const LIGHTROOM: &str = "http://ns.adobe.com/lightroom/1.0/";
+const DARKTABLE: &str = "http://darktable.sf.net/";
+const EXIF: &str = "http://ns.adobe.com/exif/1.0/";
+const REGIONS: &str = "http://www.metadataworkinggroup.com/schemas/regions/";
+const AREA: &str = "http://ns.adobe.com/xmp/sType/Area#";
+const DIMENSIONS: &str = "http://ns.adobe.com/xap/1.0/sType/Dimensions#";
+
+/// The names Lightroom gives its color labels, in the order of darktable's label numbers.
+const COLOR_LABELS: [(ColorLabel, &str); 5] = [
+    (ColorLabel::Red, "Red"),
+    (ColorLabel::Yellow, "Yellow"),
+    (ColorLabel::Green, "Green"),
+    (ColorLabel::Blue, "Blue"),
+    (ColorLabel::Purple, "Purple"),
+];
+
+/// What a new sidecar starts from.
+const EMPTY_SIDECAR: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>
+<x:xmpmeta xmlns:x=\"adobe:ns:meta/\">
+ <rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">
+  <rdf:Description rdf:about=\"\"/>
+ </rdf:RDF>
+</x:xmpmeta>
+";
+
+/// What a sidecar says about a photo.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct XmpSidecar {
+    /// Stars from 0 to 5, unknown for rejected photos.
+    pub rating: Option<i16>,
+    pub flag: Option<PhotoFlag>,
+    pub color_label: Option<ColorLabel>,
+    /// `dc:subject` and the last level of `lr:hierarchicalSubject`, without duplicates.
+    pub keywords: Vec<String>,
+    /// The people named in the face regions.
+    pub face_names: Vec<String>,
+    /// `dc:description`, in the default language when there are several.
+    pub caption: Option<String>,
+}
+
+/// A face the catalog knows the name of.
+#[derive(Debug, Clone)]
+pub struct XmpFace {
+    pub name: String,
+    pub region: FaceRegion,
+}
+
+/// The properties of a sidecar written from the catalog. Rejected photos are rated -1 like
+/// Lightroom and darktable do, the fields left to `None` keep what the sidecar has.
+#[derive(Debug, Default, Clone)]
+pub struct XmpUpdate {
+    pub rating: i16,
+    pub flag: Option<PhotoFlag>,
+    pub color_label: Option<ColorLabel>,
+    /// Replaces `dc:subject`, `lr:hierarchicalSubject` is left alone.
+    pub keywords: Option<Vec<String>>,
+    pub faces: Option<Vec<XmpFace>>,
+    /// Width and height in pixels of the image the face regions were found on.
+    pub dimensions: Option<(i32, i32)>,
+    /// Latitude and longitude in decimal degrees.
+    pub position: Option<(f64, f64)>,
+}
+
+impl XmpUpdate {
+    /// The properties removed from the sidecar before the new values are written.
+    fn replaced(&self) -> Vec<(&'static str, &'static str)> {
+        let mut replaced = vec![(XMP, "Rating"), (XMP, "Label"), (DARKTABLE, "colorlabels")];
+        if self.keywords.is_some() {
+            replaced.push((DC, "subject"));
+        }
+        if self.faces.is_some() {
+            replaced.push((REGIONS, "Regions"));
+        }
+        if self.position.is_some() {
+            replaced.extend([(EXIF, "GPSLatitude"), (EXIF, "GPSLongitude")]);
+        }
+        replaced
+    }
+
+    /// The namespaces the new values are written in.
+    fn prefixes(&self) -> Vec<(&'static str, &'static str)> {
+        let mut prefixes = vec![("rdf", RDF), ("xmp", XMP)];
+        if self.color_label.is_some() {
+            prefixes.push(("darktable", DARKTABLE));
+        }
+        if self.keywords.is_some() {
+            prefixes.push(("dc", DC));
+        }
+        if self.position.is_some() {
+            prefixes.push(("exif", EXIF));
+        }
+        if self.faces.is_some() {
+            prefixes.extend([("mwg-rs", REGIONS), ("stArea", AREA), ("stDim", DIMENSIONS)]);
+        }
+        prefixes
+    }
+}
+
+/// The sidecars a photo can have: `IMG_1.CR2.xmp` as darktable names them, then `IMG_1.xmp` as
+/// Lightroom does.
+pub fn sidecar_paths(photo_path: &Path) -> [PathBuf; 2] {
+    let mut darktable = photo_path.as_os_str().to_owned();
+    darktable.push(".xmp");
+
+    [PathBuf::from(darktable), photo_path.with_extension("xmp")]
+}
+
+/// The sidecar of a photo, when it has one.
+pub fn find_sidecar(photo_path: &Path) -> Option<PathBuf> {
+    sidecar_paths(photo_path)
+        .into_iter()
+        .find(|path| path.is_file())
+}
+
+pub fn read_sidecar(path: &Path) -> Result<XmpSidecar> {
+    let xml = fs::read_to_string(path).with_context(|| format!("Cannot read {:?}", path))?;
+
+    parse(&xml).with_context(|| format!("Invalid XMP sidecar {:?}", path))
+}
+
+/// Reads the XMP packet embedded in a photo file. JPEG, TIFF, PNG, WebP and HEIF files all
+/// store it as plain text, which is found without knowing the format.
+pub fn read_packet(data: &[u8]) -> Result<Option<XmpSidecar>> {
+    let Some(start) = find(data, b"<x:xmpmeta") else {
+        return Ok(None);
+    };
+    let end_tag = b"</x:xmpmeta>";
+    let end = find(&data[start..], end_tag)
+        .map(|end| start + end + end_tag.len())
+        .context("Unterminated XMP packet")?;
+    let xml = std::str::from_utf8(&data[start..end]).context("XMP packet is not UTF-8")?;
+
+    parse(xml).map(Some).context("Invalid XMP packet")
+}
+
+fn find(data: &[u8], needle: &[u8]) -> Option<usize> {
+    data.windows(needle.len())
+        .position(|window| window == needle)
+}
+
+/// Writes the update into the sidecar at `path`, which is created when it doesn't exist. The
+/// file is replaced at once, a sidecar is never left half written.
+pub fn write_sidecar(path: &Path, update: &XmpUpdate) -> Result<()> {
+    let existing = match fs::read_to_string(path) {
+        Ok(xml) => Some(xml),
+        Err(e) if e.kind() == ErrorKind::NotFound => None,
+        Err(e) => return Err(e).with_context(|| format!("Cannot read {:?}", path)),
+    };
+
+    let xml = merge(existing.as_deref().unwrap_or(EMPTY_SIDECAR), update)
+        .with_context(|| format!("Invalid XMP sidecar {:?}", path))?;
+
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(".tmp");
+    let temporary = PathBuf::from(temporary);
+
+    fs::write(&temporary, xml).with_context(|| format!("Cannot write {:?}", temporary))?;
+    fs::rename(&temporary, path).with_context(|| format!("Cannot write {:?}", path))?;
+
+    Ok(())
+}
+
+/// An element or attribute name, with its namespace resolved.
+#[derive(Debug)]
+struct Name {
+    namespace: Option<String>,
+    local: String,
+}
+
+impl Name {
+    fn new(namespace: ResolveResult, local: LocalName) -> Self {
+        Name {
+            namespace: match namespace {
+                ResolveResult::Bound(Namespace(namespace)) => {
+                    Some(String::from_utf8_lossy(namespace).into_owned())
+                }
+                _ => None,
+            },
+            local: String::from_utf8_lossy(local.as_ref()).into_owned(),
+        }
+    }
+
+    fn is(&self, namespace: &str, local: &str) -> bool {
+        self.namespace.as_deref() == Some(namespace) && self.local == local
+    }
+
+    fn is_any(&self, names: &[(&str, &str)]) -> bool {
+        names
+            .iter()
+            .any(|(namespace, local)| self.is(namespace, local))
+    }
+}
+
+/// A face region being read.
+#[derive(Default)]
+struct Region {
+    /// How many elements are open once its `rdf:li` is.
+    depth: usize,
+    name: Option<String>,
+    kind: Option<String>,
+}
+
+/// Properties can be written as attributes of `rdf:Description` or as elements, and their
+/// values nested in `rdf:Bag` or `rdf:Seq`. Everything is read the same way by looking at the
+/// elements a value is in.
+fn parse(xml: &str) -> Result<XmpSidecar> {
+    let mut reader = NsReader::from_str(xml);
+    let mut sidecar = XmpSidecar::default();
+    let mut darktable_label = None;
+    let mut open: Vec<Name> = Vec::new();
+    let mut text = String::new();
+    let mut region: Option<Region> = None;
+    // Whether the `rdf:li` being read is in the default language of an alternative
+    let mut is_default_language = false;
+
+    loop {
+        let (namespace, event) = reader.read_resolved_event()?;
+
+        match event {
+            Event::Start(ref start) | Event::Empty(ref start) => {
+                let is_start = matches!(event, Event::Start(_));
+                let name = Name::new(namespace, start.local_name());
+                if is_start && name.is(RDF, "li") && in_region_list(&open) {
+                    region = Some(Region {
+                        depth: open.len() + 1,
+                        ..Region::default()
+                    });
+                }
+
+                if name.is(RDF, "li") {
+                    is_default_language = false;
+                }
+
+                for attribute in start.attributes() {
+                    let attribute = attribute?;
+                    if attribute.key.as_ref() == b"xml:lang" {
+                        is_default_language = attribute.value.as_ref() == b"x-default";
+                    }
+                    let (namespace, local) = reader.resolve_attribute(attribute.key);
+                    let attribute_name = Name::new(namespace, local);
+                    let value = attribute.unescape_value()?;
+
+                    read_value(&mut sidecar, &mut region, &attribute_name, &value);
+                }
+
+                if is_start {
+                    open.push(name);
+                    text.clear();
+                }
+            }
+            Event::Text(content) => text.push_str(&content.unescape()?),
+            Event::End(_) => {
+                let Some(name) = open.pop() else {
+                    bail!("Unbalanced end tag");
+                };
+                let value = text.trim();
+
+                if name.is(RDF, "li") && open.len() >= 2 {
+                    let property = &open[open.len() - 2];
+                    if property.is(DC, "subject") {
+                        add_keyword(&mut sidecar.keywords, value);
+                    } else if property.is(LIGHTROOM, "hierarchicalSubject") {
+                        add_keyword(
+                            &mut sidecar.keywords,
+                            value.rsplit('|').next().unwrap_or(value),
+                        );
+                    } else if property.is(DC, "description") && !value.is_empty() {
+                        if sidecar.caption.is_none() || is_default_language {
+                            sidecar.caption = Some(value.to_string());
+                        }
+                    } else if property.is(DARKTABLE, "colorlabels") && darktable_label.is_none() {
+                        darktable_label = value
+                            .parse::<usize>()
+                            .ok()
+                            .and_then(|index| COLOR_LABELS.get(index))
+                            .map(|(label, _)| *label);
+                    }
+                } else {
+                    read_value(&mut sidecar, &mut region, &name, value);
+                }
+
+                if region
+                    .as_ref()
+                    .is_some_and(|region| open.len() < region.depth)
+                {
+                    let region = region.take().unwrap_or_default();
+                    let is_face = region.kind.as_deref().is_none_or(|kind| kind == "Face");
+                    if let Some(name) = region.name.filter(|_| is_face) {
+                        add_keyword(&mut sidecar.face_names, &name);
+                    }
+                }
+
+                text.clear();
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    // darktable also writes xmp:Label when it knows the name, which is more reliable
+    sidecar.color_label = sidecar.color_label.or(darktable_label);
+
+    Ok(sidecar)
+}
+
+/// Whether the element opened next is a region of `mwg-rs:RegionList`.
+fn in_region_list(open: &[Name]) -> bool {
+    match open {
+        [.., list, container] => {
+            list.is(REGIONS, "RegionList") && (container.is(RDF, "Bag") || container.is(RDF, "Seq"))
+        }
+        _ => false,
+    }
+}
+
+/// Reads a simple property, written as an attribute or as an element.
+fn read_value(sidecar: &mut XmpSidecar, region: &mut Option<Region>, name: &Name, value: &str) {
+    if name.is(XMP, "Rating") {
+        let Ok(rating) = value.trim().parse::<f64>() else {
+            return;
+        };
+        if rating < 0.0 {
+            sidecar.flag = Some(PhotoFlag::Reject);
+        } else {
+            sidecar.rating = Some((rating.round() as i16).min(MAX_RATING));
+        }
+    } else if name.is(XMP, "Label") {
+        sidecar.color_label = COLOR_LABELS
+            .iter()
+            .find(|(_, label)| label.eq_ignore_ascii_case(value.trim()))
+            .map(|(label, _)| *label);
+    } else if let Some(region) = region {
+        if name.is(REGIONS, "Name") {
+            region.name = Some(value.trim().to_string());
+        } else if name.is(REGIONS, "Type") {
+            region.kind = Some(value.trim().to_string());
+        }
+    }
+}
+
+/// Adds a keyword unless it is blank or already there.
+pub(crate) fn add_keyword(keywords: &mut Vec<String>, keyword: &str) {
+    let keyword = keyword.trim();
+    if !keyword.is_empty() && !keywords.iter().any(|known| known == keyword) {
+        keywords.push(keyword.to_string());
+    }
+}
+
+/// Copies a sidecar without the replaced properties, and adds the new values at the end of its
+/// first `rdf:Description`.
+fn merge(xml: &str, update: &XmpUpdate) -> Result<String> {
+    let mut reader = NsReader::from_str(xml);
+    let mut writer = Writer::new(Vec::new());
+    let replaced = update.replaced();
+    let mut open: Vec<Name> = Vec::new();
+    // How deep the reader is in a property being dropped
+    let mut skipped = 0;
+    // Whitespace is held back, so dropped properties don't leave blank lines behind
+    let mut whitespace: Option<Event> = None;
+    let mut written = false;
+
+    loop {
+        let (namespace, event) = reader.read_resolved_event()?;
+
+        if skipped > 0 {
+            match event {
+                Event::Start(_) => skipped += 1,
+                Event::End(_) => skipped -= 1,
+                Event::Eof => bail!("Unexpected end of file"),
+                _ => {}
+            }
+            continue;
+        }
+
+        match event {
+            Event::Text(content) if content.iter().all(u8::is_ascii_whitespace) => {
+                if let Some(previous) = whitespace.replace(Event::Text(content)) {
+                    writer.write_event(previous)?;
+                }
+            }
+            Event::Start(ref start) | Event::Empty(ref start) => {
+                let is_start = matches!(event, Event::Start(_));
+                let name = Name::new(namespace, start.local_name());
+
+                if is_property(&open) && name.is_any(&replaced) {
+                    whitespace = None;
+                    if is_start {
+                        skipped = 1;
+                    }
+                    continue;
+                }
+                if let Some(whitespace) = whitespace.take() {
+                    writer.write_event(whitespace)?;
+                }
+
+                if !(name.is(RDF, "Description") && is_description(&open)) {
+                    writer.write_event(event.borrow())?;
+                    if is_start {
+                        open.push(name);
+                    }
+                    continue;
+                }
+
+                // Attributes go on their own lines, as Lightroom and darktable write them
+                let mut attributes = Vec::new();
+                let mut changed = false;
+                for attribute in start.attributes() {
+                    let attribute = attribute?;
+                    let (namespace, local) = reader.resolve_attribute(attribute.key);
+                    if Name::new(namespace, local).is_any(&replaced) {
+                        changed = true;
+                        continue;
+                    }
+
+                    // Values are kept escaped, only their quotes may have to change
+                    let key = String::from_utf8_lossy(attribute.key.as_ref());
+                    let value = String::from_utf8_lossy(&attribute.value);
+                    if value.contains('"') {
+                        attributes.push(format!("{}='{}'", key, value));
+                    } else {
+                        attributes.push(format!("{}=\"{}\"", key, value));
+                    }
+                }
+
+                if !written {
+                    for (prefix, namespace) in update.prefixes() {
+                        let qualified = format!("{}:_", prefix);
+                        match reader.resolve_element(QName(qualified.as_bytes())).0 {
+                            ResolveResult::Bound(Namespace(bound))
+                                if bound == namespace.as_bytes() => {}
+                            ResolveResult::Bound(Namespace(bound)) => bail!(
+                                "The prefix {} is used for {} instead of {}",
+                                prefix,
+                                String::from_utf8_lossy(bound),
+                                namespace
+                            ),
+                            _ => {
+                                attributes.push(format!("xmlns:{}=\"{}\"", prefix, namespace));
+                                changed = true;
+                            }
+                        }
+                    }
+                }
+
+                // Written as a start tag when the new properties go in
+                let tag = String::from_utf8_lossy(start.name().as_ref()).into_owned();
+                let opens = is_start || !written;
+                if changed || opens != is_start {
+                    let indent = format!("\n{}", " ".repeat(open.len() + 2));
+                    let end = if opens { ">" } else { "/>" };
+                    let start_tag =
+                        format!("<{}{}{}{}", tag, indent, attributes.join(&indent), end);
+                    writer.get_mut().write_all(start_tag.as_bytes())?;
+                } else {
+                    writer.write_event(event.borrow())?;
+                }
+
+                if is_start {
+                    open.push(name);
+                } else if !written {
+                    let indent = " ".repeat(open.len() + 1);
+                    writer
+                        .get_mut()
+                        .write_all(properties(update, &indent).as_bytes())?;
+                    writer
+                        .get_mut()
+                        .write_all(format!("\n{}", " ".repeat(open.len())).as_bytes())?;
+                    writer.write_event(Event::End(BytesEnd::new(tag)))?;
+                    written = true;
+                }
+            }
+            Event::End(end) => {
+                let name = open.pop();
+                let closes_description = !written
+                    && name
+                        .as_ref()
+                        .is_some_and(|name| name.is(RDF, "Description"))
+                    && is_description(&open);
+                if closes_description {
+                    let indent = " ".repeat(open.len() + 1);
+                    writer
+                        .get_mut()
+                        .write_all(properties(update, &indent).as_bytes())?;
+                    written = true;
+                }
+
+                if let Some(whitespace) = whitespace.take() {
+                    writer.write_event(whitespace)?;
+                }
+                writer.write_event(Event::End(end))?;
+            }
+            Event::Eof => break,
+            event => {
+                if let Some(whitespace) = whitespace.take() {
+                    writer.write_event(whitespace)?;
+                }
+                writer.write_event(event)?;
+            }
+        }
+    }
+
+    if let Some(whitespace) = whitespace.take() {
+        writer.write_event(whitespace)?;
+    }
+    if !written {
+        bail!("No rdf:Description to write to");
+    }
+
+    Ok(String::from_utf8(writer.into_inner())?)
+}
+
+/// Whether the element opened next is a top level `rdf:Description`.
+fn is_description(open: &[Name]) -> bool {
+    open.last().is_some_and(|parent| parent.is(RDF, "RDF"))
+}
+
+/// Whether the element opened next is a property of a top level `rdf:Description`.
+fn is_property(open: &[Name]) -> bool {
+    match open {
+        [.., rdf, description] => rdf.is(RDF, "RDF") && description.is(RDF, "Description"),
+        _ => false,
+    }
+}
+
+/// The new values of the update as RDF/XML, one element per line.
+fn properties(update: &XmpUpdate, indent: &str) -> String {
+    let mut xml = String::new();
+    let mut line = |depth: usize, element: &str| {
+        xml.push('\n');
+        xml.push_str(indent);
+        xml.push_str(&" ".repeat(depth));
+        xml.push_str(element);
+    };
+
+    let rating = match update.flag {
+        Some(PhotoFlag::Reject) => -1,
+        _ => update.rating,
+    };
+    line(0, &format!("<xmp:Rating>{}</xmp:Rating>", rating));
+
+    if let Some(color_label) = update.color_label {
+        let index = COLOR_LABELS
+            .iter()
+            .position(|(label, _)| *label == color_label)
+            .unwrap_or_default();
+        line(
+            0,
+            &format!("<xmp:Label>{}</xmp:Label>", COLOR_LABELS[index].1),
+        );
+        line(0, "<darktable:colorlabels>");
+        line(1, "<rdf:Seq>");
+        line(2, &format!("<rdf:li>{}</rdf:li>", index));
+        line(1, "</rdf:Seq>");
+        line(0, "</darktable:colorlabels>");
+    }
+
+    if let Some(keywords) = update
+        .keywords
+        .as_ref()
+        .filter(|keywords| !keywords.is_empty())
+    {
+        line(0, "<dc:subject>");
+        line(1, "<rdf:Bag>");
+        for keyword in keywords {
+            line(2, &format!("<rdf:li>{}</rdf:li>", escape(keyword.as_str())));
+        }
+        line(1, "</rdf:Bag>");
+        line(0, "</dc:subject>");
+    }
+
+    if let Some((latitude, longitude)) = update.position {
+        line(
+            0,
+            &format!(
+                "<exif:GPSLatitude>{}</exif:GPSLatitude>",
+                xmp_coordinate(latitude, ('N', 'S'))
+            ),
+        );
+        line(
+            0,
+            &format!(
+                "<exif:GPSLongitude>{}</exif:GPSLongitude>",
+                xmp_coordinate(longitude, ('E', 'W'))
+            ),
+        );
+    }
+
+    if let Some(faces) = update.faces.as_ref().filter(|faces| !faces.is_empty()) {
+        line(0, "<mwg-rs:Regions rdf:parseType=\"Resource\">");
+        if let Some((width, height)) = update.dimensions {
+            line(
+                1,
+                &format!(
+                    "<mwg-rs:AppliedToDimensions stDim:w=\"{}\" stDim:h=\"{}\" stDim:unit=\"pixel\"/>",
+                    width, height
+                ),
+            );
+        }
+        line(1, "<mwg-rs:RegionList>");
+        line(2, "<rdf:Bag>");
+        for face in faces {
+            // Areas are given by their center
+            let region = face.region;
+            line(3, "<rdf:li rdf:parseType=\"Resource\">");
+            line(
+                4,
+                &format!("<mwg-rs:Name>{}</mwg-rs:Name>", escape(face.name.as_str())),
+            );
+            line(4, "<mwg-rs:Type>Face</mwg-rs:Type>");
+            line(
+                4,
+                &format!(
+                    "<mwg-rs:Area stArea:x=\"{:.6}\" stArea:y=\"{:.6}\" stArea:w=\"{:.6}\" stArea:h=\"{:.6}\" stArea:unit=\"normalized\"/>",
+                    region.x + region.width / 2.0,
+                    region.y + region.height / 2.0,
+                    region.width,
+                    region.height
+                ),
+            );
+            line(3, "</rdf:li>");
+        }
+        line(2, "</rdf:Bag>");
+        line(1, "</mwg-rs:RegionList>");
+        line(0, "</mwg-rs:Regions>");
+    }
+
+    xml
+}
+
+/// Decimal degrees as XMP writes GPS coordinates, degrees and decimal minutes: `47,29.052000N`.
+/// `references` are the hemispheres of positive and negative values, `('N', 'S')` or `('E', 'W')`.
+pub fn xmp_coordinate(value: f64, references: (char, char)) -> String {
+    let reference = if value < 0.0 {
+        references.1
+    } else {
+        references.0
+    };
+
+    // Rounded once, so 59.9999999 minutes don't end up printed as 60
+    let microminutes = (value.abs() * 60_000_000.0).round() as u64;
+
+    format!(
+        "{},{:.6}{}",
+        microminutes / 60_000_000,
+        (microminutes % 60_000_000) as f64 / 1_000_000.0,
+        reference
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use uuid::Uuid;
+
+    /// Properties as attributes and elements, as Lightroom writes them.
+    const LIGHTROOM_SIDECAR: &str = r#"<x:xmpmeta xmlns:x="adobe:ns:meta/" x:xmptk="Adobe XMP Core 7.0">
+ <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+  <rdf:Description rdf:about=""
+    xmlns:xmp="http://ns.adobe.com/xap/1.0/"
+    xmlns:dc="http://purl.org/dc/elements/1.1/"
+    xmlns:lr="http://ns.adobe.com/lightroom/1.0/"
+    xmlns:crs="http://ns.adobe.com/camera-raw-settings/1.0/"
+    xmlns:mwg-rs="http://www.metadataworkinggroup.com/schemas/regions/"
+    xmlns:stArea="http://ns.adobe.com/xmp/sType/Area#"
+    xmp:Rating="4"
+    xmp:Label="Green"
+    crs:Exposure2012="+0.35">
+   <dc:subject>
+    <rdf:Bag>
+     <rdf:li>dog</rdf:li>
+     <rdf:li> beach </rdf:li>
+     <rdf:li></rdf:li>
+    </rdf:Bag>
+   </dc:subject>
+   <lr:hierarchicalSubject>
+    <rdf:Bag>
+     <rdf:li>Animals|dog</rdf:li>
+     <rdf:li>Places|France|Brittany</rdf:li>
+    </rdf:Bag>
+   </lr:hierarchicalSubject>
+   <dc:description>
+    <rdf:Alt>
+     <rdf:li xml:lang="fr">Sur la plage</rdf:li>
+     <rdf:li xml:lang="x-default">On the beach &amp; the dunes</rdf:li>
+    </rdf:Alt>
+   </dc:description>
+   <mwg-rs:Regions rdf:parseType="Resource">
+    <mwg-rs:RegionList>
+     <rdf:Bag>
+      <rdf:li rdf:parseType="Resource">
+       <mwg-rs:Name>Alice</mwg-rs:Name>
+       <mwg-rs:Type>Face</mwg-rs:Type>
+       <mwg-rs:Area stArea:x="0.5" stArea:y="0.5" stArea:w="0.1" stArea:h="0.1"/>
+      </rdf:li>
+      <rdf:li mwg-rs:Name="Rex" mwg-rs:Type="Pet"/>
+      <rdf:li>
+       <rdf:Description mwg-rs:Name="Bob"/>
+      </rdf:li>
+     </rdf:Bag>
+    </mwg-rs:RegionList>
+   </mwg-rs:Regions>
+   <crs:ToneCurvePV2012>
+    <rdf:Seq>
+     <rdf:li>0, 0</rdf:li>
+     <rdf:li>255, 255</rdf:li>
+    </rdf:Seq>
+   </crs:ToneCurvePV2012>
+  </rdf:Description>
+ </rdf:RDF>
+</x:xmpmeta>
+"#;
+
+    /// A rejected photo with a color label, as darktable writes them.
+    const DARKTABLE_SIDECAR: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<x:xmpmeta xmlns:x="adobe:ns:meta/" x:xmptk="XMP Core 4.4.0-Exiv2">
+ <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+  <rdf:Description rdf:about=""
+    xmlns:xmp="http://ns.adobe.com/xap/1.0/"
+    xmlns:darktable="http://darktable.sf.net/">
+   <xmp:Rating>-1</xmp:Rating>
+   <darktable:colorlabels>
+    <rdf:Seq>
+     <rdf:li>3</rdf:li>
+     <rdf:li>0</rdf:li>
+    </rdf:Seq>
+   </darktable:colorlabels>
+   <darktable:history>
+    <rdf:Seq>
+     <rdf:li darktable:operation="exposure" darktable:enabled="1"/>
+    </rdf:Seq>
+   </darktable:history>
+  </rdf:Description>
+ </rdf:RDF>
+</x:xmpmeta>
+"#;
+
+    fn face(name: &str) -> XmpFace {
+        XmpFace {
+            name: name.to_string(),
+            region: FaceRegion {
+                x: 0.25,
+                y: 0.5,
+                width: 0.1,
+                height: 0.2,
+            },
+        }
+    }
+
+    #[test]
+    fn reads_lightroom_sidecar() {
+        let sidecar = parse(LIGHTROOM_SIDECAR).unwrap();
+
+        assert_eq!(sidecar.rating, Some(4));
+        assert_eq!(sidecar.flag, None);
+        assert_eq!(sidecar.color_label, Some(ColorLabel::Green));
+        // The last level of the hierarchy, without the blank and repeated keywords
+        assert_eq!(sidecar.keywords, ["dog", "beach", "Brittany"]);
+        // Pets aren't faces
+        assert_eq!(sidecar.face_names, ["Alice", "Bob"]);
+        assert_eq!(sidecar.caption.as_deref(), Some("On the beach & the dunes"));
+    }
+
+    #[test]
+    fn reads_darktable_sidecar() {
+        let sidecar = parse(DARKTABLE_SIDECAR).unwrap();
+
+        assert_eq!(sidecar.rating, None);
+        assert_eq!(sidecar.flag, Some(PhotoFlag::Reject));
+        // Only the first of the labels is kept
+        assert_eq!(sidecar.color_label, Some(ColorLabel::Blue));
+        assert!(sidecar.keywords.is_empty());
+        assert_eq!(sidecar.caption, None);
+    }
+
+    #[test]
+    fn label_name_wins_over_darktable_index() {
+        let xml = DARKTABLE_SIDECAR.replace(
+            "<xmp:Rating>-1</xmp:Rating>",
+            "<xmp:Rating>2</xmp:Rating><xmp:Label>purple</xmp:Label>",
+        );
+        let sidecar = parse(&xml).unwrap();
+
+        assert_eq!(sidecar.rating, Some(2));
+        assert_eq!(sidecar.color_label, Some(ColorLabel::Purple));
+    }
+
+    #[test]
+    fn ratings_are_rounded_and_capped() {
+        let rated = |rating: &str| {
+            parse(&LIGHTROOM_SIDECAR.replace("xmp:Rating=\"4\"", rating))
+                .unwrap()
+                .rating
+        };
+
+        assert_eq!(rated("xmp:Rating=\"2.6\""), Some(3));
+        assert_eq!(rated("xmp:Rating=\"9\""), Some(MAX_RATING));
+        assert_eq!(rated("xmp:Rating=\"high\""), None);
+        assert_eq!(rated(""), None);
+    }
+
+    #[test]
+    fn reads_embedded_packet() {
+        let mut data = b"\xff\xd8\xff\xe1\x00\x10http://ns.adobe.com/xap/1.0/\0".to_vec();
+        data.extend_from_slice(b"<?xpacket begin=\"\" id=\"W5M0MpCehiHzreSzNTczkc9d\"?>");
+        data.extend_from_slice(LIGHTROOM_SIDECAR.trim_end().as_bytes());
+        data.extend_from_slice(b"<?xpacket end=\"w\"?>\xff\xd9");
+
+        let sidecar = read_packet(&data).unwrap().unwrap();
+        assert_eq!(sidecar, parse(LIGHTROOM_SIDECAR).unwrap());
+
+        assert_eq!(read_packet(b"\xff\xd8\xff\xd9").unwrap(), None);
+        assert!(read_packet(b"<x:xmpmeta xmlns:x=\"adobe:ns:meta/\">").is_err());
+    }
+
+    #[test]
+    fn rejects_unbalanced_xml() {
+        assert!(parse("<x:xmpmeta xmlns:x=\"adobe:ns:meta/\"></rdf:RDF>").is_err());
+    }
+
+    #[test]
+    fn update_keeps_unknown_properties() {
+        let update = XmpUpdate {
+            rating: 2,
+            color_label: Some(ColorLabel::Red),
+            keywords: Some(vec!["cat".to_string(), "R&D".to_string()]),
+            ..XmpUpdate::default()
+        };
+        let xml = merge(LIGHTROOM_SIDECAR, &update).unwrap();
+        let sidecar = parse(&xml).unwrap();
+
+        assert_eq!(sidecar.rating, Some(2));
+        assert_eq!(sidecar.color_label, Some(ColorLabel::Red));
+        // The hierarchy isn't replaced, only dc:subject which is now written after it
+        assert_eq!(sidecar.keywords, ["dog", "Brittany", "cat", "R&D"]);
+        assert_eq!(sidecar.face_names, ["Alice", "Bob"]);
+        assert_eq!(sidecar.caption.as_deref(), Some("On the beach & the dunes"));
+
+        assert_eq!(xml.matches("xmp:Rating").count(), 2);
+        assert!(!xml.contains("xmp:Rating=\"4\""));
+        assert!(!xml.contains("beach </rdf:li>"));
+        assert!(xml.contains("crs:Exposure2012=\"+0.35\""));
+        assert!(xml.contains("<rdf:li>255, 255</rdf:li>"));
+        assert!(xml.contains("x:xmptk=\"Adobe XMP Core 7.0\""));
+        assert!(xml.contains("<rdf:li>R&amp;D</rdf:li>"));
+        assert!(xml.contains("xmlns:darktable=\"http://darktable.sf.net/\""));
+    }
+
+    #[test]
+    fn update_without_keywords_or_faces_keeps_them() {
+        let update = XmpUpdate {
+            flag: Some(PhotoFlag::Reject),
+            ..XmpUpdate::default()
+        };
+        let sidecar = parse(&merge(LIGHTROOM_SIDECAR, &update).unwrap()).unwrap();
+
+        assert_eq!(sidecar.rating, None);
+        assert_eq!(sidecar.flag, Some(PhotoFlag::Reject));
+        assert_eq!(sidecar.color_label, None);
+        assert_eq!(sidecar.keywords, ["dog", "beach", "Brittany"]);
+        assert_eq!(sidecar.face_names, ["Alice", "Bob"]);
+    }
+
+    #[test]
+    fn update_keeps_darktable_history() {
+        let update = XmpUpdate {
+            rating: 5,
+            ..XmpUpdate::default()
+        };
+        let xml = merge(DARKTABLE_SIDECAR, &update).unwrap();
+        let sidecar = parse(&xml).unwrap();
+
+        assert_eq!(sidecar.rating, Some(5));
+        assert_eq!(sidecar.flag, None);
+        assert_eq!(sidecar.color_label, None);
+        assert!(!xml.contains("colorlabels"));
+        assert!(xml.contains("<rdf:li darktable:operation=\"exposure\" darktable:enabled=\"1\"/>"));
+    }
+
+    #[test]
+    fn writes_new_sidecar() {
+        let update = XmpUpdate {
+            rating: 3,
+            color_label: Some(ColorLabel::Yellow),
+            keywords: Some(vec!["dog".to_string()]),
+            faces: Some(vec![face("Alice"), face("Bob <3")]),
+            dimensions: Some((4000, 3000)),
+            position: Some((47.4842, -3.1)),
+            ..XmpUpdate::default()
+        };
+        let xml = merge(EMPTY_SIDECAR, &update).unwrap();
+        let sidecar = parse(&xml).unwrap();
+
+        assert_eq!(
+            sidecar,
+            XmpSidecar {
+                rating: Some(3),
+                flag: None,
+                color_label: Some(ColorLabel::Yellow),
+                keywords: vec!["dog".to_string()],
+                face_names: vec!["Alice".to_string(), "Bob <3".to_string()],
+                caption: None,
+            }
+        );
+        assert!(xml.contains("<exif:GPSLatitude>47,29.052000N</exif:GPSLatitude>"));
+        assert!(xml.contains("<exif:GPSLongitude>3,6.000000W</exif:GPSLongitude>"));
+        assert!(xml.contains("stDim:w=\"4000\" stDim:h=\"3000\""));
+        // Areas are given by their center
+        assert!(xml.contains("stArea:x=\"0.300000\" stArea:y=\"0.600000\""));
+    }
+
+    #[test]
+    fn updates_are_stable() {
+        let update = XmpUpdate {
+            rating: 1,
+            color_label: Some(ColorLabel::Blue),
+            keywords: Some(vec!["dog".to_string()]),
+            faces: Some(vec![face("Alice")]),
+            position: Some((1.0, 2.0)),
+            ..XmpUpdate::default()
+        };
+        let once = merge(LIGHTROOM_SIDECAR, &update).unwrap();
+        let twice = merge(&once, &update).unwrap();
+
+        assert_eq!(once, twice);
+    }
+
+    #[test]
+    fn refuses_prefixes_bound_elsewhere() {
+        let xml = LIGHTROOM_SIDECAR.replace(
+            "xmlns:dc=\"http://purl.org/dc/elements/1.1/\"",
+            "xmlns:dc=\"http://example.com/dc/\"",
+        );
+        let update = XmpUpdate {
+            keywords: Some(vec!["dog".to_string()]),
+            ..XmpUpdate::default()
+        };
+
+        assert!(merge(&xml, &update).is_err());
+        assert!(merge("<x:xmpmeta xmlns:x=\"adobe:ns:meta/\"/>", &update).is_err());
+    }
+
+    #[test]
+    fn writes_sidecar_files() {
+        let path = env::temp_dir().join(format!("{}.xmp", Uuid::new_v4()));
+        let update = XmpUpdate {
+            rating: 4,
+            ..XmpUpdate::default()
+        };
+
+        write_sidecar(&path, &update).unwrap();
+        assert_eq!(read_sidecar(&path).unwrap().rating, Some(4));
+
+        let update = XmpUpdate {
+            keywords: Some(vec!["dog".to_string()]),
+            ..update
+        };
+        write_sidecar(&path, &update).unwrap();
+        let sidecar = read_sidecar(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(sidecar.rating, Some(4));
+        assert_eq!(sidecar.keywords, ["dog"]);
+    }
+
+    #[test]
+    fn sidecar_names() {
+        let [darktable, lightroom] = sidecar_paths(Path::new("/photos/IMG_1.CR2"));
+
+        assert_eq!(darktable, Path::new("/photos/IMG_1.CR2.xmp"));
+        assert_eq!(lightroom, Path::new("/photos/IMG_1.xmp"));
+    }
+
+    #[test]
+    fn formats_coordinates() {
+        assert_eq!(xmp_coordinate(47.4842, ('N', 'S')), "47,29.052000N");
+        assert_eq!(xmp_coordinate(-33.87, ('N', 'S')), "33,52.200000S");
+        assert_eq!(xmp_coordinate(0.0, ('E', 'W')), "0,0.000000E");
+        assert_eq!(xmp_coordinate(9.9999999999, ('E', 'W')), "10,0.000000E");
+    }
+}
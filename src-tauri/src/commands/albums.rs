@@ -0,0 +1,98 @@
+use tauri::State;
+use uuid::Uuid;
+
+use db_service::db::DbPool;
+use db_service::schema::{Album, Photo};
+use db_service::services::albums;
+
+/// Every album with its cover and number of photos.
+#[tracing::instrument]
+#[tauri::command]
+pub fn get_albums(pool: State<DbPool>) -> Result<Vec<Album>, String> {
+    let conn = &mut pool.get().map_err(|e| e.to_string())?;
+
+    albums::get_albums(conn).map_err(|e| e.to_string())
+}
+
+#[tracing::instrument]
+#[tauri::command]
+pub fn create_album(pool: State<DbPool>, name: &str) -> Result<Album, String> {
+    let conn = &mut pool.get().map_err(|e| e.to_string())?;
+
+    albums::create_album(conn, name).map_err(|e| e.to_string())
+}
+
+#[tracing::instrument]
+#[tauri::command]
+pub fn rename_album(pool: State<DbPool>, id: Uuid, name: &str) -> Result<Album, String> {
+    let conn = &mut pool.get().map_err(|e| e.to_string())?;
+
+    albums::rename_album(conn, &id, name).map_err(|e| e.to_string())
+}
+
+#[tracing::instrument]
+#[tauri::command]
+pub fn delete_album(pool: State<DbPool>, id: Uuid) -> Result<usize, String> {
+    let conn = &mut pool.get().map_err(|e| e.to_string())?;
+
+    albums::delete_album(conn, &id).map_err(|e| e.to_string())
+}
+
+#[tracing::instrument]
+#[tauri::command]
+pub fn get_album_photos(pool: State<DbPool>, id: Uuid) -> Result<Vec<Photo>, String> {
+    let conn = &mut pool.get().map_err(|e| e.to_string())?;
+
+    albums::get_album_photos(conn, &id).map_err(|e| e.to_string())
+}
+
+#[tracing::instrument]
+#[tauri::command]
+pub fn add_photos_to_album(
+    pool: State<DbPool>,
+    id: Uuid,
+    photo_ids: Vec<Uuid>,
+) -> Result<usize, String> {
+    let conn = &mut pool.get().map_err(|e| e.to_string())?;
+
+    albums::add_photos_to_album(conn, &id, &photo_ids).map_err(|e| e.to_string())
+}
+
+#[tracing::instrument]
+#[tauri::command]
+pub fn remove_photos_from_album(
+    pool: State<DbPool>,
+    id: Uuid,
+    photo_ids: Vec<Uuid>,
+) -> Result<usize, String> {
+    let conn = &mut pool.get().map_err(|e| e.to_string())?;
+
+    albums::remove_photos_from_album(conn, &id, &photo_ids).map_err(|e| e.to_string())
+}
+
+/// Moves photos right before another photo of the album, or to its end without `before`.
+#[tracing::instrument]
+#[tauri::command]
+pub fn move_album_photos(
+    pool: State<DbPool>,
+    id: Uuid,
+    photo_ids: Vec<Uuid>,
+    before: Option<Uuid>,
+) -> Result<(), String> {
+    let conn = &mut pool.get().map_err(|e| e.to_string())?;
+
+    albums::move_album_photos(conn, &id, &photo_ids, before.as_ref()).map_err(|e| e.to_string())
+}
+
+/// Picks the cover of an album, `None` goes back to its first photo.
+#[tracing::instrument]
+#[tauri::command]
+pub fn set_album_cover(
+    pool: State<DbPool>,
+    id: Uuid,
+    photo_id: Option<Uuid>,
+) -> Result<Album, String> {
+    let conn = &mut pool.get().map_err(|e| e.to_string())?;
+
+    albums::set_album_cover(conn, &id, photo_id.as_ref()).map_err(|e| e.to_string())
+}
@@ -0,0 +1,67 @@
+use tauri::State;
+use uuid::Uuid;
+
+use crate::commands::sidecars::sync_photo_sidecars;
+use db_service::db::DbPool;
+use db_service::schema::types::{ColorLabel, PhotoFlag};
+use db_service::schema::PhotoCulling;
+use db_service::services::culling;
+
+/// Rates every selected photo at once, 0 removes the rating.
+#[tracing::instrument]
+#[tauri::command]
+pub fn set_photo_rating(
+    pool: State<DbPool>,
+    photo_ids: Vec<Uuid>,
+    rating: i16,
+) -> Result<usize, String> {
+    let conn = &mut pool.get().map_err(|e| e.to_string())?;
+
+    let updated = culling::set_rating(conn, &photo_ids, rating).map_err(|e| e.to_string())?;
+    sync_photo_sidecars(conn, &photo_ids);
+
+    Ok(updated)
+}
+
+/// Picks or rejects every selected photo at once, no flag clears it.
+#[tracing::instrument]
+#[tauri::command]
+pub fn set_photo_flag(
+    pool: State<DbPool>,
+    photo_ids: Vec<Uuid>,
+    flag: Option<PhotoFlag>,
+) -> Result<usize, String> {
+    let conn = &mut pool.get().map_err(|e| e.to_string())?;
+
+    let updated = culling::set_flag(conn, &photo_ids, flag).map_err(|e| e.to_string())?;
+    sync_photo_sidecars(conn, &photo_ids);
+
+    Ok(updated)
+}
+
+#[tracing::instrument]
+#[tauri::command]
+pub fn set_photo_color_label(
+    pool: State<DbPool>,
+    photo_ids: Vec<Uuid>,
+    color_label: Option<ColorLabel>,
+) -> Result<usize, String> {
+    let conn = &mut pool.get().map_err(|e| e.to_string())?;
+
+    let updated =
+        culling::set_color_label(conn, &photo_ids, color_label).map_err(|e| e.to_string())?;
+    sync_photo_sidecars(conn, &photo_ids);
+
+    Ok(updated)
+}
+
+#[tracing::instrument]
+#[tauri::command]
+pub fn get_photo_culling(
+    pool: State<DbPool>,
+    photo_ids: Vec<Uuid>,
+) -> Result<Vec<PhotoCulling>, String> {
+    let conn = &mut pool.get().map_err(|e| e.to_string())?;
+
+    culling::get_photo_culling(conn, &photo_ids).map_err(|e| e.to_string())
+}
@@ -1,14 +1,17 @@
+use crate::task_queue::tasks::worker::preview_folder;
 use crate::task_queue::tasks::Task;
 use crate::task_queue::TaskQueue;
-use crate::APP_NAME;
+use crate::watcher::LibraryWatcher;
 use db_service::db::DbPool;
-use db_service::schema::{Directory, NewDirectory};
+use db_service::schema::{Directory, FolderNode, NewDirectory};
 use db_service::services::directory::{
-    delete_directory_from_database, get_directories, get_directory_id_by_name, insert_directory,
+    delete_directory_from_database, get_directories, get_directory_by_id, get_directory_id_by_name,
+    insert_directory,
 };
-use db_service::services::photo::insert_photos_from_directory;
+use db_service::services::metadata::set_directory_utc_offset;
+use db_service::services::photo::get_folder_tree;
+use db_service::services::tasks::cancel_directory_tasks;
 use std::fs;
-use std::path::Path;
 use std::sync::Arc;
 use tauri::State;
 use tokio::sync::Mutex;
@@ -22,11 +25,24 @@ pub fn get_folders(pool: State<DbPool>) -> Result<Vec<Directory>, String> {
     get_directories(conn).map_err(|err| err.to_string())
 }
 
+#[tracing::instrument]
+#[tauri::command]
+pub fn get_sub_folders(pool: State<DbPool>, path: &str) -> Result<FolderNode, String> {
+    let conn = &mut pool.get().map_err(|err| err.to_string())?;
+
+    let path_uuid = get_directory_id_by_name(conn, path)
+        .ok_or_else(|| format!("No UUID found for path: {}", path))?;
+    let dir_record = get_directory_by_id(conn, &path_uuid).map_err(|e| e.to_string())?;
+
+    get_folder_tree(conn, &dir_record).map_err(|err| err.to_string())
+}
+
 #[tracing::instrument]
 #[tauri::command]
 pub async fn add_folder(
     pool: State<'_, DbPool>,
     state: State<'_, Arc<Mutex<TaskQueue>>>,
+    watcher: State<'_, Arc<Mutex<LibraryWatcher>>>,
     path: &str,
 ) -> Result<Directory, String> {
     let conn = &mut pool.get().map_err(|e| e.to_string())?;
@@ -39,18 +55,63 @@ pub async fn add_folder(
 
     let dir_record = insert_directory(conn, new_dir).map_err(|e| e.to_string())?;
 
-    insert_photos_from_directory(conn, &dir_record).map_err(|e| e.to_string())?;
+    if let Err(e) = watcher.lock().await.watch(&dir_record.path) {
+        tracing::error!("Failed to watch {}: {}", dir_record.path, e);
+    }
 
+    // The photos are scanned in the background, which queues their metadata and previews
     let queue = state.lock().await;
 
-    queue.add_task(Task::CreatePreviewForPhotos(dir_record.clone()));
+    queue.add_task(Task::AddPhotosToDatabase(dir_record.clone()));
 
     Ok(dir_record)
 }
 
 #[tracing::instrument]
 #[tauri::command]
-pub fn delete_folder(pool: State<'_, DbPool>, path: &str) -> Result<(), String> {
+pub async fn rescan_folder(
+    pool: State<'_, DbPool>,
+    state: State<'_, Arc<Mutex<TaskQueue>>>,
+    path: &str,
+) -> Result<(), String> {
+    let conn = &mut pool.get().map_err(|e| e.to_string())?;
+
+    let path_uuid = get_directory_id_by_name(conn, path)
+        .ok_or_else(|| format!("No UUID found for path: {}", path))?;
+    let dir_record = get_directory_by_id(conn, &path_uuid).map_err(|e| e.to_string())?;
+
+    // The walk runs in the background, the photos-added and photos-removed events report changes
+    let queue = state.lock().await;
+
+    queue.add_task(Task::RescanDirectory(dir_record));
+
+    Ok(())
+}
+
+/// Sets the time zone the cameras of a folder were set to, for the photos that don't record
+/// their own. Returns the number of photos whose capture time changed.
+#[tracing::instrument]
+#[tauri::command]
+pub fn set_folder_time_zone(
+    pool: State<DbPool>,
+    path: &str,
+    utc_offset_minutes: Option<i32>,
+) -> Result<usize, String> {
+    let conn = &mut pool.get().map_err(|e| e.to_string())?;
+
+    let path_uuid = get_directory_id_by_name(conn, path)
+        .ok_or_else(|| format!("No UUID found for path: {}", path))?;
+
+    set_directory_utc_offset(conn, &path_uuid, utc_offset_minutes).map_err(|e| e.to_string())
+}
+
+#[tracing::instrument]
+#[tauri::command]
+pub async fn delete_folder(
+    pool: State<'_, DbPool>,
+    watcher: State<'_, Arc<Mutex<LibraryWatcher>>>,
+    path: &str,
+) -> Result<(), String> {
     let conn = &mut pool.get().map_err(|e| e.to_string())?;
 
     let path_uuid: Option<Uuid> = get_directory_id_by_name(conn, path);
@@ -63,16 +124,20 @@ pub fn delete_folder(pool: State<'_, DbPool>, path: &str) -> Result<(), String>
         }
     };
 
+    if let Err(e) = watcher.lock().await.unwatch(path) {
+        tracing::error!("Failed to stop watching {}: {}", path, e);
+    }
+
+    // A preview task running for this directory stops after its current batch
+    cancel_directory_tasks(conn, &path_uuid).map_err(|e| e.to_string())?;
+
     let delete_status = delete_directory_from_database(conn, &path_uuid);
 
     if let Err(e) = delete_status {
         return Err(e.to_string());
     }
 
-    let local_photo_path = dirs::data_local_dir()
-        .unwrap_or_else(|| Path::new(".").to_path_buf())
-        .join(APP_NAME)
-        .join(path_uuid.to_string());
+    let local_photo_path = preview_folder(&path_uuid);
 
     if local_photo_path.exists() {
         fs::remove_dir_all(&local_photo_path).map_err(|e| {
@@ -0,0 +1,84 @@
+use crate::task_queue::tasks::worker::remove_previews;
+use db_service::db::DbPool;
+use db_service::schema::{DuplicateReport, SimilarPhoto};
+use db_service::services::directory::get_directory_id_by_name;
+use db_service::services::duplicates::{
+    delete_discarded_copies, get_discardable_copies, get_duplicate_groups,
+};
+use db_service::services::similarity::{get_similar_photo_groups, DEFAULT_MAX_DISTANCE};
+use std::path::Path;
+use tauri::State;
+use uuid::Uuid;
+
+#[tracing::instrument]
+#[tauri::command]
+pub fn get_duplicates(pool: State<DbPool>) -> Result<DuplicateReport, String> {
+    let conn = &mut pool.get().map_err(|e| e.to_string())?;
+
+    get_duplicate_groups(conn).map_err(|e| e.to_string())
+}
+
+/// Keeps one copy of a duplicate group, moving the discarded files to the OS trash.
+/// Returns the ids of the photos that were removed from the catalog.
+#[tracing::instrument]
+#[tauri::command]
+pub fn keep_duplicate(
+    pool: State<DbPool>,
+    keep: Uuid,
+    discard: Vec<Uuid>,
+) -> Result<Vec<Uuid>, String> {
+    let conn = &mut pool.get().map_err(|e| e.to_string())?;
+
+    let copies = get_discardable_copies(conn, &keep, &discard).map_err(|e| e.to_string())?;
+
+    let trashed: Vec<_> = copies
+        .into_iter()
+        .filter(|copy| {
+            let file_path = Path::new(&copy.directory_path).join(&copy.name);
+            match trash::delete(&file_path) {
+                Ok(_) => true,
+                Err(e) => {
+                    tracing::error!("Failed to move {:?} to the trash: {}", file_path, e);
+                    false
+                }
+            }
+        })
+        .collect();
+
+    delete_discarded_copies(conn, &trashed).map_err(|e| e.to_string())?;
+
+    for copy in &trashed {
+        remove_previews(&copy.directory_id, &[copy.id]);
+    }
+
+    Ok(trashed.iter().map(|copy| copy.id).collect())
+}
+
+/// Groups visually similar photos (bursts, re-encodes, resized copies) of a directory, or of
+/// the whole library when `path` is empty. `max_distance` is the Hamming distance between two
+/// perceptual hashes.
+#[tracing::instrument]
+#[tauri::command]
+pub async fn get_similar_photos(
+    pool: State<'_, DbPool>,
+    path: &str,
+    max_distance: Option<u32>,
+) -> Result<Vec<Vec<SimilarPhoto>>, String> {
+    let conn = &mut pool.get().map_err(|e| e.to_string())?;
+
+    let path_uuid = if path.is_empty() {
+        None
+    } else {
+        match get_directory_id_by_name(conn, path) {
+            Some(uuid) => Some(uuid),
+            None => return Ok(vec![]),
+        }
+    };
+
+    get_similar_photo_groups(
+        conn,
+        path_uuid,
+        max_distance.unwrap_or(DEFAULT_MAX_DISTANCE),
+    )
+    .map_err(|e| e.to_string())
+}
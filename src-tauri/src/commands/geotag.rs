@@ -0,0 +1,58 @@
+use crate::commands::sidecars::sync_photo_sidecars;
+use crate::commands::types::GeotagSummary;
+use db_service::db::DbPool;
+use db_service::schema::{GeotagMatch, GeotagOptions};
+use db_service::services::geotag::{match_photos_to_track, save_geotags, write_geotags_to_files};
+use db_service::track_log::Track;
+use tauri::State;
+
+/// Matches the photos without coordinates to the points of GPX or KML track logs by capture
+/// time. Nothing is stored, the matches are shown for review first.
+#[tracing::instrument]
+#[tauri::command]
+pub async fn preview_geotags(
+    pool: State<'_, DbPool>,
+    track_files: Vec<String>,
+    options: GeotagOptions,
+) -> Result<Vec<GeotagMatch>, String> {
+    let track = Track::from_files(&track_files).map_err(|e| format!("{:#}", e))?;
+    tracing::info!("Matching photos against {} track points", track.len());
+
+    let conn = &mut pool.get().map_err(|e| e.to_string())?;
+
+    match_photos_to_track(conn, &track, &options).map_err(|e| e.to_string())
+}
+
+/// Stores the reviewed matches, and writes them into the photo files when asked to.
+#[tracing::instrument]
+#[tauri::command]
+pub async fn apply_geotags(
+    pool: State<'_, DbPool>,
+    matches: Vec<GeotagMatch>,
+    write_to_files: bool,
+) -> Result<GeotagSummary, String> {
+    let conn = &mut pool.get().map_err(|e| e.to_string())?;
+
+    let tagged = save_geotags(conn, &matches).map_err(|e| e.to_string())?;
+    let photo_ids: Vec<_> = matches.iter().map(|m| m.photo_id).collect();
+    sync_photo_sidecars(conn, &photo_ids);
+
+    let failures = if write_to_files {
+        write_geotags_to_files(conn, &matches).map_err(|e| format!("{:#}", e))?
+    } else {
+        Vec::new()
+    };
+
+    Ok(GeotagSummary {
+        tagged,
+        written: if write_to_files {
+            matches.len() - failures.len()
+        } else {
+            0
+        },
+        failures: failures
+            .into_iter()
+            .map(|failure| format!("{}: {}", failure.name, failure.error))
+            .collect(),
+    })
+}
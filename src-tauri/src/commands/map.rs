@@ -0,0 +1,45 @@
+use db_service::db::DbPool;
+use db_service::schema::{GeoBounds, GeoPhoto, PhotoCluster};
+use db_service::services::geo::{
+    get_photo_clusters, get_photos_in_bounds, get_photos_within_radius,
+};
+use tauri::State;
+
+/// Photos taken inside the visible area of the map.
+#[tracing::instrument]
+#[tauri::command]
+pub async fn get_photos_in_area(
+    pool: State<'_, DbPool>,
+    bounds: GeoBounds,
+) -> Result<Vec<GeoPhoto>, String> {
+    let conn = &mut pool.get().map_err(|e| e.to_string())?;
+
+    get_photos_in_bounds(conn, &bounds).map_err(|e| e.to_string())
+}
+
+/// Photos taken within `radius_meters` of a point, nearest first.
+#[tracing::instrument]
+#[tauri::command]
+pub async fn get_photos_near(
+    pool: State<'_, DbPool>,
+    latitude: f64,
+    longitude: f64,
+    radius_meters: f64,
+) -> Result<Vec<GeoPhoto>, String> {
+    let conn = &mut pool.get().map_err(|e| e.to_string())?;
+
+    get_photos_within_radius(conn, latitude, longitude, radius_meters).map_err(|e| e.to_string())
+}
+
+/// One marker per group of nearby photos, for the visible area at the current zoom level.
+#[tracing::instrument]
+#[tauri::command]
+pub async fn get_map_clusters(
+    pool: State<'_, DbPool>,
+    bounds: GeoBounds,
+    zoom: u8,
+) -> Result<Vec<PhotoCluster>, String> {
+    let conn = &mut pool.get().map_err(|e| e.to_string())?;
+
+    get_photo_clusters(conn, &bounds, zoom).map_err(|e| e.to_string())
+}
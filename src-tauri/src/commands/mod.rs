@@ -1,4 +1,13 @@
+pub mod albums;
+pub mod culling;
 pub mod directories;
+pub mod duplicates;
 pub mod faces;
+pub mod geotag;
+pub mod map;
 pub mod photos;
+pub mod places;
+pub mod sidecars;
+pub mod smart_albums;
+pub mod tags;
 pub mod types;
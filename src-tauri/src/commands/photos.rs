@@ -1,21 +1,45 @@
 use tauri::State;
 use uuid::Uuid;
 
-use crate::commands::types::PhotoData;
-use db_service::db::DbPool;
-use db_service::schema::PhotoSummary;
+use crate::commands::types::{PhotoData, SearchResults};
+use db_service::db::{DbPool, DbPoolConn};
+use db_service::schema::{PhotoPageRequest, PhotoSummary, PlaceFilter};
 use db_service::services::directory::get_directory_id_by_name;
-use db_service::services::metadata::get_basic_metadata_for_photos;
-use db_service::services::photo::get_photos_filtered;
+use db_service::services::metadata::{get_basic_metadata_for_photos, shift_capture_times};
+use db_service::services::photo::{
+    count_photos_filtered, get_photos_filtered, CullingFilter, PhotoQuery,
+};
 use db_service::services::tags::get_unique_filters;
+use db_service::services::tasks::prioritize_directory_tasks;
+use db_service::tag_query::{TagQuery, TagQueryError};
 
+/// Photos must have one of the selected tags and match the typed query, when there is one.
+fn tag_filter(tag_filters: Vec<String>, query: Option<&str>) -> Result<Option<TagQuery>, String> {
+    let typed = match query.filter(|query| !query.trim().is_empty()) {
+        Some(query) => Some(TagQuery::parse(query).map_err(|e| e.to_string())?),
+        None => None,
+    };
+
+    Ok(match (TagQuery::any_of(tag_filters), typed) {
+        (Some(selected), Some(typed)) => Some(selected.and(typed)),
+        (selected, typed) => selected.or(typed),
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
 #[tracing::instrument]
 #[tauri::command]
 pub fn get_photos_from_path(
     pool: State<DbPool>,
     path: &str,
+    sub_folder: Option<String>,
     tag_filters: Vec<String>,
+    tag_query: Option<String>,
+    place: Option<PlaceFilter>,
+    culling: Option<CullingFilter>,
+    page: Option<PhotoPageRequest>,
 ) -> Result<PhotoData, String> {
+    let tags = tag_filter(tag_filters, tag_query.as_deref())?;
     let conn = &mut pool.get().map_err(|e| e.to_string())?;
 
     let path_uuid: Option<Uuid> = get_directory_id_by_name(conn, path);
@@ -32,17 +56,112 @@ pub fn get_photos_from_path(
                 return Ok(PhotoData {
                     photos: vec![],
                     tags: vec![],
+                    next_cursor: None,
                 });
             }
         }
     };
 
+    // The previews of the directory being looked at are created first
+    if let Some(dir_id) = &path_uuid {
+        if let Err(e) = prioritize_directory_tasks(conn, dir_id) {
+            tracing::error!("Cannot prioritize the tasks of {}: {}", path, e);
+        }
+    }
+
+    let photo_query = PhotoQuery {
+        directory_id: path_uuid,
+        sub_folder,
+        tags,
+        place,
+        culling: culling.unwrap_or_default(),
+        ..Default::default()
+    };
+    let page = get_photos_filtered(conn, &photo_query, &page.unwrap_or_default())
+        .map_err(|e| e.to_string())?;
+
     Ok(PhotoData {
-        photos: get_photos_filtered(conn, path_uuid, tag_filters).map_err(|e| e.to_string())?,
+        photos: page.photos,
         tags: get_unique_filters(conn, path_uuid).map_err(|e| e.to_string())?,
+        next_cursor: page.next_cursor,
     })
 }
 
+/// The number of photos `get_photos_from_path` pages through with the same filters.
+#[tracing::instrument]
+#[tauri::command]
+pub fn count_photos_from_path(
+    pool: State<DbPool>,
+    path: &str,
+    sub_folder: Option<String>,
+    tag_filters: Vec<String>,
+    tag_query: Option<String>,
+    place: Option<PlaceFilter>,
+    culling: Option<CullingFilter>,
+) -> Result<i64, String> {
+    let tags = tag_filter(tag_filters, tag_query.as_deref())?;
+    let conn = &mut pool.get().map_err(|e| e.to_string())?;
+
+    let path_uuid = if path.is_empty() {
+        None
+    } else {
+        match get_directory_id_by_name(conn, path) {
+            Some(uuid) => Some(uuid),
+            None => return Ok(0),
+        }
+    };
+
+    let photo_query = PhotoQuery {
+        directory_id: path_uuid,
+        sub_folder,
+        tags,
+        place,
+        culling: culling.unwrap_or_default(),
+        ..Default::default()
+    };
+
+    count_photos_filtered(conn, &photo_query).map_err(|e| e.to_string())
+}
+
+/// Searches photos by any combination of folder, tags, place, camera and exposure. The total
+/// is only counted for the first page.
+#[tracing::instrument]
+#[tauri::command]
+pub fn search_photos(
+    pool: State<DbPool>,
+    query: PhotoQuery,
+    page: Option<PhotoPageRequest>,
+) -> Result<SearchResults, String> {
+    let conn = &mut pool.get().map_err(|e| e.to_string())?;
+
+    search(conn, &query, &page.unwrap_or_default())
+}
+
+pub(crate) fn search(
+    conn: &mut DbPoolConn,
+    query: &PhotoQuery,
+    page: &PhotoPageRequest,
+) -> Result<SearchResults, String> {
+    let total = match page.after {
+        Some(_) => None,
+        None => Some(count_photos_filtered(conn, query).map_err(|e| e.to_string())?),
+    };
+    let results = get_photos_filtered(conn, query, page).map_err(|e| e.to_string())?;
+
+    Ok(SearchResults {
+        photos: results.photos,
+        next_cursor: results.next_cursor,
+        total,
+    })
+}
+
+/// Checks a tag query as it is typed, `None` when it is valid.
+#[tracing::instrument]
+#[tauri::command]
+pub fn check_tag_query(query: &str) -> Option<TagQueryError> {
+    TagQuery::parse(query).err()
+}
+
 #[tracing::instrument]
 #[tauri::command]
 pub fn get_basic_metadata(
@@ -55,3 +174,16 @@ pub fn get_basic_metadata(
 
     Ok(results)
 }
+
+/// Moves the capture times of photos by `seconds`, for cameras whose clock was wrong.
+#[tracing::instrument]
+#[tauri::command]
+pub fn adjust_capture_times(
+    pool: State<DbPool>,
+    photo_ids: Vec<Uuid>,
+    seconds: i64,
+) -> Result<usize, String> {
+    let conn = &mut pool.get().map_err(|e| e.to_string())?;
+
+    shift_capture_times(conn, &photo_ids, seconds).map_err(|e| e.to_string())
+}
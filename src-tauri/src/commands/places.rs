@@ -0,0 +1,23 @@
+use db_service::db::DbPool;
+use db_service::schema::PlaceNode;
+use db_service::services::directory::get_directory_id_by_name;
+use db_service::services::places::get_place_tree;
+use tauri::State;
+
+/// Countries, regions and cities photos were taken in, with their photo counts. An empty path
+/// lists the places of the whole library.
+#[tracing::instrument]
+#[tauri::command]
+pub fn get_places(pool: State<DbPool>, path: &str) -> Result<Vec<PlaceNode>, String> {
+    let conn = &mut pool.get().map_err(|e| e.to_string())?;
+
+    let path_uuid = if path.is_empty() {
+        None
+    } else {
+        let path_uuid = get_directory_id_by_name(conn, path)
+            .ok_or_else(|| format!("No UUID found for path: {}", path))?;
+        Some(path_uuid)
+    };
+
+    get_place_tree(conn, path_uuid).map_err(|e| e.to_string())
+}
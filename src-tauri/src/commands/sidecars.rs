@@ -0,0 +1,67 @@
+use crate::commands::types::SidecarSummary;
+use db_service::db::{DbPool, DbPoolConn};
+use db_service::services::directory::get_directory_id_by_name;
+use db_service::services::sidecars::{set_xmp_sync, sync_sidecars, write_directory_sidecars};
+use tauri::State;
+use uuid::Uuid;
+
+/// Keeps the XMP sidecars of a folder in sync with the catalog, or stops doing so. They are all
+/// written when the sync starts.
+#[tracing::instrument]
+#[tauri::command]
+pub async fn set_folder_xmp_sync(
+    pool: State<'_, DbPool>,
+    path: &str,
+    enabled: bool,
+) -> Result<SidecarSummary, String> {
+    let conn = &mut pool.get().map_err(|e| e.to_string())?;
+
+    let path_uuid = get_directory_id_by_name(conn, path)
+        .ok_or_else(|| format!("No UUID found for path: {}", path))?;
+
+    set_xmp_sync(conn, &path_uuid, enabled).map_err(|e| e.to_string())?;
+    if !enabled {
+        return Ok(SidecarSummary {
+            written: 0,
+            failures: Vec::new(),
+        });
+    }
+
+    write_directory_sidecars(conn, &path_uuid)
+        .map(SidecarSummary::from)
+        .map_err(|e| format!("{:#}", e))
+}
+
+/// Writes the XMP sidecars of every photo of a folder once, whether it is kept in sync or not.
+#[tracing::instrument]
+#[tauri::command]
+pub async fn write_folder_sidecars(
+    pool: State<'_, DbPool>,
+    path: &str,
+) -> Result<SidecarSummary, String> {
+    let conn = &mut pool.get().map_err(|e| e.to_string())?;
+
+    let path_uuid = get_directory_id_by_name(conn, path)
+        .ok_or_else(|| format!("No UUID found for path: {}", path))?;
+
+    write_directory_sidecars(conn, &path_uuid)
+        .map(SidecarSummary::from)
+        .map_err(|e| format!("{:#}", e))
+}
+
+/// Writes the changes made to the photos into their sidecars, for the folders kept in sync. The
+/// changes are already stored, a sidecar that can't be written doesn't undo them.
+pub(crate) fn sync_photo_sidecars(conn: &mut DbPoolConn, photo_ids: &[Uuid]) {
+    match sync_sidecars(conn, photo_ids) {
+        Ok(report) => {
+            for failure in report.failures {
+                tracing::warn!(
+                    "Cannot sync the sidecar of {}: {}",
+                    failure.name,
+                    failure.error
+                );
+            }
+        }
+        Err(e) => tracing::error!("Cannot sync sidecars: {:#}", e),
+    }
+}
@@ -0,0 +1,66 @@
+use tauri::State;
+use uuid::Uuid;
+
+use crate::commands::photos::search;
+use crate::commands::types::SearchResults;
+use db_service::db::DbPool;
+use db_service::schema::{PhotoPageRequest, SmartAlbum};
+use db_service::services::photo::PhotoQuery;
+use db_service::services::smart_albums;
+
+/// Every smart album with the number of photos it currently matches.
+#[tracing::instrument]
+#[tauri::command]
+pub fn get_smart_albums(pool: State<DbPool>) -> Result<Vec<SmartAlbum>, String> {
+    let conn = &mut pool.get().map_err(|e| e.to_string())?;
+
+    smart_albums::get_smart_albums(conn).map_err(|e| e.to_string())
+}
+
+#[tracing::instrument]
+#[tauri::command]
+pub fn create_smart_album(
+    pool: State<DbPool>,
+    name: &str,
+    query: PhotoQuery,
+) -> Result<SmartAlbum, String> {
+    let conn = &mut pool.get().map_err(|e| e.to_string())?;
+
+    smart_albums::create_smart_album(conn, name, &query).map_err(|e| e.to_string())
+}
+
+#[tracing::instrument]
+#[tauri::command]
+pub fn update_smart_album(
+    pool: State<DbPool>,
+    id: Uuid,
+    name: &str,
+    query: PhotoQuery,
+) -> Result<SmartAlbum, String> {
+    let conn = &mut pool.get().map_err(|e| e.to_string())?;
+
+    smart_albums::update_smart_album(conn, &id, name, &query).map_err(|e| e.to_string())
+}
+
+#[tracing::instrument]
+#[tauri::command]
+pub fn delete_smart_album(pool: State<DbPool>, id: Uuid) -> Result<usize, String> {
+    let conn = &mut pool.get().map_err(|e| e.to_string())?;
+
+    smart_albums::delete_smart_album(conn, &id).map_err(|e| e.to_string())
+}
+
+/// The photos an album matches, read page by page like `search_photos`.
+#[tracing::instrument]
+#[tauri::command]
+pub fn get_smart_album_photos(
+    pool: State<DbPool>,
+    id: Uuid,
+    page: Option<PhotoPageRequest>,
+) -> Result<SearchResults, String> {
+    let conn = &mut pool.get().map_err(|e| e.to_string())?;
+
+    let query = smart_albums::get_smart_album_query(conn, &id).map_err(|e| e.to_string())?;
+
+    search(conn, &query, &page.unwrap_or_default())
+}
@@ -0,0 +1,36 @@
+use crate::commands::sidecars::sync_photo_sidecars;
+use db_service::db::DbPool;
+use db_service::services::tags;
+use tauri::State;
+use uuid::Uuid;
+
+/// Tags every selected photo by hand. Returns the number of tags added.
+#[tracing::instrument]
+#[tauri::command]
+pub fn add_photo_tags(
+    pool: State<DbPool>,
+    photo_ids: Vec<Uuid>,
+    tags: Vec<String>,
+) -> Result<usize, String> {
+    let conn = &mut pool.get().map_err(|e| e.to_string())?;
+
+    let added = tags::add_photo_tags(conn, &photo_ids, &tags).map_err(|e| e.to_string())?;
+    sync_photo_sidecars(conn, &photo_ids);
+
+    Ok(added)
+}
+
+#[tracing::instrument]
+#[tauri::command]
+pub fn remove_photo_tags(
+    pool: State<DbPool>,
+    photo_ids: Vec<Uuid>,
+    tags: Vec<String>,
+) -> Result<usize, String> {
+    let conn = &mut pool.get().map_err(|e| e.to_string())?;
+
+    let removed = tags::remove_photo_tags(conn, &photo_ids, &tags).map_err(|e| e.to_string())?;
+    sync_photo_sidecars(conn, &photo_ids);
+
+    Ok(removed)
+}
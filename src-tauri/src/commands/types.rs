@@ -1,4 +1,5 @@
-use db_service::schema::Photo;
+use db_service::schema::{Photo, PhotoCursor};
+use db_service::services::metadata::MetadataReport;
 use serde::{Deserialize, Serialize};
 
 #[derive(Deserialize, Serialize)]
@@ -6,4 +7,45 @@ use serde::{Deserialize, Serialize};
 pub struct PhotoData {
     pub photos: Vec<Photo>,
     pub tags: Vec<String>,
+    /// Passed back to read the next page, `None` on the last one.
+    pub next_cursor: Option<PhotoCursor>,
+}
+
+/// A page of `search_photos`, `total` is only set on the first page.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchResults {
+    pub photos: Vec<Photo>,
+    pub next_cursor: Option<PhotoCursor>,
+    pub total: Option<i64>,
+}
+
+/// What `apply_geotags` stored, `failures` are the files that couldn't be written.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GeotagSummary {
+    pub tagged: usize,
+    pub written: usize,
+    pub failures: Vec<String>,
+}
+
+/// What was written to the XMP sidecars, `failures` are the files that couldn't be written.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SidecarSummary {
+    pub written: usize,
+    pub failures: Vec<String>,
+}
+
+impl From<MetadataReport> for SidecarSummary {
+    fn from(report: MetadataReport) -> Self {
+        SidecarSummary {
+            written: report.saved,
+            failures: report
+                .failures
+                .into_iter()
+                .map(|failure| format!("{}: {}", failure.name, failure.error))
+                .collect(),
+        }
+    }
 }
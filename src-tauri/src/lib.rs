@@ -1,15 +1,42 @@
 pub mod commands;
+pub mod progress;
 pub mod task_queue;
+pub mod watcher;
 
-use crate::commands::directories::{add_folder, delete_folder, get_folders};
+use crate::commands::albums::{
+    add_photos_to_album, create_album, delete_album, get_album_photos, get_albums,
+    move_album_photos, remove_photos_from_album, rename_album, set_album_cover,
+};
+use crate::commands::culling::{
+    get_photo_culling, set_photo_color_label, set_photo_flag, set_photo_rating,
+};
+use crate::commands::directories::{
+    add_folder, delete_folder, get_folders, get_sub_folders, rescan_folder, set_folder_time_zone,
+};
+use crate::commands::duplicates::{get_duplicates, get_similar_photos, keep_duplicate};
 use crate::commands::faces::get_face_clusters;
-use crate::commands::photos::{get_basic_metadata, get_photos_from_path};
-use crate::task_queue::tasks::pre_initialization::restart_background_processing;
+use crate::commands::geotag::{apply_geotags, preview_geotags};
+use crate::commands::map::{get_map_clusters, get_photos_in_area, get_photos_near};
+use crate::commands::photos::{
+    adjust_capture_times, check_tag_query, count_photos_from_path, get_basic_metadata,
+    get_photos_from_path, search_photos,
+};
+use crate::commands::places::get_places;
+use crate::commands::sidecars::{set_folder_xmp_sync, write_folder_sidecars};
+use crate::commands::smart_albums::{
+    create_smart_album, delete_smart_album, get_smart_album_photos, get_smart_albums,
+    update_smart_album,
+};
+use crate::commands::tags::{add_photo_tags, remove_photo_tags};
+use crate::progress::relay::relay_service_progress;
+use crate::task_queue::tasks::pre_initialization::{prepare_places, restart_background_processing};
 use crate::task_queue::tasks::worker::task_worker;
 use crate::task_queue::TaskQueue;
-use db_service::db::init_pool;
-use std::sync::Arc;
+use crate::watcher::LibraryWatcher;
+use db_service::db::{init_pool, migrate};
+use db_service::services::directory::get_directories;
 use rayon::ThreadPoolBuilder;
+use std::sync::Arc;
 use tauri::Manager;
 use tokio::sync::Mutex;
 
@@ -33,11 +60,25 @@ pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_window_state::Builder::new().build())
         .setup(|app| {
-            let pool = init_pool();
+            let pool = init_pool()?;
+            migrate(&pool)?;
 
-            let (task_queue, task_receiver) = TaskQueue::new();
+            let (task_queue, task_receiver) = TaskQueue::new(pool.clone());
             let queue_state = Arc::new(Mutex::new(task_queue));
 
+            let mut watcher = LibraryWatcher::new(pool.clone(), queue_state.clone())?;
+            match get_directories(&mut pool.get()?) {
+                Ok(dirs) => watcher.watch_directories(&dirs),
+                Err(err) => tracing::error!("Cannot load directories to watch: {:?}", err),
+            }
+
+            let relay_db_pool = pool.clone();
+            let relay_app_handle = app.handle().clone();
+            std::thread::spawn(move || relay_service_progress(relay_db_pool, relay_app_handle));
+
+            let places_db_pool = pool.clone();
+            std::thread::spawn(move || prepare_places(places_db_pool));
+
             let worker_db_pool = pool.clone();
             let q = queue_state.clone();
             let app_handle = app.handle().clone();
@@ -48,14 +89,15 @@ pub fn run() {
                     .map_err(|err| err.to_string())
                     .expect("We should have DB connection");
 
-                if let Err(err) = restart_background_processing(conn, q).await {
+                if let Err(err) = restart_background_processing(conn, q.clone()).await {
                     tracing::error!("Error while restarting image preview gen: {:?}", err);
                 }
-                task_worker(task_receiver, worker_db_pool, app_handle).await;
+                task_worker(task_receiver, q, worker_db_pool, app_handle).await;
             });
 
             app.manage(pool);
             app.manage(queue_state);
+            app.manage(Arc::new(Mutex::new(watcher)));
 
             Ok(())
         })
@@ -66,9 +108,47 @@ pub fn run() {
             add_folder,
             delete_folder,
             get_folders,
+            get_sub_folders,
+            rescan_folder,
             get_photos_from_path,
+            count_photos_from_path,
+            check_tag_query,
+            search_photos,
             get_face_clusters,
             get_basic_metadata,
+            get_duplicates,
+            get_similar_photos,
+            keep_duplicate,
+            get_photos_in_area,
+            get_photos_near,
+            get_map_clusters,
+            get_places,
+            preview_geotags,
+            apply_geotags,
+            set_folder_time_zone,
+            adjust_capture_times,
+            get_smart_albums,
+            create_smart_album,
+            update_smart_album,
+            delete_smart_album,
+            get_smart_album_photos,
+            get_albums,
+            create_album,
+            rename_album,
+            delete_album,
+            get_album_photos,
+            add_photos_to_album,
+            remove_photos_from_album,
+            move_album_photos,
+            set_album_cover,
+            set_photo_rating,
+            set_photo_flag,
+            set_photo_color_label,
+            get_photo_culling,
+            set_folder_xmp_sync,
+            write_folder_sidecars,
+            add_photo_tags,
+            remove_photo_tags,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,129 @@
+use db_service::schema::types::ProcessingStage;
+use db_service::schema::Directory;
+use serde::Serialize;
+use std::time::{Duration, Instant};
+use tauri::Emitter;
+use uuid::Uuid;
+
+pub mod relay;
+
+/// Name of the event every background stage reports its progress with.
+pub const PROGRESS_EVENT: &str = "progress";
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ProgressStage {
+    Scanning,
+    Metadata,
+    Preview,
+    Tagging,
+    FaceAnalysis,
+}
+
+impl From<ProcessingStage> for ProgressStage {
+    fn from(stage: ProcessingStage) -> Self {
+        match stage {
+            ProcessingStage::Preview => ProgressStage::Preview,
+            ProcessingStage::Tagging => ProgressStage::Tagging,
+            ProcessingStage::FaceEmbeddings => ProgressStage::FaceAnalysis,
+        }
+    }
+}
+
+/// Where a background stage is for a directory. `done` counts the failed files as well, the
+/// last event of a run has `finished` set.
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ProgressEvent {
+    pub stage: ProgressStage,
+    pub directory_id: Uuid,
+    pub directory: String,
+    pub done: u64,
+    pub total: u64,
+    pub current_file: Option<String>,
+    pub errors: u64,
+    pub last_error: Option<String>,
+    pub eta_seconds: Option<u64>,
+    pub finished: bool,
+}
+
+impl ProgressEvent {
+    pub fn new(stage: ProgressStage, dir: &Directory, total: u64) -> Self {
+        Self {
+            stage,
+            directory_id: dir.id,
+            directory: dir.path.clone(),
+            done: 0,
+            total,
+            current_file: None,
+            errors: 0,
+            last_error: None,
+            eta_seconds: None,
+            finished: false,
+        }
+    }
+
+    /// Extrapolates the time left from the pace so far.
+    fn estimate_eta(&mut self, elapsed: Duration) {
+        self.eta_seconds = match self.done {
+            0 => None,
+            done => {
+                let left = self.total.saturating_sub(done);
+                Some((elapsed.as_secs_f64() / done as f64 * left as f64).round() as u64)
+            }
+        };
+    }
+
+    pub fn emit(&self, app_handle: &tauri::AppHandle) {
+        if let Err(e) = app_handle.emit(PROGRESS_EVENT, self) {
+            tracing::error!("Failed to emit {:?} progress: {}", self.stage, e);
+        }
+    }
+}
+
+/// Emits the progress of a stage run by the desktop app, from its start to its end.
+pub struct ProgressReporter {
+    app_handle: tauri::AppHandle,
+    event: ProgressEvent,
+    started: Instant,
+}
+
+impl ProgressReporter {
+    pub fn start(
+        app_handle: tauri::AppHandle,
+        stage: ProgressStage,
+        dir: &Directory,
+        total: u64,
+    ) -> Self {
+        let reporter = Self {
+            app_handle,
+            event: ProgressEvent::new(stage, dir, total),
+            started: Instant::now(),
+        };
+        reporter.event.emit(&reporter.app_handle);
+
+        reporter
+    }
+
+    /// Records files that couldn't be processed, they are still counted by `advance`.
+    pub fn record_errors(&mut self, count: u64, error: String) {
+        self.event.errors += count;
+        self.event.last_error = Some(error);
+    }
+
+    pub fn advance(&mut self, done: u64, current_file: Option<&str>) {
+        self.event.done += done;
+        // Files retried in the same run are counted again
+        self.event.total = self.event.total.max(self.event.done);
+        self.event.current_file = current_file.map(String::from);
+        self.event.estimate_eta(self.started.elapsed());
+        self.event.emit(&self.app_handle);
+    }
+
+    pub fn finish(mut self) {
+        self.event.current_file = None;
+        self.event.eta_seconds = Some(0);
+        self.event.finished = true;
+        self.event.emit(&self.app_handle);
+    }
+}
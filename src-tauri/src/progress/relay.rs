@@ -0,0 +1,82 @@
+use crate::progress::{ProgressEvent, ProgressStage};
+use anyhow::{anyhow, Result};
+use db_service::db::DbPool;
+use db_service::listener::{ChangeListener, PROGRESS_CHANNEL};
+use db_service::schema::types::ProcessingStage;
+use db_service::services::directory::get_directory_by_id;
+use db_service::services::progress::get_progress;
+use std::thread::sleep;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// How long to wait before listening again when the connection is lost.
+const RECONNECT_DELAY: Duration = Duration::from_secs(10);
+
+/// Upper bound of a single wait, so a dead connection is noticed eventually.
+const WAIT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Forwards the progress the tagging service publishes in the database to the front-end.
+/// Blocks forever, it is meant to run on its own thread.
+pub fn relay_service_progress(pool: DbPool, app_handle: tauri::AppHandle) {
+    loop {
+        let mut listener = match ChangeListener::listen(PROGRESS_CHANNEL) {
+            Ok(listener) => listener,
+            Err(e) => {
+                tracing::error!("Cannot listen for the tagging service progress: {}", e);
+                sleep(RECONNECT_DELAY);
+                continue;
+            }
+        };
+
+        loop {
+            match listener.wait(WAIT_TIMEOUT) {
+                Ok(payloads) => {
+                    for payload in payloads {
+                        if let Err(e) = relay(&pool, &app_handle, &payload) {
+                            tracing::error!("Cannot relay progress {}: {}", payload, e);
+                        }
+                    }
+                }
+                Err(e) => {
+                    tracing::error!("Lost the progress listener: {}", e);
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Loads the progress row named by a `<directory_id>:<stage>` payload and emits it.
+fn relay(pool: &DbPool, app_handle: &tauri::AppHandle, payload: &str) -> Result<()> {
+    let (dir_id, stage) = payload
+        .split_once(':')
+        .ok_or_else(|| anyhow!("Invalid progress payload"))?;
+    let dir_id = Uuid::parse_str(dir_id)?;
+    let stage = ProcessingStage::ALL
+        .into_iter()
+        .find(|known| known.as_str() == stage)
+        .ok_or_else(|| anyhow!("Unknown processing stage: {}", stage))?;
+
+    let conn = &mut pool.get()?;
+    let Some(progress) = get_progress(conn, &dir_id, stage)? else {
+        return Ok(());
+    };
+    let dir = get_directory_by_id(conn, &dir_id)?;
+
+    let mut event = ProgressEvent::new(ProgressStage::from(stage), &dir, progress.total as u64);
+    event.done = progress.done as u64;
+    event.errors = progress.errors as u64;
+    event.current_file = progress.current_file;
+    event.last_error = progress.last_error;
+    event.finished = progress.finished;
+
+    // Both timestamps come from the database clock
+    let elapsed = (progress.updated_at - progress.started_at)
+        .to_std()
+        .unwrap_or_default();
+    event.estimate_eta(elapsed);
+
+    event.emit(app_handle);
+
+    Ok(())
+}
@@ -1,20 +1,92 @@
 pub mod tasks;
 
 use crate::task_queue::tasks::Task;
-use tokio::sync::mpsc;
+use anyhow::Result;
+use db_service::db::{DbPool, DbPoolConn};
+use db_service::services::tasks::{claim_next_task, enqueue_task, fail_task, PRIORITY_BACKGROUND};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Notify;
+use tokio::time::sleep;
+use uuid::Uuid;
 
+/// How long the worker waits before trying again when the database can't be reached.
+const RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// The queue of background tasks. Tasks are stored in the database, so the ones that didn't
+/// get to run before the app was closed are picked up on the next start.
 #[derive(Debug)]
 pub struct TaskQueue {
-    sender: mpsc::UnboundedSender<Task>,
+    pool: DbPool,
+    notify: Arc<Notify>,
+}
+
+/// The worker side of the queue, handing out the most urgent task first.
+pub struct TaskReceiver {
+    pool: DbPool,
+    notify: Arc<Notify>,
 }
 
 impl TaskQueue {
-    pub fn new() -> (Self, mpsc::UnboundedReceiver<Task>) {
-        let (tx, rx) = mpsc::unbounded_channel();
-        (Self { sender: tx }, rx)
+    pub fn new(pool: DbPool) -> (Self, TaskReceiver) {
+        let notify = Arc::new(Notify::new());
+        let receiver = TaskReceiver {
+            pool: pool.clone(),
+            notify: notify.clone(),
+        };
+
+        (Self { pool, notify }, receiver)
     }
 
     pub fn add_task(&self, task: Task) {
-        let _ = self.sender.send(task);
+        self.add_task_with_priority(task, PRIORITY_BACKGROUND);
     }
+
+    pub fn add_task_with_priority(&self, task: Task, priority: i32) {
+        let queued = task.to_new_task(priority).and_then(|new_task| {
+            let conn = &mut self.pool.get()?;
+            enqueue_task(conn, new_task)
+        });
+
+        match queued {
+            Ok(_) => self.notify.notify_one(),
+            Err(e) => tracing::error!("Failed to queue task: {}", e),
+        }
+    }
+}
+
+impl TaskReceiver {
+    /// Waits for the next task, returning it along with its id in the queue.
+    pub async fn recv(&mut self) -> (Uuid, Task) {
+        loop {
+            let next = self
+                .pool
+                .get()
+                .map_err(anyhow::Error::from)
+                .and_then(|mut conn| next_task(&mut conn));
+
+            match next {
+                Ok(Some(task)) => return task,
+                Ok(None) => self.notify.notified().await,
+                Err(e) => {
+                    tracing::error!("Cannot read the task queue: {}", e);
+                    sleep(RETRY_DELAY).await;
+                }
+            }
+        }
+    }
+}
+
+fn next_task(conn: &mut DbPoolConn) -> Result<Option<(Uuid, Task)>> {
+    while let Some(queued) = claim_next_task(conn)? {
+        match Task::from_queued(conn, &queued) {
+            Ok(task) => return Ok(Some((queued.id, task))),
+            Err(e) => {
+                tracing::error!("Cannot load task {}: {}", queued.id, e);
+                fail_task(conn, &queued.id, &e.to_string())?;
+            }
+        }
+    }
+
+    Ok(None)
 }
@@ -0,0 +1,204 @@
+use crate::progress::{ProgressReporter, ProgressStage};
+use crate::task_queue::tasks::watched_files::PhotosChangedEvent;
+use crate::task_queue::tasks::worker::remove_previews;
+use crate::task_queue::tasks::{Task, TaskOutcome};
+use crate::task_queue::TaskQueue;
+use anyhow::Result;
+use db_service::db::DbPoolConn;
+use db_service::schema::types::ProcessingStage;
+use db_service::schema::Directory;
+use db_service::services::directory::get_directory_by_id;
+use db_service::services::duplicates::hash_unhashed_photos;
+use db_service::services::metadata::{get_photos_without_metadata, save_metadata_from_photos};
+use db_service::services::photo::{insert_photos_from_directory, rescan_directory};
+use db_service::services::places::{assign_places, place_index};
+use db_service::services::processing::requeue_directory_stage;
+use db_service::services::tasks::{has_more_urgent_task, is_task_running};
+use std::sync::Arc;
+use tauri::Emitter;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+/// Photos read between two checks for cancellation and progress updates.
+const METADATA_BATCH_SIZE: usize = 64;
+
+/// Walks a newly added directory, inserts its photos and queues the next stages of the import.
+pub async fn add_photos_to_database(
+    dir: Directory,
+    conn: &mut DbPoolConn,
+    app_handle: tauri::AppHandle,
+    queue: Arc<Mutex<TaskQueue>>,
+) -> Result<TaskOutcome> {
+    tracing::info!("Started scanning: {}", dir.path);
+
+    // The number of files is only known once the walk is over
+    let mut reporter = ProgressReporter::start(app_handle, ProgressStage::Scanning, &dir, 0);
+
+    let inserted = insert_photos_from_directory(conn, &dir)?;
+
+    reporter.advance(inserted as u64, None);
+    reporter.finish();
+
+    tracing::info!("Found {} photos in {}", inserted, dir.path);
+
+    let q = queue.lock().await;
+
+    q.add_task(Task::GetPhotoMetadata(dir.clone()));
+    q.add_task(Task::CreatePreviewForPhotos(dir));
+
+    Ok(TaskOutcome::Done)
+}
+
+/// Walks a directory again, adding its new files, refreshing the changed ones and marking the
+/// deleted ones as missing, then notifies the front-end like the watcher does.
+pub async fn rescan_photos(
+    dir: Directory,
+    conn: &mut DbPoolConn,
+    app_handle: tauri::AppHandle,
+    queue: Arc<Mutex<TaskQueue>>,
+) -> Result<TaskOutcome> {
+    tracing::info!("Started rescanning: {}", dir.path);
+
+    let mut reporter =
+        ProgressReporter::start(app_handle.clone(), ProgressStage::Scanning, &dir, 0);
+
+    let summary = rescan_directory(conn, &dir)?;
+
+    reporter.advance(summary.photo_count as u64, None);
+    reporter.finish();
+
+    tracing::info!(
+        "Rescan of {} added {}, updated {} and found {} missing photos",
+        dir.path,
+        summary.added.len(),
+        summary.updated.len(),
+        summary.missing.len()
+    );
+
+    // Changed files keep their id, the worker recreates their stale previews
+    remove_previews(&dir.id, &summary.updated);
+
+    if !summary.added.is_empty() || !summary.updated.is_empty() {
+        let dir = get_directory_by_id(conn, &dir.id)?;
+        let q = queue.lock().await;
+
+        q.add_task(Task::CreatePreviewForPhotos(dir));
+    }
+
+    let photo_ids: Vec<_> = summary
+        .added
+        .iter()
+        .chain(summary.updated.iter())
+        .copied()
+        .collect();
+    let events = [
+        ("photos-added", photo_ids),
+        ("photos-removed", summary.missing),
+    ];
+
+    for (event, photo_ids) in events {
+        if photo_ids.is_empty() {
+            continue;
+        }
+        let payload = PhotosChangedEvent {
+            directory: dir.path.clone(),
+            photo_ids,
+        };
+        if let Err(e) = app_handle.emit(event, payload) {
+            tracing::error!("Failed to emit {} event: {}", event, e);
+        }
+    }
+
+    Ok(TaskOutcome::Done)
+}
+
+/// Reads the metadata of the photos of a directory that don't have any yet. Like previews, the
+/// photos are handled in batches so the task can be cancelled or step aside in between.
+pub async fn get_photo_metadata(
+    dir: Directory,
+    conn: &mut DbPoolConn,
+    app_handle: tauri::AppHandle,
+    task_id: &Uuid,
+) -> Result<TaskOutcome> {
+    let photos = get_photos_without_metadata(conn, &dir.id)?;
+
+    if photos.is_empty() {
+        tracing::info!("No metadata left to read for directory: {}", dir.path);
+        return Ok(TaskOutcome::Done);
+    }
+
+    tracing::info!("Started reading metadata of {} photos", photos.len());
+
+    let mut reporter = ProgressReporter::start(
+        app_handle,
+        ProgressStage::Metadata,
+        &dir,
+        photos.len() as u64,
+    );
+    let places = place_index(conn)?;
+    let mut outcome = TaskOutcome::Done;
+
+    for batch in photos.chunks(METADATA_BATCH_SIZE) {
+        if !is_task_running(conn, task_id)? {
+            outcome = TaskOutcome::Cancelled;
+            break;
+        }
+        if has_more_urgent_task(conn, task_id)? {
+            tracing::info!("Pausing metadata of {} for a more urgent task", dir.path);
+            outcome = TaskOutcome::Yielded;
+            break;
+        }
+
+        // Photos that can't be read are retried the next time the task runs
+        let report = save_metadata_from_photos(batch, &dir, conn)?;
+
+        let photo_ids: Vec<Uuid> = batch.iter().map(|photo| photo.id).collect();
+        assign_places(conn, &places, &photo_ids)?;
+
+        if let Some(failure) = report.failures.last() {
+            reporter.record_errors(
+                report.failures.len() as u64,
+                format!("{}: {}", failure.name, failure.error),
+            );
+        }
+        reporter.advance(
+            batch.len() as u64,
+            batch.last().map(|photo| photo.name.as_str()),
+        );
+    }
+
+    reporter.finish();
+
+    if let TaskOutcome::Done = outcome {
+        tracing::info!("Finished reading metadata for directory: {}", dir.path);
+    }
+
+    Ok(outcome)
+}
+
+/// Hands the photos of a directory back to the tagging service, which detects their objects
+/// again once it is notified of the change.
+pub fn detect_objects_from_photos(
+    dir: Directory,
+    conn: &mut DbPoolConn,
+    app_handle: tauri::AppHandle,
+) -> Result<TaskOutcome> {
+    let mut reporter = ProgressReporter::start(app_handle, ProgressStage::Tagging, &dir, 0);
+
+    let requeued = requeue_directory_stage(conn, &dir.id, ProcessingStage::Tagging)?;
+
+    reporter.advance(requeued as u64, None);
+    reporter.finish();
+
+    tracing::info!("Queued {} photos of {} for tagging", requeued, dir.path);
+
+    Ok(TaskOutcome::Done)
+}
+
+/// Hashes the photos imported before content hashes were stored, so their copies show up in
+/// the duplicates view.
+pub fn hash_photos(dir: Directory, conn: &mut DbPoolConn) -> Result<TaskOutcome> {
+    hash_unhashed_photos(conn, &dir.id)?;
+
+    Ok(TaskOutcome::Done)
+}
@@ -1,11 +1,112 @@
-use db_service::schema::Directory;
+use anyhow::{anyhow, Result};
+use db_service::db::DbPoolConn;
+use db_service::schema::{Directory, NewTask, QueuedTask};
+use db_service::services::directory::get_directory_by_id;
+use serde_json::Value;
+use std::path::PathBuf;
+use uuid::Uuid;
 
+pub mod import;
 pub mod pre_initialization;
+pub mod watched_files;
 pub mod worker;
 
 pub enum Task {
-    AddPhotosToDatabase(String),
+    AddPhotosToDatabase(Directory),
+    RescanDirectory(Directory),
     CreatePreviewForPhotos(Directory),
-    GetPhotoMetadata(String),
-    DetectObjectsFromPhotos(Directory, String),
+    GetPhotoMetadata(Directory),
+    DetectObjectsFromPhotos(Directory),
+    HashPhotos(Directory),
+    InsertPhotosFromPaths(Directory, Vec<PathBuf>),
+    RemovePhotosFromPaths(Directory, Vec<PathBuf>),
+}
+
+/// How a task left the worker.
+pub enum TaskOutcome {
+    Done,
+    /// The task was removed from the queue while running, e.g. its directory was deleted.
+    Cancelled,
+    /// The task stopped to let a more urgent one run, it continues later.
+    Yielded,
+}
+
+impl Task {
+    fn kind(&self) -> &'static str {
+        match self {
+            Task::AddPhotosToDatabase(_) => "add_photos_to_database",
+            Task::RescanDirectory(_) => "rescan_directory",
+            Task::CreatePreviewForPhotos(_) => "create_preview_for_photos",
+            Task::GetPhotoMetadata(_) => "get_photo_metadata",
+            Task::DetectObjectsFromPhotos(_) => "detect_objects_from_photos",
+            Task::HashPhotos(_) => "hash_photos",
+            Task::InsertPhotosFromPaths(_, _) => "insert_photos_from_paths",
+            Task::RemovePhotosFromPaths(_, _) => "remove_photos_from_paths",
+        }
+    }
+
+    fn directory_id(&self) -> Uuid {
+        match self {
+            Task::AddPhotosToDatabase(dir)
+            | Task::RescanDirectory(dir)
+            | Task::CreatePreviewForPhotos(dir)
+            | Task::GetPhotoMetadata(dir)
+            | Task::DetectObjectsFromPhotos(dir)
+            | Task::HashPhotos(dir)
+            | Task::InsertPhotosFromPaths(dir, _)
+            | Task::RemovePhotosFromPaths(dir, _) => dir.id,
+        }
+    }
+
+    /// The arguments of the task besides its directory, which is loaded again when it runs.
+    fn payload(&self) -> Result<Value> {
+        let payload = match self {
+            Task::InsertPhotosFromPaths(_, paths) | Task::RemovePhotosFromPaths(_, paths) => {
+                serde_json::to_value(paths)?
+            }
+            Task::AddPhotosToDatabase(_)
+            | Task::RescanDirectory(_)
+            | Task::CreatePreviewForPhotos(_)
+            | Task::GetPhotoMetadata(_)
+            | Task::DetectObjectsFromPhotos(_)
+            | Task::HashPhotos(_) => Value::Null,
+        };
+
+        Ok(payload)
+    }
+
+    pub fn to_new_task(&self, priority: i32) -> Result<NewTask> {
+        Ok(NewTask {
+            id: Uuid::new_v4(),
+            kind: self.kind().to_string(),
+            directory_id: Some(self.directory_id()),
+            payload: self.payload()?,
+            priority,
+        })
+    }
+
+    pub fn from_queued(conn: &mut DbPoolConn, queued: &QueuedTask) -> Result<Self> {
+        let directory = |conn: &mut DbPoolConn| -> Result<Directory> {
+            let dir_id = queued
+                .directory_id
+                .ok_or_else(|| anyhow!("Task {} has no directory", queued.kind))?;
+            get_directory_by_id(conn, &dir_id)
+        };
+        let paths =
+            || -> Result<Vec<PathBuf>> { Ok(serde_json::from_value(queued.payload.clone())?) };
+
+        let task = match queued.kind.as_str() {
+            "add_photos_to_database" => Task::AddPhotosToDatabase(directory(conn)?),
+            "rescan_directory" => Task::RescanDirectory(directory(conn)?),
+            "create_preview_for_photos" => Task::CreatePreviewForPhotos(directory(conn)?),
+            "get_photo_metadata" => Task::GetPhotoMetadata(directory(conn)?),
+            "detect_objects_from_photos" => Task::DetectObjectsFromPhotos(directory(conn)?),
+            "hash_photos" => Task::HashPhotos(directory(conn)?),
+            "insert_photos_from_paths" => Task::InsertPhotosFromPaths(directory(conn)?, paths()?),
+            "remove_photos_from_paths" => Task::RemovePhotosFromPaths(directory(conn)?, paths()?),
+            kind => return Err(anyhow!("Unknown task kind: {}", kind)),
+        };
+
+        Ok(task)
+    }
 }
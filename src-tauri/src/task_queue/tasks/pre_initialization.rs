@@ -1,8 +1,15 @@
 use crate::task_queue::tasks::Task;
 use crate::task_queue::TaskQueue;
 use anyhow::Result;
-use db_service::db::DbPoolConn;
-use db_service::services::directory::get_directories_by_status;
+use db_service::db::{DbPool, DbPoolConn};
+use db_service::schema::types::ProcessingStage;
+use db_service::seed::{insert_places_from_geonames, DEFAULT_GEONAMES_CITIES, GEONAMES_CITIES_VAR};
+use db_service::services::duplicates::get_directories_with_unhashed_photos;
+use db_service::services::places::{assign_missing_places, place_index};
+use db_service::services::processing::{get_directories_with_claimable, release_interrupted};
+use db_service::services::tasks::requeue_interrupted_tasks;
+use std::env;
+use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
@@ -10,7 +17,19 @@ pub async fn restart_background_processing(
     conn: &mut DbPoolConn,
     queue: Arc<Mutex<TaskQueue>>,
 ) -> Result<()> {
-    let un_processed_dirs = get_directories_by_status(conn, "is_imported", false)?;
+    // Tasks and previews that were in progress when the app was closed
+    let requeued = requeue_interrupted_tasks(conn)?;
+    if requeued > 0 {
+        tracing::info!("Requeued {} interrupted tasks", requeued);
+    }
+
+    let released = release_interrupted(conn, &[ProcessingStage::Preview])?;
+    if released > 0 {
+        tracing::info!("Released {} interrupted previews", released);
+    }
+
+    // Directories imported before the queue was stored, already queued ones aren't added twice
+    let un_processed_dirs = get_directories_with_claimable(conn, ProcessingStage::Preview)?;
 
     let q = queue.lock().await;
 
@@ -18,5 +37,45 @@ pub async fn restart_background_processing(
         q.add_task(Task::CreatePreviewForPhotos(d));
     });
 
+    // Photos imported before content hashes were stored, for the duplicates view
+    get_directories_with_unhashed_photos(conn)?
+        .into_iter()
+        .for_each(|d| q.add_task(Task::HashPhotos(d)));
+
+    Ok(())
+}
+
+/// Loads the GeoNames dump on the first start and geocodes the photos that have coordinates but
+/// no place yet. Runs on its own thread, the dump takes a few seconds to insert.
+pub fn prepare_places(pool: DbPool) {
+    if let Err(err) = load_and_assign_places(&pool) {
+        tracing::error!("Error while preparing places: {:?}", err);
+    }
+}
+
+fn load_and_assign_places(pool: &DbPool) -> Result<()> {
+    let conn = &mut pool.get()?;
+
+    let cities: PathBuf = env::var(GEONAMES_CITIES_VAR)
+        .unwrap_or_else(|_| DEFAULT_GEONAMES_CITIES.to_string())
+        .into();
+    if cities.exists() {
+        insert_places_from_geonames(conn, &cities)?;
+    }
+
+    let index = place_index(conn)?;
+    if index.is_empty() {
+        tracing::warn!(
+            "No places loaded, set {} to a GeoNames cities dump to geocode photos",
+            GEONAMES_CITIES_VAR
+        );
+        return Ok(());
+    }
+
+    let assigned = assign_missing_places(conn, &index)?;
+    if assigned > 0 {
+        tracing::info!("Geocoded {} photos", assigned);
+    }
+
     Ok(())
 }
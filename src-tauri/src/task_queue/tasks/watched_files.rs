@@ -0,0 +1,94 @@
+use crate::task_queue::tasks::worker::{create_preview_for_photos, remove_previews};
+use crate::task_queue::tasks::TaskOutcome;
+use anyhow::Result;
+use db_service::db::DbPoolConn;
+use db_service::schema::Directory;
+use db_service::services::directory::get_directory_by_id;
+use db_service::services::photo::{insert_photos_from_paths, remove_photos_from_paths};
+use serde::Serialize;
+use std::path::PathBuf;
+use tauri::Emitter;
+use uuid::Uuid;
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PhotosChangedEvent {
+    pub directory: String,
+    pub photo_ids: Vec<Uuid>,
+}
+
+/// Inserts the files reported by the watcher, creates their previews and notifies the front-end.
+pub async fn insert_watched_photos(
+    dir: Directory,
+    paths: Vec<PathBuf>,
+    conn: &mut DbPoolConn,
+    app_handle: tauri::AppHandle,
+    task_id: &Uuid,
+) -> Result<TaskOutcome> {
+    let summary = insert_photos_from_paths(conn, &dir, &paths)?;
+
+    if !summary.has_changes() {
+        return Ok(TaskOutcome::Done);
+    }
+
+    // Changed files keep their id, so their old preview has to go before it is recreated
+    remove_previews(&dir.id, &summary.updated);
+
+    let photo_ids: Vec<Uuid> = summary
+        .added
+        .iter()
+        .chain(summary.updated.iter())
+        .copied()
+        .collect();
+
+    tracing::info!("Watcher added {} photos to {}", photo_ids.len(), dir.path);
+
+    let dir = get_directory_by_id(conn, &dir.id)?;
+    let directory = dir.path.clone();
+    let outcome = create_preview_for_photos(dir, conn, app_handle.clone(), task_id).await?;
+
+    if let Err(e) = app_handle.emit(
+        "photos-added",
+        PhotosChangedEvent {
+            directory,
+            photo_ids,
+        },
+    ) {
+        tracing::error!("Failed to emit photos-added event: {}", e);
+    }
+
+    Ok(outcome)
+}
+
+/// Marks the photos of the files reported as deleted by the watcher as missing. Their previews
+/// are kept like the ones of the photos a rescan doesn't find, for when the files come back.
+pub async fn remove_watched_photos(
+    dir: Directory,
+    paths: Vec<PathBuf>,
+    conn: &mut DbPoolConn,
+    app_handle: tauri::AppHandle,
+) -> Result<()> {
+    let photo_ids = remove_photos_from_paths(conn, &dir, &paths)?;
+
+    if photo_ids.is_empty() {
+        return Ok(());
+    }
+
+    tracing::info!(
+        "Watcher marked {} photos of {} as missing",
+        photo_ids.len(),
+        dir.path
+    );
+
+    if let Err(e) = app_handle.emit(
+        "photos-removed",
+        PhotosChangedEvent {
+            directory: dir.path,
+            photo_ids,
+        },
+    ) {
+        tracing::error!("Failed to emit photos-removed event: {}", e);
+    }
+
+    Ok(())
+}
@@ -1,137 +1,219 @@
-use crate::task_queue::tasks::Task;
+use crate::progress::{ProgressReporter, ProgressStage};
+use crate::task_queue::tasks::import::{
+    add_photos_to_database, detect_objects_from_photos, get_photo_metadata, hash_photos,
+    rescan_photos,
+};
+use crate::task_queue::tasks::watched_files::{insert_watched_photos, remove_watched_photos};
+use crate::task_queue::tasks::{Task, TaskOutcome};
+use crate::task_queue::{TaskQueue, TaskReceiver};
 use crate::APP_NAME;
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, Context, Result};
 use db_service::db::{DbPool, DbPoolConn};
-use db_service::schema::Directory;
-use db_service::services::directory::change_directories_status;
-use db_service::services::photo::get_photos_from_directory;
+use db_service::schema::types::ProcessingStage;
+use db_service::schema::{Directory, Photo};
+use db_service::services::processing::{claim_photos, count_unfinished, finish_photos};
+use db_service::services::tasks::{
+    complete_task, fail_task, has_more_urgent_task, is_task_running, requeue_task,
+};
 use image::imageops::FilterType;
 use image::ImageFormat;
 use rayon::prelude::*;
 use std::fs;
-use std::path::Path;
-use std::sync::atomic::AtomicU32;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::time::Duration;
-use tauri::Emitter;
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, Mutex};
 use tokio::time::sleep;
+use uuid::Uuid;
 
 pub async fn task_worker(
-    mut receiver: mpsc::UnboundedReceiver<Task>,
+    mut receiver: TaskReceiver,
+    queue: Arc<Mutex<TaskQueue>>,
     db_pool: DbPool,
     app_handle: tauri::AppHandle,
 ) {
-    while let Some(task) = receiver.recv().await {
+    loop {
+        let (task_id, task) = receiver.recv().await;
         let conn = &mut db_pool.get().expect("Conn is available");
-        match task {
-            Task::AddPhotosToDatabase(msg) => {
-                println!("Processing message: {}", msg);
+        let outcome = match task {
+            Task::AddPhotosToDatabase(dir) => {
+                add_photos_to_database(dir, conn, app_handle.clone(), queue.clone()).await
             }
+            Task::RescanDirectory(dir) => {
+                rescan_photos(dir, conn, app_handle.clone(), queue.clone()).await
+            }
+            Task::GetPhotoMetadata(dir) => {
+                get_photo_metadata(dir, conn, app_handle.clone(), &task_id).await
+            }
+            Task::DetectObjectsFromPhotos(dir) => {
+                detect_objects_from_photos(dir, conn, app_handle.clone())
+            }
+            Task::HashPhotos(dir) => hash_photos(dir, conn),
             Task::CreatePreviewForPhotos(dir) => {
                 sleep(Duration::from_secs(10)).await;
-                match create_preview_for_photos(dir, conn, app_handle.clone()).await {
-                    Ok(_) => {}
-                    Err(err) => {
-                        tracing::error!("{}", err)
-                    }
-                };
+                create_preview_for_photos(dir, conn, app_handle.clone(), &task_id).await
+            }
+            Task::InsertPhotosFromPaths(dir, paths) => {
+                insert_watched_photos(dir, paths, conn, app_handle.clone(), &task_id).await
+            }
+            Task::RemovePhotosFromPaths(dir, paths) => {
+                remove_watched_photos(dir, paths, conn, app_handle.clone())
+                    .await
+                    .map(|_| TaskOutcome::Done)
+            }
+        };
+
+        let recorded = match outcome {
+            Ok(TaskOutcome::Done) => complete_task(conn, &task_id),
+            Ok(TaskOutcome::Yielded) => requeue_task(conn, &task_id),
+            Ok(TaskOutcome::Cancelled) => {
+                tracing::info!("Task {} was cancelled", task_id);
+                Ok(())
+            }
+            Err(err) => {
+                tracing::error!("{}", err);
+                fail_task(conn, &task_id, &err.to_string())
+            }
+        };
+
+        if let Err(err) = recorded {
+            tracing::error!("Cannot update task {}: {}", task_id, err);
+        }
+    }
+}
+
+/// The folder where the previews of a directory are stored.
+pub fn preview_folder(dir_id: &Uuid) -> PathBuf {
+    let local_photo_path = dirs::data_local_dir().unwrap_or_else(|| Path::new(".").to_path_buf());
+
+    local_photo_path.join(APP_NAME).join(dir_id.to_string())
+}
+
+/// Deletes the previews of the given photos, e.g. when they are stale or the photos are gone.
+pub fn remove_previews(dir_id: &Uuid, photo_ids: &[Uuid]) {
+    let output_folder = preview_folder(dir_id);
+
+    for photo_id in photo_ids {
+        let preview = output_folder.join(format!("{}.preview.{}", photo_id, "webp"));
+        if preview.exists() {
+            if let Err(e) = fs::remove_file(&preview) {
+                tracing::error!("Failed to remove preview {:?}: {}", preview, e);
             }
-            _ => {}
         }
     }
 }
 
+/// Photos claimed at once, so an interruption only loses the previews of one batch.
+const PREVIEW_BATCH_SIZE: i64 = 256;
+
+fn create_preview(dir: &Directory, photo: &Photo, output_folder: &Path) -> Result<()> {
+    let input_path = Path::new(&dir.path).join(&photo.name);
+    if !input_path.exists() {
+        return Err(anyhow!("File not found: {:?}", input_path));
+    }
+
+    let output_path = output_folder.join(format!("{}.preview.{}", photo.id, "webp"));
+
+    if output_path.exists() {
+        tracing::debug!("Preview already exists, skipping: {:?}", input_path);
+        return Ok(());
+    }
+
+    let img =
+        image::open(&input_path).with_context(|| format!("Failed to open image {}", photo.name))?;
+    let resized = img.resize(640, 640, FilterType::CatmullRom);
+    resized
+        .save_with_format(&output_path, ImageFormat::WebP)
+        .with_context(|| format!("Failed to save preview for {}", photo.name))?;
+    tracing::debug!("Preview saved at {:?}", output_path);
+
+    Ok(())
+}
+
+/// Creates the missing previews of a directory in batches. Between two batches the task stops
+/// if it was cancelled, or steps aside if a more urgent one was queued.
 pub async fn create_preview_for_photos(
     dir: Directory,
     conn: &mut DbPoolConn,
     app_handle: tauri::AppHandle,
-) -> Result<()> {
-    let photos = get_photos_from_directory(conn, dir.id);
-    let total_photos = photos.len();
+    task_id: &Uuid,
+) -> Result<TaskOutcome> {
+    let total_photos = count_unfinished(conn, dir.id, ProcessingStage::Preview)?;
 
-    if photos.is_empty() {
-        println!("No photos found in directory: {}", dir.path);
-        return Err(anyhow!("No photos found in directory: {}", dir.path));
+    if total_photos == 0 {
+        tracing::info!("No previews left to create for directory: {}", dir.path);
+        return Ok(TaskOutcome::Done);
     }
 
-    let local_photo_path = dirs::data_local_dir().unwrap_or_else(|| Path::new(".").to_path_buf());
-
-    let output_folder = local_photo_path.join(APP_NAME).join(dir.id.to_string());
+    let output_folder = preview_folder(&dir.id);
 
     fs::create_dir_all(&output_folder)?;
 
     tracing::info!("Started preview creation for: {}", dir.path);
 
-    // Emit the "preview-start" event to notify that preview generation is beginning.
-    if let Err(e) = app_handle.emit("preview-start", &dir.path) {
-        tracing::error!("Failed to emit preview-start event: {}", e);
-    }
-
-    // Create an unbounded channel for reporting progress.
-    let (progress_tx, mut progress_rx) = mpsc::unbounded_channel::<u32>();
-
-    // Spawn an async task that listens for progress messages and emits them
-    // to the front-end as percentage updates.
-    let progress_handle = {
-        let app_handle = app_handle.clone();
-        tokio::spawn(async move {
-            tracing::warn!("Starting progress listener");
-            while let Some(completed) = progress_rx.recv().await {
-                // Calculate progress percentage.
-                let progress = (completed as f64 / total_photos as f64) * 100.0;
-                if let Err(e) = app_handle.emit("preview-progress", progress) {
-                    tracing::error!("Failed to emit preview progress: {}", e);
-                }
-                tracing::info!("Sending status {}", progress);
+    let mut reporter = ProgressReporter::start(
+        app_handle,
+        ProgressStage::Preview,
+        &dir,
+        total_photos as u64,
+    );
+
+    // The previews are created on the rayon threads, each one reports its photo and the error
+    // it failed with, if any.
+    let (progress_tx, mut progress_rx) = mpsc::unbounded_channel::<(String, Option<String>)>();
+
+    let progress_handle = tokio::spawn(async move {
+        while let Some((name, error)) = progress_rx.recv().await {
+            if let Some(error) = error {
+                reporter.record_errors(1, error);
             }
-        })
-    };
-    let counter = AtomicU32::new(0);
-
-    photos.par_iter().for_each(|photo| {
-        let input_path = Path::new(&dir.path).join(&photo.name);
-        if !input_path.exists() {
-            tracing::error!("File not found: {:?}", input_path);
-            return;
+            reporter.advance(1, Some(&name));
         }
+        reporter
+    });
+    let mut outcome = TaskOutcome::Done;
 
-        let output_path = output_folder.join(format!("{}.preview.{}", photo.id, "webp"));
-
-        if output_path.exists() {
-            tracing::debug!("Preview already exists, skipping: {:?}", input_path);
-            let new_count = counter.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
-            let _ = progress_tx.send(new_count);
-            return;
+    loop {
+        if !is_task_running(conn, task_id)? {
+            outcome = TaskOutcome::Cancelled;
+            break;
+        }
+        if has_more_urgent_task(conn, task_id)? {
+            tracing::info!("Pausing previews of {} for a more urgent task", dir.path);
+            outcome = TaskOutcome::Yielded;
+            break;
         }
 
-        match image::open(input_path) {
-            Ok(img) => {
-                let resized = img.resize(640, 640, FilterType::CatmullRom);
-                if let Err(e) = resized.save_with_format(&output_path, ImageFormat::WebP) {
-                    tracing::error!("Failed to save preview for {}: {}", photo.path, e);
-                } else {
-                    tracing::debug!("Preview saved at {:?}", output_path);
-                }
-            }
-            Err(e) => {
-                tracing::error!("Failed to open image {}: {}", photo.name, e);
-            }
+        let photos = claim_photos(conn, dir.id, ProcessingStage::Preview, PREVIEW_BATCH_SIZE)?;
+        if photos.is_empty() {
+            break;
         }
 
-        let new_count = counter.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
-        let status = progress_tx.send(new_count);
+        let outcomes: Vec<(Uuid, Result<()>)> = photos
+            .par_iter()
+            .map(|photo| {
+                let outcome = create_preview(&dir, photo, &output_folder);
 
-        tracing::info!("Status is {:?}", status);
-    });
+                let error = outcome.as_ref().err().map(|err| format!("{:#}", err));
+                if progress_tx.send((photo.name.clone(), error)).is_err() {
+                    tracing::warn!("Preview progress listener is gone");
+                }
+                (photo.id, outcome)
+            })
+            .collect();
+
+        finish_photos(conn, ProcessingStage::Preview, None, outcomes)?;
+    }
 
     drop(progress_tx);
-    let _ = progress_handle.await;
+    match progress_handle.await {
+        Ok(reporter) => reporter.finish(),
+        Err(e) => tracing::error!("Preview progress listener failed: {}", e),
+    }
 
-    // Emit the "preview-end" event to notify that preview generation is complete.
-    if let Err(e) = app_handle.emit("preview-end", &dir.path) {
-        tracing::error!("Failed to emit preview-end event: {}", e);
+    if let TaskOutcome::Done = outcome {
+        tracing::info!("Finished processing previews for directory: {}", dir.path);
     }
 
-    tracing::info!("Finished processing previews for directory: {}", dir.path);
-    change_directories_status(conn, &dir.id, "is_imported")
+    Ok(outcome)
 }
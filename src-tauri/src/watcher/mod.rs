@@ -0,0 +1,160 @@
+use crate::task_queue::tasks::Task;
+use crate::task_queue::TaskQueue;
+use anyhow::Result;
+use db_service::db::DbPool;
+use db_service::schema::Directory;
+use db_service::services::directory::get_directories;
+use db_service::services::photo::is_photo;
+use notify_debouncer_full::notify::event::{ModifyKind, RenameMode};
+use notify_debouncer_full::notify::{EventKind, RecommendedWatcher, RecursiveMode};
+use notify_debouncer_full::{
+    new_debouncer, DebounceEventResult, DebouncedEvent, Debouncer, RecommendedCache,
+};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+/// How long the filesystem has to be quiet before the collected events are processed.
+const DEBOUNCE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Watches the imported library roots and keeps the catalog in sync with what happens on disk.
+pub struct LibraryWatcher {
+    debouncer: Debouncer<RecommendedWatcher, RecommendedCache>,
+}
+
+impl LibraryWatcher {
+    pub fn new(db_pool: DbPool, queue: Arc<Mutex<TaskQueue>>) -> Result<Self> {
+        let debouncer = new_debouncer(
+            DEBOUNCE_TIMEOUT,
+            None,
+            move |result: DebounceEventResult| match result {
+                Ok(events) => handle_events(events, &db_pool, &queue),
+                Err(errors) => errors
+                    .iter()
+                    .for_each(|e| tracing::error!("Filesystem watcher error: {:?}", e)),
+            },
+        )?;
+
+        Ok(Self { debouncer })
+    }
+
+    pub fn watch(&mut self, path: &str) -> Result<()> {
+        self.debouncer
+            .watch(Path::new(path), RecursiveMode::Recursive)?;
+        tracing::info!("Watching {} for changes", path);
+
+        Ok(())
+    }
+
+    pub fn unwatch(&mut self, path: &str) -> Result<()> {
+        self.debouncer.unwatch(Path::new(path))?;
+        tracing::info!("Stopped watching {}", path);
+
+        Ok(())
+    }
+
+    pub fn watch_directories(&mut self, dirs: &[Directory]) {
+        for dir in dirs {
+            if let Err(e) = self.watch(&dir.path) {
+                tracing::error!("Failed to watch {}: {}", dir.path, e);
+            }
+        }
+    }
+}
+
+/// Collects the photos found under a path that was created, which can also be a whole folder.
+fn collect_photos(path: &Path, photos: &mut HashSet<PathBuf>) {
+    if path.is_dir() {
+        if let Ok(entries) = fs::read_dir(path) {
+            for entry in entries.flatten() {
+                collect_photos(&entry.path(), photos);
+            }
+        }
+    } else if is_photo(path) {
+        photos.insert(path.to_path_buf());
+    }
+}
+
+/// Finds the imported directory a path belongs to, preferring the most nested one.
+fn owning_directory<'a>(dirs: &'a [Directory], path: &Path) -> Option<&'a Directory> {
+    dirs.iter()
+        .filter(|dir| path.starts_with(&dir.path))
+        .max_by_key(|dir| dir.path.len())
+}
+
+fn handle_events(events: Vec<DebouncedEvent>, db_pool: &DbPool, queue: &Arc<Mutex<TaskQueue>>) {
+    let mut created: HashSet<PathBuf> = HashSet::new();
+    let mut removed: HashSet<PathBuf> = HashSet::new();
+
+    for event in events {
+        match event.kind {
+            EventKind::Create(_)
+            | EventKind::Modify(ModifyKind::Data(_))
+            | EventKind::Modify(ModifyKind::Any)
+            | EventKind::Modify(ModifyKind::Name(RenameMode::To)) => {
+                event
+                    .paths
+                    .iter()
+                    .for_each(|path| collect_photos(path, &mut created));
+            }
+            EventKind::Remove(_) | EventKind::Modify(ModifyKind::Name(RenameMode::From)) => {
+                removed.extend(event.paths.iter().cloned());
+            }
+            EventKind::Modify(ModifyKind::Name(RenameMode::Both)) => {
+                if let [from, to] = &event.paths[..] {
+                    removed.insert(from.clone());
+                    collect_photos(to, &mut created);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // A file deleted and written again inside the debounce window is still there. Removed
+    // paths that aren't photos may be whole folders, so they are kept as well.
+    removed.retain(|path| !path.exists());
+
+    if created.is_empty() && removed.is_empty() {
+        return;
+    }
+
+    let dirs = match db_pool
+        .get()
+        .map_err(anyhow::Error::from)
+        .and_then(|mut conn| get_directories(&mut conn))
+    {
+        Ok(dirs) => dirs,
+        Err(e) => {
+            tracing::error!("Cannot load directories for watcher events: {}", e);
+            return;
+        }
+    };
+
+    let group_by_directory = |paths: HashSet<PathBuf>| {
+        let mut grouped: HashMap<Uuid, Vec<PathBuf>> = HashMap::new();
+        for path in paths {
+            if let Some(dir) = owning_directory(&dirs, &path) {
+                grouped.entry(dir.id).or_default().push(path);
+            }
+        }
+        grouped
+    };
+    let mut created_by_dir = group_by_directory(created);
+    let mut removed_by_dir = group_by_directory(removed);
+
+    // The debouncer runs on its own thread, outside of the async runtime
+    let q = queue.blocking_lock();
+
+    for dir in &dirs {
+        if let Some(paths) = removed_by_dir.remove(&dir.id) {
+            q.add_task(Task::RemovePhotosFromPaths(dir.clone(), paths));
+        }
+        if let Some(paths) = created_by_dir.remove(&dir.id) {
+            q.add_task(Task::InsertPhotosFromPaths(dir.clone(), paths));
+        }
+    }
+}
@@ -4,7 +4,8 @@ import { ReactQueryDevtools } from "@tanstack/react-query-devtools";
 import { RouterProvider } from "react-router";
 import router from "@/Router";
 import { AnimatePresence } from "framer-motion";
-import PreviewNotifier from "@/components/notifications/PreviewNotifier";
+import ImportNotifier from "@/components/notifications/ImportNotifier";
+import ProgressNotifier from "@/components/notifications/ProgressNotifier";
 import Titlebar from "@/components/menu/Titlebar";
 
 export const APP_NAME = "photo-organizer";
@@ -15,7 +16,8 @@ const App: React.FC = () => {
             <Titlebar />
             <RouterProvider router={router} />
             <ReactQueryDevtools initialIsOpen={false} />
-            <PreviewNotifier />
+            <ImportNotifier />
+            <ProgressNotifier />
         </AnimatePresence>
     );
 };
@@ -11,6 +11,8 @@ import { clearSelectedPhotos, clearSelectedTags } from "@/contexts/slices/photos
 import { Link, useNavigate } from "react-router";
 import SidebarContextMenu from "@/components/menu/SidebarMenu";
 import SelectedPhotoPreview from "@/components/sidebar/SelectedPhotoPreview";
+import SmartAlbums from "@/components/sidebar/SmartAlbums";
+import Albums from "@/components/sidebar/Albums";
 import { Card, CardContent, CardHeader, CardTitle } from "./ui/card";
 
 /**
@@ -107,7 +109,6 @@ const groupFoldersByDrive = (folders: FolderType[]): GroupedFolderType[] => {
         id: drive, // Using the drive prefix as the id
         path: drive, // Display the drive in the tree view
         displayName: drive, // Display the drive in the tree view
-        isImported: false,
         isDriveGroup: true,
         children: groups[drive],
         photoCount: groups[drive].reduce((count, folder) => count + folder.photoCount, 0), // Sum of photo counts
@@ -117,9 +118,8 @@ const groupFoldersByDrive = (folders: FolderType[]): GroupedFolderType[] => {
 const folderToTreeElement = (folder: GroupedFolderType): TreeViewElement => ({
     id: folder.id,
     name: folder.path,
-    // You may decide that drive groups are not selectable,
-    // while real folders follow the isImported check.
-    isSelectable: !folder.isImported && !folder.isDriveGroup,
+    // Drive groups are not selectable, only the real folders are.
+    isSelectable: !folder.isDriveGroup,
     children: folder.children ? folder.children.map(folderToTreeElement) : undefined,
 });
 
@@ -189,13 +189,17 @@ const Sidebar: React.FC = () => {
             <Card className="m-2 gap-0">
                 <CardHeader>
                     <CardTitle>
-                        <Link to="/all" onClick={() => dispatch(setPath({ id: "all", path: "", isImported: false, photoCount: 0 }))}>
+                        <Link to="/all" onClick={() => dispatch(setPath({ id: "all", path: "", photoCount: 0 }))}>
                             All Photos
                         </Link>
                     </CardTitle>
                 </CardHeader>
             </Card>
 
+            <Albums />
+
+            <SmartAlbums />
+
             <SidebarContextMenu activeTreeElement={activeTreeElement}>
                 <Card className="m-2 gap-0">
                     <CardHeader>
@@ -0,0 +1,64 @@
+import * as React from "react";
+import { Star } from "lucide-react";
+import { useAppDispatch, useAppSelector } from "@/lib/hooks";
+import { selectCulling, setCulling } from "@/contexts/slices/photosSlice";
+import { COLOR_LABEL_CLASSES, COLOR_LABELS, FLAG_FILTER_LABELS } from "@/lib/culling";
+import { cn } from "@/lib/utils";
+import { ColorLabel, FlagFilter } from "@/types";
+
+const CullingFilters: React.FC = () => {
+    const dispatch = useAppDispatch();
+    const culling = useAppSelector(selectCulling);
+    const minRating = culling.minRating ?? 0;
+    const colorLabels = culling.colorLabels ?? [];
+
+    const toggleColorLabel = (label: ColorLabel) => {
+        const labels = colorLabels.includes(label) ? colorLabels.filter((l) => l !== label) : [...colorLabels, label];
+        dispatch(setCulling({ ...culling, colorLabels: labels }));
+    };
+
+    return (
+        <div className="flex items-center gap-4 border-b px-4 py-2">
+            <div className="flex items-center" title="Minimum rating">
+                {[1, 2, 3, 4, 5].map((stars) => (
+                    <button
+                        key={stars}
+                        type="button"
+                        className="cursor-pointer p-0.5"
+                        // Clicking the current minimum again removes it
+                        onClick={() => dispatch(setCulling({ ...culling, minRating: stars === minRating ? null : stars }))}
+                    >
+                        <Star className={cn("size-4", { "fill-current text-yellow-400": stars <= minRating })} />
+                    </button>
+                ))}
+            </div>
+            <select
+                className="h-8 rounded-md bg-transparent px-2 text-sm hover:bg-accent"
+                value={culling.flag ?? ""}
+                onChange={(e) => dispatch(setCulling({ ...culling, flag: (e.target.value || null) as FlagFilter | null }))}
+            >
+                <option value="">All flags</option>
+                {Object.entries(FLAG_FILTER_LABELS).map(([value, label]) => (
+                    <option key={value} value={value}>
+                        {label}
+                    </option>
+                ))}
+            </select>
+            <div className="flex items-center gap-1">
+                {COLOR_LABELS.map((label) => (
+                    <button
+                        key={label}
+                        type="button"
+                        title={label}
+                        className={cn("size-4 cursor-pointer rounded-full opacity-40", COLOR_LABEL_CLASSES[label], {
+                            "opacity-100 ring-2 ring-offset-1 ring-foreground": colorLabels.includes(label),
+                        })}
+                        onClick={() => toggleColorLabel(label)}
+                    />
+                ))}
+            </div>
+        </div>
+    );
+};
+
+export default CullingFilters;
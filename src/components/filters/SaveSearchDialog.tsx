@@ -0,0 +1,89 @@
+import * as React from "react";
+import { useState } from "react";
+import { useQueryClient } from "@tanstack/react-query";
+import { Button } from "@/components/ui/button";
+import {
+    Dialog,
+    DialogContent,
+    DialogDescription,
+    DialogFooter,
+    DialogHeader,
+    DialogTitle,
+    DialogTrigger,
+} from "@/components/ui/dialog";
+import { createSmartAlbum } from "@/lib/api";
+import { useAppSelector } from "@/lib/hooks";
+import { selectCurrentFolder } from "@/contexts/slices/pathSlice";
+import { selectSelectedTags, selectTagQuery } from "@/contexts/slices/photosSlice";
+
+/**
+ * The selected tags and the typed query as a single tag query, the same way the backend combines
+ * them: any of the selected tags, and the query.
+ */
+const combineTagFilters = (selectedTags: string[], tagQuery: string): string | null => {
+    const terms = [];
+    if (selectedTags.length > 0) {
+        terms.push("(" + selectedTags.map((tag) => `"${tag}"`).join(" OR ") + ")");
+    }
+    if (tagQuery.trim()) {
+        terms.push("(" + tagQuery + ")");
+    }
+    return terms.length > 0 ? terms.join(" AND ") : null;
+};
+
+const SaveSearchDialog: React.FC = () => {
+    const queryClient = useQueryClient();
+    const folder = useAppSelector(selectCurrentFolder);
+    const selectedTags = useAppSelector(selectSelectedTags);
+    const tagQuery = useAppSelector(selectTagQuery);
+    const [open, setOpen] = useState<boolean>(false);
+    const [name, setName] = useState<string>("");
+    const [error, setError] = useState<string | null>(null);
+
+    const save = async () => {
+        try {
+            await createSmartAlbum(name, {
+                directoryId: folder.id && folder.id !== "all" ? folder.id : null,
+                tags: combineTagFilters(Array.from(selectedTags), tagQuery),
+            });
+            await queryClient.invalidateQueries({ queryKey: ["smartAlbums"] });
+            setOpen(false);
+            setName("");
+            setError(null);
+        } catch (e) {
+            setError(String(e));
+        }
+    };
+
+    return (
+        <Dialog open={open} onOpenChange={setOpen}>
+            <DialogTrigger asChild>
+                <Button variant="ghost">Save search</Button>
+            </DialogTrigger>
+            <DialogContent>
+                <DialogHeader>
+                    <DialogTitle>Save as smart album</DialogTitle>
+                    <DialogDescription>
+                        The album keeps the current folder and tag filters, photos matching them later show up in it too.
+                    </DialogDescription>
+                </DialogHeader>
+                <input
+                    className="h-9 w-full rounded-md border bg-transparent px-3 text-sm aria-invalid:border-destructive"
+                    placeholder="Name"
+                    value={name}
+                    aria-invalid={!!error}
+                    onChange={(e) => setName(e.target.value)}
+                    onKeyDown={(e) => e.key === "Enter" && save()}
+                />
+                {error && <div className="text-xs text-destructive">{error}</div>}
+                <DialogFooter>
+                    <Button onClick={save} disabled={!name.trim()}>
+                        Save
+                    </Button>
+                </DialogFooter>
+            </DialogContent>
+        </Dialog>
+    );
+};
+
+export default SaveSearchDialog;
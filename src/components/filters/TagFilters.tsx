@@ -1,16 +1,59 @@
 import * as React from "react";
+import { useEffect, useState } from "react";
 import { ClickableBadge } from "@/components/ui/badge";
 import { motion } from "framer-motion";
 import { useAppDispatch, useAppSelector } from "@/lib/hooks";
-import { addTagFilter, removeTagFilter, selectTags } from "@/contexts/slices/photosSlice";
+import { addTagFilter, removeTagFilter, selectTagQuery, selectTags, setTagQuery } from "@/contexts/slices/photosSlice";
 import { capitalize } from "lodash";
+import { checkTagQuery } from "@/lib/api";
+import { TagQueryError } from "@/types";
 
 const TagFilters: React.FC = () => {
     const tags = useAppSelector(selectTags);
+    const tagQuery = useAppSelector(selectTagQuery);
     const dispatch = useAppDispatch();
+    const [query, setQuery] = useState<string>(tagQuery);
+    const [error, setError] = useState<TagQueryError | null>(null);
+
+    // Checked while typing, the photos are only reloaded once the query is submitted
+    useEffect(() => {
+        if (!query.trim()) {
+            setError(null);
+            return;
+        }
+
+        let cancelled = false;
+        checkTagQuery(query).then((result) => {
+            if (!cancelled) setError(result);
+        });
+        return () => {
+            cancelled = true;
+        };
+    }, [query]);
+
+    const submitQuery = () => {
+        if (!error) dispatch(setTagQuery(query));
+    };
 
     return (
-        <motion.div initial={{ y: -10, opacity: 0 }} animate={{ y: 0, opacity: 1 }} className="flex border-b p-4">
+        <motion.div initial={{ y: -10, opacity: 0 }} animate={{ y: 0, opacity: 1 }} className="flex flex-col gap-2 border-b p-4">
+            <div className="flex flex-col gap-1">
+                <input
+                    className="h-9 w-full rounded-md border bg-transparent px-3 text-sm font-mono aria-invalid:border-destructive"
+                    placeholder="person AND (dog OR cat) NOT car"
+                    value={query}
+                    aria-invalid={!!error}
+                    onChange={(e) => setQuery(e.target.value)}
+                    onKeyDown={(e) => e.key === "Enter" && submitQuery()}
+                    onBlur={submitQuery}
+                />
+                {error && (
+                    <div className="text-xs text-destructive font-mono whitespace-pre">
+                        {query}
+                        {"\n" + " ".repeat(error.position) + "^ " + error.message}
+                    </div>
+                )}
+            </div>
             <div className="flex items-center gap-2 w-full flex-wrap">
                 {tags.map((tag) => (
                     <ClickableBadge
@@ -16,7 +16,7 @@ import {
 } from "@/components/ui/context-menu";
 import { PropsWithChildren } from "react";
 import { Folder, Photo } from "@/types";
-import { deleteFolder } from "@/lib/api";
+import { deleteFolder, setFolderXmpSync, writeFolderSidecars } from "@/lib/api";
 import { useQueryClient } from "@tanstack/react-query";
 
 interface SidebarContextMenuProps {
@@ -36,6 +36,25 @@ const SidebarContextMenu: React.FC<PropsWithChildren<SidebarContextMenuProps>> =
         }
     };
 
+    const folder = activeTreeElement && "photoCount" in activeTreeElement ? activeTreeElement : null;
+
+    const toggleXmpSync = async (enabled: boolean) => {
+        if (!folder) {
+            return;
+        }
+        const summary = await setFolderXmpSync(folder.path, enabled);
+        summary.failures.forEach((failure) => console.warn("Cannot write sidecar:", failure));
+        await queryClient.invalidateQueries({ queryKey: ["folders"] });
+    };
+
+    const writeSidecars = async () => {
+        if (!folder) {
+            return;
+        }
+        const summary = await writeFolderSidecars(folder.path);
+        summary.failures.forEach((failure) => console.warn("Cannot write sidecar:", failure));
+    };
+
     return (
         <ContextMenu>
             <ContextMenuTrigger asChild>{children}</ContextMenuTrigger>
@@ -52,6 +71,13 @@ const SidebarContextMenu: React.FC<PropsWithChildren<SidebarContextMenuProps>> =
                     Reload
                     <ContextMenuShortcut>⌘R</ContextMenuShortcut>
                 </ContextMenuItem>
+                <ContextMenuSeparator />
+                <ContextMenuCheckboxItem disabled={!folder} checked={folder?.xmpSync ?? false} onCheckedChange={toggleXmpSync}>
+                    Sync XMP Sidecars
+                </ContextMenuCheckboxItem>
+                <ContextMenuItem inset disabled={!folder} onClick={writeSidecars}>
+                    Write XMP Sidecars
+                </ContextMenuItem>
                 {/*<ContextMenuSub>*/}
                 {/*    <ContextMenuSubTrigger inset>More Tools</ContextMenuSubTrigger>*/}
                 {/*    <ContextMenuSubContent className="w-48">*/}
@@ -0,0 +1,29 @@
+import { useEffect } from "react";
+import { listen } from "@tauri-apps/api/event";
+import { useQueryClient } from "@tanstack/react-query";
+import { ProgressEvent } from "@/types";
+
+// Folders are scanned in the background, refresh the photo counts once a scan or the
+// metadata of an import is done
+const ImportNotifier = () => {
+    const queryClient = useQueryClient();
+
+    useEffect(() => {
+        const unlisten = listen<ProgressEvent>("progress", ({ payload }) => {
+            if (payload.finished && (payload.stage === "scanning" || payload.stage === "metadata")) {
+                queryClient.invalidateQueries({ queryKey: ["folders"] });
+                queryClient.invalidateQueries({ queryKey: ["photos"] });
+                queryClient.invalidateQueries({ queryKey: ["smartAlbums"] });
+                queryClient.invalidateQueries({ queryKey: ["albums"] });
+            }
+        });
+
+        return () => {
+            unlisten.then((fn) => fn());
+        };
+    }, [queryClient]);
+
+    return null;
+};
+
+export default ImportNotifier;
@@ -0,0 +1,81 @@
+import * as React from "react";
+import { useEffect, useRef } from "react";
+import { toast } from "sonner";
+import { listen } from "@tauri-apps/api/event";
+import { Progress } from "@/components/ui/progress";
+import { Toaster } from "../ui/sonner";
+import { ProgressEvent, ProgressStage } from "@/types";
+
+const STAGE_LABELS: Record<ProgressStage, string> = {
+    scanning: "Scanning folder",
+    metadata: "Reading metadata",
+    preview: "Generating previews",
+    tagging: "Detecting objects",
+    faceAnalysis: "Analysing faces",
+};
+
+const formatEta = (seconds: number) =>
+    seconds < 60 ? `${seconds}s left` : `${Math.round(seconds / 60)} min left`;
+
+const ProgressToast = ({ event }: { event: ProgressEvent }) => {
+    const percentage = event.total > 0 ? (event.done / event.total) * 100 : 0;
+
+    return (
+        <div style={{ padding: "1rem", minWidth: "250px" }}>
+            <p style={{ marginBottom: "0.5rem" }}>
+                {event.finished
+                    ? `${STAGE_LABELS[event.stage]} complete!`
+                    : `${STAGE_LABELS[event.stage]}... ${event.done}/${event.total}`}
+            </p>
+            <Progress value={event.finished ? 100 : percentage} max={100} />
+            <p style={{ marginTop: "0.5rem", fontSize: "0.75rem", opacity: 0.7 }}>
+                {event.currentFile ?? event.directory}
+                {!event.finished && event.etaSeconds != null && ` · ${formatEta(event.etaSeconds)}`}
+            </p>
+            {event.errors > 0 && (
+                <p style={{ fontSize: "0.75rem", color: "var(--destructive)" }}>
+                    {event.errors} failed{event.lastError && `: ${event.lastError}`}
+                </p>
+            )}
+        </div>
+    );
+};
+
+// One toast per directory and stage, updated in place until the stage finishes
+const ProgressNotifier = () => {
+    const toastIds = useRef(new Map<string, string | number>());
+
+    useEffect(() => {
+        const unlisten = listen<ProgressEvent>("progress", ({ payload }) => {
+            const key = `${payload.directoryId}:${payload.stage}`;
+            const toastId = toastIds.current.get(key);
+
+            if (payload.finished) {
+                if (toastId === undefined) {
+                    return;
+                }
+                toast.success(<ProgressToast event={payload} />, { id: toastId });
+                toastIds.current.delete(key);
+                setTimeout(() => toast.dismiss(toastId), 4000);
+                return;
+            }
+
+            toastIds.current.set(
+                key,
+                toast(<ProgressToast event={payload} />, {
+                    id: toastId,
+                    duration: Infinity,
+                    dismissible: true,
+                }),
+            );
+        });
+
+        return () => {
+            unlisten.then((fn) => fn());
+        };
+    }, []);
+
+    return <Toaster visibleToasts={5} />;
+};
+
+export default ProgressNotifier;
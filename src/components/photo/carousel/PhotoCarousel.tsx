@@ -11,6 +11,7 @@ import { selectCurrentPath, selectPreviewDir } from "@/contexts/slices/pathSlice
 import { LAZY_RANGE } from "./constants";
 import { useFullscreen } from "./hooks/useFullscreen";
 import { BackgroundColor, useCarouselKeyNavigation } from "./hooks/useCarouselKeyNavigation";
+import { usePhotoCulling } from "./hooks/usePhotoCulling";
 import FullscreenToggle from "./FullscreenToggle";
 import CarouselSlides from "./CarouselSlides";
 import NavigationArrows from "./NavigationArrows";
@@ -47,6 +48,7 @@ const PhotoCarousel: React.FC = () => {
     const { isFullscreen, setIsFullscreen, elementRef: carouselWrapperRef } = useFullscreen();
     const [backgroundColor, setBackgroundColor] = useState<BackgroundColor>("black");
     useCarouselKeyNavigation(emblaApi, isFullscreen, setIsFullscreen, backgroundColor, setBackgroundColor);
+    const culling = usePhotoCulling(selectedIndex != null ? photos[selectedIndex] : undefined);
 
     // Update emblaIndex when selectedIndex changes
     useEffect(() => {
@@ -153,6 +155,7 @@ const PhotoCarousel: React.FC = () => {
                     currentIndex={selectedIndex}
                     totalCount={photos.length}
                     photoName={photos[selectedIndex]?.name || ""}
+                    culling={culling}
                     backgroundColor={backgroundColor}
                 />
             </div>
@@ -1,16 +1,20 @@
 import * as React from "react";
 import { BackgroundColor } from "@/components/photo/carousel/hooks/useCarouselKeyNavigation";
 import { clsx } from "clsx";
+import { Flag, FlagOff, Star } from "lucide-react";
+import { COLOR_LABEL_CLASSES } from "@/lib/culling";
+import { PhotoCulling } from "@/types";
 
 interface PhotoInfoProps {
     isFullscreen: boolean;
     currentIndex: number;
     totalCount: number;
     photoName: string;
+    culling?: PhotoCulling;
     backgroundColor?: BackgroundColor;
 }
 
-const PhotoInfo: React.FC<PhotoInfoProps> = ({ isFullscreen, currentIndex, totalCount, photoName, backgroundColor }) => {
+const PhotoInfo: React.FC<PhotoInfoProps> = ({ isFullscreen, currentIndex, totalCount, photoName, culling, backgroundColor }) => {
     const infoContainerClass = isFullscreen
         ? "absolute top-8 left-0 right-0 flex flex-col items-center"
         : "mt-4 flex flex-col items-center text-align-left w-full absolute top-8 left-0 right-0";
@@ -22,6 +26,18 @@ const PhotoInfo: React.FC<PhotoInfoProps> = ({ isFullscreen, currentIndex, total
                 {currentIndex + 1} / {totalCount}
             </div>
             <div className={clsx("mt-2 text-lg md:text-xl font-medium line-clamp-1 text-left w-full px-12", color)}>{photoName}</div>
+            {culling && (
+                <div className={clsx("mt-1 flex items-center gap-2 w-full px-12", color)}>
+                    <div className="flex items-center">
+                        {[1, 2, 3, 4, 5].map((stars) => (
+                            <Star key={stars} className={clsx("size-4", { "fill-current text-yellow-400": stars <= culling.rating })} />
+                        ))}
+                    </div>
+                    {culling.flag === "pick" && <Flag className="size-4 fill-current" />}
+                    {culling.flag === "reject" && <FlagOff className="size-4 text-red-500" />}
+                    {culling.colorLabel && <span className={clsx("size-3 rounded-full", COLOR_LABEL_CLASSES[culling.colorLabel])} />}
+                </div>
+            )}
         </div>
     );
 };
@@ -0,0 +1,72 @@
+import * as React from "react";
+import { useQuery, useQueryClient } from "@tanstack/react-query";
+import { FolderPlusIcon, ImageIcon, ImageMinusIcon } from "lucide-react";
+import {
+    ContextMenuItem,
+    ContextMenuSeparator,
+    ContextMenuSub,
+    ContextMenuSubContent,
+    ContextMenuSubTrigger,
+} from "@/components/ui/context-menu";
+import { addPhotosToAlbum, getAlbums, removePhotosFromAlbum, setAlbumCover } from "@/lib/api";
+import { useAppSelector } from "@/lib/hooks";
+import { selectAlbumId } from "@/contexts/slices/pathSlice";
+import { selectSelectedPhoto } from "@/contexts/slices/photosSlice";
+
+// Adds the photo shown to an album, or edits the album it is shown from
+const AlbumMenuItems: React.FC = () => {
+    const queryClient = useQueryClient();
+    const photo = useAppSelector(selectSelectedPhoto);
+    const albumId = useAppSelector(selectAlbumId);
+    const { data: albums } = useQuery({ queryKey: ["albums"], queryFn: getAlbums });
+
+    if (!photo) {
+        return null;
+    }
+
+    const refresh = async () => {
+        await queryClient.invalidateQueries({ queryKey: ["albums"] });
+        await queryClient.invalidateQueries({ queryKey: ["photos"] });
+    };
+
+    return (
+        <>
+            <ContextMenuSeparator />
+            <ContextMenuSub>
+                <ContextMenuSubTrigger className="cursor-pointer" disabled={!albums?.length}>
+                    <FolderPlusIcon className="mr-2 h-4 w-4" />
+                    Add to Album
+                </ContextMenuSubTrigger>
+                <ContextMenuSubContent className="w-48">
+                    {albums?.map((album) => (
+                        <ContextMenuItem
+                            key={album.id}
+                            className="cursor-pointer"
+                            onClick={() => addPhotosToAlbum(album.id, [photo.id]).then(refresh)}
+                        >
+                            {album.name}
+                        </ContextMenuItem>
+                    ))}
+                </ContextMenuSubContent>
+            </ContextMenuSub>
+            {albumId && (
+                <>
+                    <ContextMenuItem className="cursor-pointer" onClick={() => setAlbumCover(albumId, photo.id).then(refresh)}>
+                        <ImageIcon className="mr-2 h-4 w-4" />
+                        Set as Album Cover
+                    </ContextMenuItem>
+                    <ContextMenuItem
+                        variant="destructive"
+                        className="cursor-pointer"
+                        onClick={() => removePhotosFromAlbum(albumId, [photo.id]).then(refresh)}
+                    >
+                        <ImageMinusIcon className="mr-2 h-4 w-4" />
+                        Remove from Album
+                    </ContextMenuItem>
+                </>
+            )}
+        </>
+    );
+};
+
+export default AlbumMenuItems;
@@ -18,6 +18,7 @@ import {
     ZoomInIcon,
     ZoomOutIcon,
 } from "lucide-react";
+import AlbumMenuItems from "./AlbumMenuItems";
 
 interface ImageContextMenuProps {
     children: React.ReactNode;
@@ -122,6 +123,8 @@ const ImageContextMenu: React.FC<ImageContextMenuProps> = ({
                     Share
                     <ContextMenuShortcut>S</ContextMenuShortcut>
                 </ContextMenuItem>
+
+                <AlbumMenuItems />
             </ContextMenuContent>
         </ContextMenu>
     );
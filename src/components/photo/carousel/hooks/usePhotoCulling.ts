@@ -0,0 +1,54 @@
+import { useEffect } from "react";
+import { useQuery, useQueryClient } from "@tanstack/react-query";
+import { getPhotoCulling, setPhotoColorLabel, setPhotoFlag, setPhotoRating } from "@/lib/api";
+import { COLOR_LABEL_KEYS } from "@/lib/culling";
+import { Photo, PhotoCulling } from "@/types";
+
+// Culls the photo shown with the keys of Lightroom: 0 to 5 rate it, P picks, X rejects,
+// U clears the flag and 6 to 9 toggle a color label
+export const usePhotoCulling = (photo?: Photo): PhotoCulling | undefined => {
+    const queryClient = useQueryClient();
+    const { data: culling } = useQuery({
+        queryKey: ["culling", photo?.id],
+        queryFn: () => getPhotoCulling([photo!.id]).then((culling) => culling[0] ?? null),
+        enabled: !!photo,
+    });
+
+    useEffect(() => {
+        if (!photo) return;
+
+        const onKeyDown = async (e: KeyboardEvent) => {
+            if (document.activeElement?.tagName.match(/input|textarea|select/i) || e.ctrlKey || e.metaKey) {
+                return;
+            }
+
+            const key = e.key.toLowerCase();
+            const ids = [photo.id];
+            if (/^[0-5]$/.test(key)) {
+                await setPhotoRating(ids, Number(key));
+            } else if (key === "p") {
+                await setPhotoFlag(ids, "pick");
+            } else if (key === "x") {
+                await setPhotoFlag(ids, "reject");
+            } else if (key === "u") {
+                await setPhotoFlag(ids, null);
+            } else if (COLOR_LABEL_KEYS[key]) {
+                const label = COLOR_LABEL_KEYS[key];
+                await setPhotoColorLabel(ids, culling?.colorLabel === label ? null : label);
+            } else {
+                return;
+            }
+
+            await queryClient.invalidateQueries({ queryKey: ["culling", photo.id] });
+            // The grid may be filtered or sorted by what just changed, it is read again once shown
+            // rather than moving the photos under the carousel
+            queryClient.invalidateQueries({ queryKey: ["photos"], refetchType: "none" });
+            queryClient.invalidateQueries({ queryKey: ["photoCount"], refetchType: "none" });
+        };
+
+        window.addEventListener("keydown", onKeyDown);
+        return () => window.removeEventListener("keydown", onKeyDown);
+    }, [photo, culling, queryClient]);
+
+    return culling ?? undefined;
+};
@@ -1,16 +1,34 @@
 import * as React from "react";
 import { useEffect, useState } from "react";
 import PhotoGrid from "@/components/photo/grid/PhotoGrid";
-import { useAppSelector } from "@/lib/hooks";
-import { selectPhotos } from "@/contexts/slices/photosSlice";
+import { useAppDispatch, useAppSelector } from "@/lib/hooks";
+import { selectPhotoCount, selectPhotos, selectSort, selectSortDescending, setSort } from "@/contexts/slices/photosSlice";
 import { Slider } from "@/components/ui/slider";
-import { useParams } from "react-router";
+import { useOutletContext, useParams } from "react-router";
 import { Button } from "@/components/ui/button";
 import TagFilters from "@/components/filters/TagFilters";
+import CullingFilters from "@/components/filters/CullingFilters";
+import SaveSearchDialog from "@/components/filters/SaveSearchDialog";
 import FacesDisplay from "@/components/faces/FacesDisplay";
+import { ArrowDownWideNarrow, ArrowUpNarrowWide } from "lucide-react";
+import { PhotoSort } from "@/types";
+import { PhotoOutletContext } from "@/pages/HomePage";
+
+const SORT_LABELS: Record<PhotoSort, string> = {
+    captureDate: "Capture date",
+    importDate: "Import date",
+    name: "Name",
+    fileSize: "File size",
+    rating: "Rating",
+};
 
 const PhotoArea: React.FC = () => {
+    const dispatch = useAppDispatch();
     const photos = useAppSelector(selectPhotos);
+    const photoCount = useAppSelector(selectPhotoCount);
+    const sort = useAppSelector(selectSort);
+    const descending = useAppSelector(selectSortDescending);
+    const { loadMorePhotos } = useOutletContext<PhotoOutletContext>();
     const { directory } = useParams<{ directory: string }>();
     const [gridSize, setGridSize] = useState<[number]>([4]);
     const [filtersOpen, setFiltersOpen] = useState<boolean>(false);
@@ -43,6 +61,21 @@ const PhotoArea: React.FC = () => {
                 <Button variant="ghost" onClick={() => setFiltersOpen(!filtersOpen)}>
                     Filters
                 </Button>
+                <SaveSearchDialog />
+                <select
+                    className="h-9 rounded-md bg-transparent px-2 text-sm hover:bg-accent"
+                    value={sort}
+                    onChange={(e) => dispatch(setSort({ sort: e.target.value as PhotoSort, descending }))}
+                >
+                    {Object.entries(SORT_LABELS).map(([value, label]) => (
+                        <option key={value} value={value}>
+                            {label}
+                        </option>
+                    ))}
+                </select>
+                <Button variant="ghost" size="icon" onClick={() => dispatch(setSort({ sort, descending: !descending }))}>
+                    {descending ? <ArrowDownWideNarrow /> : <ArrowUpNarrowWide />}
+                </Button>
                 <Slider
                     className="w-80"
                     defaultValue={gridSize}
@@ -58,8 +91,9 @@ const PhotoArea: React.FC = () => {
                 />
             </div>
             {filtersOpen && <TagFilters />}
+            {filtersOpen && <CullingFilters />}
             {facesOpen && <FacesDisplay />}
-            <PhotoGrid photos={photos} columnCount={gridSize[0]} />
+            <PhotoGrid photos={photos} photoCount={photoCount} columnCount={gridSize[0]} onEndReached={loadMorePhotos} />
         </>
     );
 };
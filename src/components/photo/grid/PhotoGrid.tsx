@@ -8,10 +8,13 @@ import { useParams } from "react-router";
 
 interface PhotoGridProps {
     photos: Photo[];
+    // Size of the grid, photos past the loaded ones are read when they get close
+    photoCount?: number;
     columnCount: number;
+    onEndReached?: () => void;
 }
 
-const PhotoGrid: React.FC<PhotoGridProps> = ({ photos, columnCount = 3 }) => {
+const PhotoGrid: React.FC<PhotoGridProps> = ({ photos, photoCount = 0, columnCount = 3, onEndReached }) => {
     const parentRef = useRef<HTMLDivElement>(null);
     const { directory } = useParams<{ directory: string }>();
     const [sessionOffset, setSessionOffset] = useState<number>(0);
@@ -26,7 +29,7 @@ const PhotoGrid: React.FC<PhotoGridProps> = ({ photos, columnCount = 3 }) => {
     }, [directory, getScrollKey]);
 
     // For the virtualizer
-    const rowCount = Math.ceil(photos?.length / columnCount);
+    const rowCount = Math.ceil(Math.max(photos?.length, photoCount) / columnCount);
 
     const calculateHeight = useCallback(() => {
         if (!parentRef.current) return 280;
@@ -67,6 +70,16 @@ const PhotoGrid: React.FC<PhotoGridProps> = ({ photos, columnCount = 3 }) => {
         };
     }, [rowVirtualizer]);
 
+    // Read the next page once the rows being shown get close to the last loaded photo
+    const virtualItems = rowVirtualizer.getVirtualItems();
+    const lastVisibleRow = virtualItems[virtualItems.length - 1]?.index ?? 0;
+    useEffect(() => {
+        const loadedRows = Math.ceil(photos.length / columnCount);
+        if (lastVisibleRow >= loadedRows - 3) {
+            onEndReached?.();
+        }
+    }, [lastVisibleRow, photos.length, columnCount, onEndReached]);
+
     // Save scroll position per directory to session storage
     const updateIfScrolling = (e: React.UIEvent<HTMLDivElement, UIEvent>) => {
         // TODO: Make this more efficient
@@ -79,7 +92,7 @@ const PhotoGrid: React.FC<PhotoGridProps> = ({ photos, columnCount = 3 }) => {
         <LayoutGroup>
             <div ref={parentRef} className="h-screen overflow-auto relative" onScroll={updateIfScrolling}>
                 <div className="relative" style={{ height: rowVirtualizer.getTotalSize() }}>
-                    {rowVirtualizer.getVirtualItems().map((virtualRow) => {
+                    {virtualItems.map((virtualRow) => {
                         const rowIndex = virtualRow.index;
                         return (
                             <div
@@ -21,6 +21,7 @@ const PhotoHoverCard: React.FC<HoverCardProps> = ({ photo }) => {
             <h4 className="text-sm font-semibold">Name: {photo.name}</h4>
             {summary ? (
                 <>
+                    {summary.caption && <p className="text-sm italic">{summary.caption}</p>}
                     <p className="text-sm">
                         Date: {summary.date_time_original ? new Date(summary.date_time_original).toLocaleString() : "N/A"}
                     </p>
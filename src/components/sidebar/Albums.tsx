@@ -0,0 +1,125 @@
+import * as React from "react";
+import { useState } from "react";
+import { useQuery, useQueryClient } from "@tanstack/react-query";
+import { useNavigate } from "react-router";
+import { Images, Plus } from "lucide-react";
+import { createAlbum, deleteAlbum, getAlbums } from "@/lib/api";
+import { useAppDispatch, useAppSelector } from "@/lib/hooks";
+import { selectAlbumId, selectPreviewDir, setAlbum } from "@/contexts/slices/pathSlice";
+import { clearSelectedPhotos } from "@/contexts/slices/photosSlice";
+import { Album } from "@/types";
+import { getPreviewPath, cn } from "@/lib/utils";
+import { Button } from "@/components/ui/button";
+import { ContextMenu, ContextMenuContent, ContextMenuItem, ContextMenuTrigger } from "@/components/ui/context-menu";
+import { Card, CardContent, CardHeader, CardTitle } from "@/components/ui/card";
+import { Dialog, DialogContent, DialogFooter, DialogHeader, DialogTitle } from "@/components/ui/dialog";
+
+const Albums: React.FC = () => {
+    const dispatch = useAppDispatch();
+    const navigate = useNavigate();
+    const queryClient = useQueryClient();
+    const albumId = useAppSelector(selectAlbumId);
+    const previewDir = useAppSelector(selectPreviewDir);
+    const [activeAlbum, setActiveAlbum] = useState<Album | null>(null);
+    const [createOpen, setCreateOpen] = useState<boolean>(false);
+    const [name, setName] = useState<string>("");
+    const [error, setError] = useState<string | null>(null);
+    const { data: albums } = useQuery({ queryKey: ["albums"], queryFn: getAlbums });
+
+    const create = async () => {
+        try {
+            await createAlbum(name);
+            await queryClient.invalidateQueries({ queryKey: ["albums"] });
+            setCreateOpen(false);
+            setName("");
+            setError(null);
+        } catch (e) {
+            setError(String(e));
+        }
+    };
+
+    const deleteActiveAlbum = async () => {
+        if (!activeAlbum) {
+            return;
+        }
+        await deleteAlbum(activeAlbum.id);
+        await queryClient.invalidateQueries({ queryKey: ["albums"] });
+    };
+
+    return (
+        <>
+            <ContextMenu>
+                <ContextMenuTrigger asChild>
+                    <Card className="m-2 gap-0">
+                        <CardHeader className="flex items-center justify-between">
+                            <CardTitle>Albums</CardTitle>
+                            <Button variant="ghost" size="icon" className="size-6" onClick={() => setCreateOpen(true)}>
+                                <Plus />
+                            </Button>
+                        </CardHeader>
+                        <CardContent className="flex flex-col gap-1 p-2">
+                            {albums?.map((album) => (
+                                <button
+                                    key={album.id}
+                                    type="button"
+                                    className={cn("flex items-center justify-between gap-1 pr-1 rounded-md text-sm cursor-pointer", {
+                                        "bg-muted": album.id === albumId,
+                                    })}
+                                    onClick={() => {
+                                        dispatch(setAlbum(album.id));
+                                        dispatch(clearSelectedPhotos());
+                                        navigate("/album-" + album.id);
+                                    }}
+                                    onContextMenu={() => setActiveAlbum(album)}
+                                >
+                                    <div className="flex items-center gap-1">
+                                        {album.coverPhoto ? (
+                                            <img
+                                                src={getPreviewPath(album.coverPhoto.path, album.coverPhoto.id, previewDir)}
+                                                alt={album.name}
+                                                className="size-4 rounded-sm object-cover"
+                                            />
+                                        ) : (
+                                            <Images className="size-4" />
+                                        )}
+                                        <span className="truncate">{album.name}</span>
+                                    </div>
+                                    <span className="text-xs text-gray-600">{album.photoCount}</span>
+                                </button>
+                            ))}
+                        </CardContent>
+                    </Card>
+                </ContextMenuTrigger>
+                <ContextMenuContent className="w-64">
+                    <ContextMenuItem variant="destructive" inset disabled={!activeAlbum} onClick={deleteActiveAlbum}>
+                        Delete
+                    </ContextMenuItem>
+                </ContextMenuContent>
+            </ContextMenu>
+
+            <Dialog open={createOpen} onOpenChange={setCreateOpen}>
+                <DialogContent>
+                    <DialogHeader>
+                        <DialogTitle>New album</DialogTitle>
+                    </DialogHeader>
+                    <input
+                        className="h-9 w-full rounded-md border bg-transparent px-3 text-sm aria-invalid:border-destructive"
+                        placeholder="Name"
+                        value={name}
+                        aria-invalid={!!error}
+                        onChange={(e) => setName(e.target.value)}
+                        onKeyDown={(e) => e.key === "Enter" && create()}
+                    />
+                    {error && <div className="text-xs text-destructive">{error}</div>}
+                    <DialogFooter>
+                        <Button onClick={create} disabled={!name.trim()}>
+                            Create
+                        </Button>
+                    </DialogFooter>
+                </DialogContent>
+            </Dialog>
+        </>
+    );
+};
+
+export default Albums;
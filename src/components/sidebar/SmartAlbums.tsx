@@ -0,0 +1,78 @@
+import * as React from "react";
+import { useState } from "react";
+import { useQuery, useQueryClient } from "@tanstack/react-query";
+import { useNavigate } from "react-router";
+import { Sparkles } from "lucide-react";
+import { deleteSmartAlbum, getSmartAlbums } from "@/lib/api";
+import { useAppDispatch, useAppSelector } from "@/lib/hooks";
+import { selectSmartAlbumId, setSmartAlbum } from "@/contexts/slices/pathSlice";
+import { clearSelectedPhotos } from "@/contexts/slices/photosSlice";
+import { SmartAlbum } from "@/types";
+import { ContextMenu, ContextMenuContent, ContextMenuItem, ContextMenuTrigger } from "@/components/ui/context-menu";
+import { Card, CardContent, CardHeader, CardTitle } from "@/components/ui/card";
+import { cn } from "@/lib/utils";
+
+// Saved searches, their counts are evaluated again every time the list is loaded
+const SmartAlbums: React.FC = () => {
+    const dispatch = useAppDispatch();
+    const navigate = useNavigate();
+    const queryClient = useQueryClient();
+    const smartAlbumId = useAppSelector(selectSmartAlbumId);
+    const [activeAlbum, setActiveAlbum] = useState<SmartAlbum | null>(null);
+    const { data: albums } = useQuery({ queryKey: ["smartAlbums"], queryFn: getSmartAlbums });
+
+    const deleteActiveAlbum = async () => {
+        if (!activeAlbum) {
+            return;
+        }
+        await deleteSmartAlbum(activeAlbum.id);
+        await queryClient.invalidateQueries({ queryKey: ["smartAlbums"] });
+    };
+
+    if (!albums?.length) {
+        return null;
+    }
+
+    return (
+        <ContextMenu>
+            <ContextMenuTrigger asChild>
+                <Card className="m-2 gap-0">
+                    <CardHeader>
+                        <CardTitle>Smart albums</CardTitle>
+                    </CardHeader>
+                    <CardContent className="flex flex-col gap-1 p-2">
+                        {albums.map((album) => (
+                            <button
+                                key={album.id}
+                                type="button"
+                                title={album.query.tags ?? undefined}
+                                className={cn("flex items-center justify-between gap-1 pr-1 rounded-md text-sm cursor-pointer", {
+                                    "bg-muted": album.id === smartAlbumId,
+                                })}
+                                onClick={() => {
+                                    dispatch(setSmartAlbum(album.id));
+                                    dispatch(clearSelectedPhotos());
+                                    navigate("/album-" + album.id);
+                                }}
+                                onContextMenu={() => setActiveAlbum(album)}
+                            >
+                                <div className="flex items-center gap-1">
+                                    <Sparkles className="size-4" />
+                                    <span className="truncate">{album.name}</span>
+                                </div>
+                                <span className="text-xs text-gray-600">{album.photoCount}</span>
+                            </button>
+                        ))}
+                    </CardContent>
+                </Card>
+            </ContextMenuTrigger>
+            <ContextMenuContent className="w-64">
+                <ContextMenuItem variant="destructive" inset onClick={deleteActiveAlbum}>
+                    Delete
+                </ContextMenuItem>
+            </ContextMenuContent>
+        </ContextMenu>
+    );
+};
+
+export default SmartAlbums;
@@ -4,11 +4,16 @@ import { Folder } from "@/types/folder";
 
 export interface PathState {
     folder: Folder;
+    // Shown instead of the folder while set
+    smartAlbumId: string | null;
+    albumId: string | null;
     previewsDir: string;
 }
 
 const initialState: PathState = {
-    folder: { id: "", path: "", isImported: false, photoCount: 0 },
+    folder: { id: "", path: "", photoCount: 0 },
+    smartAlbumId: null,
+    albumId: null,
     previewsDir: "",
 };
 
@@ -18,6 +23,16 @@ export const pathSlice = createSlice({
     reducers: {
         setPath: (state, action: PayloadAction<Folder>) => {
             state.folder = action.payload;
+            state.smartAlbumId = null;
+            state.albumId = null;
+        },
+        setSmartAlbum: (state, action: PayloadAction<string>) => {
+            state.smartAlbumId = action.payload;
+            state.albumId = null;
+        },
+        setAlbum: (state, action: PayloadAction<string>) => {
+            state.albumId = action.payload;
+            state.smartAlbumId = null;
         },
         setPreviewsDir: (state, action: PayloadAction<string>) => {
             state.previewsDir = action.payload;
@@ -25,10 +40,12 @@ export const pathSlice = createSlice({
     },
 });
 
-export const { setPath, setPreviewsDir } = pathSlice.actions;
+export const { setPath, setSmartAlbum, setAlbum, setPreviewsDir } = pathSlice.actions;
 
 export const selectCurrentPath = (state: RootState) => state.path.folder.path;
 export const selectCurrentFolder = (state: RootState) => state.path.folder;
+export const selectSmartAlbumId = (state: RootState) => state.path.smartAlbumId;
+export const selectAlbumId = (state: RootState) => state.path.albumId;
 export const selectPreviewDir = (state: RootState) => state.path.previewsDir;
 
 export default pathSlice.reducer;
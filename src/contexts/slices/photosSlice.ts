@@ -1,11 +1,19 @@
 import { createSelector, createSlice, PayloadAction } from "@reduxjs/toolkit";
 import { RootState } from "@/lib/store";
-import { Photo, PhotoData } from "@/types";
+import { CullingFilter, Photo, PhotoData, PhotoSort } from "@/types";
 
 export interface PhotosState {
     photos: Photo[];
+    // All the photos of the current filters, including the pages not loaded yet
+    photoCount: number;
+    sort: PhotoSort;
+    sortDescending: boolean;
     tags: string[];
     selectedTags: string[];
+    // Boolean query over the tags, e.g. "person AND dog NOT car"
+    tagQuery: string;
+    // Rating, flag and color labels the photos must have
+    culling: CullingFilter;
     facesClusters: Record<string, Record<string, string[]>>;
     selectedPhoto?: Photo;
     selectedPhotoIndex?: number;
@@ -13,8 +21,13 @@ export interface PhotosState {
 
 const initialState: PhotosState = {
     photos: [],
+    photoCount: 0,
+    sort: "name",
+    sortDescending: false,
     tags: [],
     selectedTags: [],
+    tagQuery: "",
+    culling: {},
     facesClusters: {},
 };
 
@@ -26,6 +39,16 @@ export const photosSlice = createSlice({
             state.photos = action.payload.photos;
             state.tags = action.payload.tags;
         },
+        setPhotoCount: (state, action: PayloadAction<number>) => {
+            state.photoCount = action.payload;
+        },
+        setCulling: (state, action: PayloadAction<CullingFilter>) => {
+            state.culling = action.payload;
+        },
+        setSort: (state, action: PayloadAction<{ sort: PhotoSort; descending: boolean }>) => {
+            state.sort = action.payload.sort;
+            state.sortDescending = action.payload.descending;
+        },
         setFaceClusters: (state, action: PayloadAction<Record<string, Record<string, string[]>>>) => {
             // Sort clusters by the sum of face array lengths in each cluster
             const sortedEntries = Object.entries(action.payload).sort(([, a], [, b]) => {
@@ -70,11 +93,17 @@ export const photosSlice = createSlice({
         clearSelectedTags: (state) => {
             state.selectedTags = [];
         },
+        setTagQuery: (state, action: PayloadAction<string>) => {
+            state.tagQuery = action.payload;
+        },
     },
 });
 
 export const {
     setPhotos,
+    setPhotoCount,
+    setSort,
+    setCulling,
     setFaceClusters,
     setSelectedPhoto,
     setNextPhoto,
@@ -83,9 +112,14 @@ export const {
     addTagFilter,
     removeTagFilter,
     clearSelectedTags,
+    setTagQuery,
 } = photosSlice.actions;
 
 export const selectPhotos = (state: RootState) => state.photo.photos;
+export const selectPhotoCount = (state: RootState) => state.photo.photoCount;
+export const selectSort = (state: RootState) => state.photo.sort;
+export const selectSortDescending = (state: RootState) => state.photo.sortDescending;
+export const selectCulling = (state: RootState) => state.photo.culling;
 export const selectFaces = (state: RootState) => state.photo.facesClusters;
 export const selectTags = (state: RootState) => state.photo.tags;
 export const selectSelectedPhoto = (state: RootState) => state.photo.selectedPhoto;
@@ -103,6 +137,7 @@ export const selectPrevPhoto = createSelector(selectPhotos, selectSelectedPhotoI
     index: index - 1,
 }));
 export const selectSelectedTags = (state: RootState) => state.photo.selectedTags;
+export const selectTagQuery = (state: RootState) => state.photo.tagQuery;
 
 export const selectPhotoWithNeighbours = createSelector(selectCurrentPhoto, selectNextPhoto, selectPrevPhoto, (b, c, a) => [a, b, c]);
 
@@ -1,5 +1,32 @@
 import { invoke } from "@tauri-apps/api/core";
-import { Folder, PhotoData, PhotoSummary } from "@/types";
+import {
+    Album,
+    ColorLabel,
+    CullingFilter,
+    DuplicateReport,
+    Folder,
+    FolderNode,
+    GeoBounds,
+    GeoPhoto,
+    GeotagMatch,
+    GeotagOptions,
+    GeotagSummary,
+    Photo,
+    PhotoCluster,
+    PhotoCulling,
+    PhotoData,
+    PhotoFlag,
+    PhotoPageRequest,
+    PhotoQuery,
+    PhotoSummary,
+    PlaceFilter,
+    PlaceNode,
+    SearchResults,
+    SidecarSummary,
+    SimilarPhoto,
+    SmartAlbum,
+    TagQueryError,
+} from "@/types";
 
 export async function getFolders(): Promise<Folder[]> {
     return invoke("get_folders");
@@ -9,8 +36,121 @@ export async function addFolder(path: string): Promise<Folder> {
     return invoke("add_folder", { path });
 }
 
-export async function getPhotosAtPath(path: string, tagFilters: string[]): Promise<PhotoData> {
-    return invoke("get_photos_from_path", { path, tagFilters });
+export async function rescanFolder(path: string): Promise<void> {
+    return invoke("rescan_folder", { path });
+}
+
+export async function getSubFolders(path: string): Promise<FolderNode> {
+    return invoke("get_sub_folders", { path });
+}
+
+export async function getPhotosAtPath(
+    path: string,
+    tagFilters: string[],
+    tagQuery?: string,
+    subFolder?: string,
+    place?: PlaceFilter,
+    page?: PhotoPageRequest,
+    culling?: CullingFilter,
+): Promise<PhotoData> {
+    return invoke("get_photos_from_path", { path, tagFilters, tagQuery, subFolder, place, culling, page });
+}
+
+export async function countPhotosAtPath(
+    path: string,
+    tagFilters: string[],
+    tagQuery?: string,
+    subFolder?: string,
+    place?: PlaceFilter,
+    culling?: CullingFilter,
+): Promise<number> {
+    return invoke("count_photos_from_path", { path, tagFilters, tagQuery, subFolder, place, culling });
+}
+
+export async function searchPhotos(query: PhotoQuery, page?: PhotoPageRequest): Promise<SearchResults> {
+    return invoke("search_photos", { query, page });
+}
+
+export async function getSmartAlbums(): Promise<SmartAlbum[]> {
+    return invoke("get_smart_albums");
+}
+
+export async function createSmartAlbum(name: string, query: PhotoQuery): Promise<SmartAlbum> {
+    return invoke("create_smart_album", { name, query });
+}
+
+export async function updateSmartAlbum(id: string, name: string, query: PhotoQuery): Promise<SmartAlbum> {
+    return invoke("update_smart_album", { id, name, query });
+}
+
+export async function deleteSmartAlbum(id: string): Promise<number> {
+    return invoke("delete_smart_album", { id });
+}
+
+export async function getSmartAlbumPhotos(id: string, page?: PhotoPageRequest): Promise<SearchResults> {
+    return invoke("get_smart_album_photos", { id, page });
+}
+
+// Ratings, flags and labels are set on many photos at once, they return how many were updated
+export async function setPhotoRating(photoIds: string[], rating: number): Promise<number> {
+    return invoke("set_photo_rating", { photoIds, rating });
+}
+
+export async function setPhotoFlag(photoIds: string[], flag: PhotoFlag | null): Promise<number> {
+    return invoke("set_photo_flag", { photoIds, flag });
+}
+
+export async function setPhotoColorLabel(photoIds: string[], colorLabel: ColorLabel | null): Promise<number> {
+    return invoke("set_photo_color_label", { photoIds, colorLabel });
+}
+
+export async function getPhotoCulling(photoIds: string[]): Promise<PhotoCulling[]> {
+    return invoke("get_photo_culling", { photoIds });
+}
+
+export async function getAlbums(): Promise<Album[]> {
+    return invoke("get_albums");
+}
+
+export async function createAlbum(name: string): Promise<Album> {
+    return invoke("create_album", { name });
+}
+
+export async function renameAlbum(id: string, name: string): Promise<Album> {
+    return invoke("rename_album", { id, name });
+}
+
+export async function deleteAlbum(id: string): Promise<number> {
+    return invoke("delete_album", { id });
+}
+
+export async function getAlbumPhotos(id: string): Promise<Photo[]> {
+    return invoke("get_album_photos", { id });
+}
+
+export async function addPhotosToAlbum(id: string, photoIds: string[]): Promise<number> {
+    return invoke("add_photos_to_album", { id, photoIds });
+}
+
+export async function removePhotosFromAlbum(id: string, photoIds: string[]): Promise<number> {
+    return invoke("remove_photos_from_album", { id, photoIds });
+}
+
+// Without before, the photos are moved to the end of the album
+export async function moveAlbumPhotos(id: string, photoIds: string[], before?: string): Promise<void> {
+    return invoke("move_album_photos", { id, photoIds, before });
+}
+
+export async function setAlbumCover(id: string, photoId: string | null): Promise<Album> {
+    return invoke("set_album_cover", { id, photoId });
+}
+
+export async function checkTagQuery(query: string): Promise<TagQueryError | null> {
+    return invoke("check_tag_query", { query });
+}
+
+export async function getPlaces(path: string): Promise<PlaceNode[]> {
+    return invoke("get_places", { path });
 }
 
 export async function getFaceClusters(path: string): Promise<Record<string, Record<string, string[]>>> {
@@ -18,6 +158,30 @@ export async function getFaceClusters(path: string): Promise<Record<string, Reco
     return invoke("get_face_clusters", { dirs });
 }
 
+export async function setFolderTimeZone(path: string, utcOffsetMinutes: number | null): Promise<number> {
+    return invoke("set_folder_time_zone", { path, utcOffsetMinutes });
+}
+
+export async function setFolderXmpSync(path: string, enabled: boolean): Promise<SidecarSummary> {
+    return invoke("set_folder_xmp_sync", { path, enabled });
+}
+
+export async function writeFolderSidecars(path: string): Promise<SidecarSummary> {
+    return invoke("write_folder_sidecars", { path });
+}
+
+export async function addPhotoTags(photoIds: string[], tags: string[]): Promise<number> {
+    return invoke("add_photo_tags", { photoIds, tags });
+}
+
+export async function removePhotoTags(photoIds: string[], tags: string[]): Promise<number> {
+    return invoke("remove_photo_tags", { photoIds, tags });
+}
+
+export async function adjustCaptureTimes(photoIds: string[], seconds: number): Promise<number> {
+    return invoke("adjust_capture_times", { photoIds, seconds });
+}
+
 export async function deleteFolder(path: string): Promise<void> {
     return invoke("delete_folder", { path });
 }
@@ -25,3 +189,35 @@ export async function deleteFolder(path: string): Promise<void> {
 export async function getPhotoSummary(photoIds: string[]): Promise<PhotoSummary> {
     return invoke("get_basic_metadata", { photoIds });
 }
+
+export async function getDuplicates(): Promise<DuplicateReport> {
+    return invoke("get_duplicates");
+}
+
+export async function getSimilarPhotos(path: string, maxDistance?: number): Promise<SimilarPhoto[][]> {
+    return invoke("get_similar_photos", { path, maxDistance });
+}
+
+export async function keepDuplicate(keep: string, discard: string[]): Promise<string[]> {
+    return invoke("keep_duplicate", { keep, discard });
+}
+
+export async function getPhotosInArea(bounds: GeoBounds): Promise<GeoPhoto[]> {
+    return invoke("get_photos_in_area", { bounds });
+}
+
+export async function getPhotosNear(latitude: number, longitude: number, radiusMeters: number): Promise<GeoPhoto[]> {
+    return invoke("get_photos_near", { latitude, longitude, radiusMeters });
+}
+
+export async function getMapClusters(bounds: GeoBounds, zoom: number): Promise<PhotoCluster[]> {
+    return invoke("get_map_clusters", { bounds, zoom });
+}
+
+export async function previewGeotags(trackFiles: string[], options: GeotagOptions): Promise<GeotagMatch[]> {
+    return invoke("preview_geotags", { trackFiles, options });
+}
+
+export async function applyGeotags(matches: GeotagMatch[], writeToFiles: boolean): Promise<GeotagSummary> {
+    return invoke("apply_geotags", { matches, writeToFiles });
+}
@@ -0,0 +1,26 @@
+import { ColorLabel, FlagFilter } from "@/types";
+
+export const COLOR_LABELS: ColorLabel[] = ["red", "yellow", "green", "blue", "purple"];
+
+export const COLOR_LABEL_CLASSES: Record<ColorLabel, string> = {
+    red: "bg-red-500",
+    yellow: "bg-yellow-400",
+    green: "bg-green-500",
+    blue: "bg-blue-500",
+    purple: "bg-purple-500",
+};
+
+export const FLAG_FILTER_LABELS: Record<FlagFilter, string> = {
+    picked: "Picks",
+    rejected: "Rejects",
+    unflagged: "Unflagged",
+    notRejected: "Not rejected",
+};
+
+// Same keys as Lightroom: 6 to 9 for the first four labels, purple has no key
+export const COLOR_LABEL_KEYS: Record<string, ColorLabel> = {
+    "6": "red",
+    "7": "yellow",
+    "8": "green",
+    "9": "blue",
+};
@@ -1,29 +1,84 @@
 import * as React from "react";
-import { useEffect } from "react";
-import { useQuery } from "@tanstack/react-query";
-import { getFaceClusters, getPhotosAtPath } from "@/lib/api";
+import { useCallback, useEffect } from "react";
+import { useInfiniteQuery, useQuery } from "@tanstack/react-query";
+import { countPhotosAtPath, getAlbumPhotos, getAlbums, getFaceClusters, getPhotosAtPath, getSmartAlbumPhotos } from "@/lib/api";
 import LoadingPage from "@/pages/LoadingPage";
 import { ResizableHandle, ResizablePanel, ResizablePanelGroup } from "@/components/ui/resizable";
 import Sidebar from "@/components/Sidebar";
 import { useAppDispatch, useAppSelector } from "@/lib/hooks";
-import { selectCurrentFolder, selectPreviewDir } from "@/contexts/slices/pathSlice";
-import { selectSelectedTags, setFaceClusters, setPhotos } from "@/contexts/slices/photosSlice";
+import { selectAlbumId, selectCurrentFolder, selectPreviewDir, selectSmartAlbumId } from "@/contexts/slices/pathSlice";
+import {
+    selectCulling,
+    selectSelectedTags,
+    selectSort,
+    selectTagQuery,
+    selectSortDescending,
+    setFaceClusters,
+    setPhotoCount,
+    setPhotos,
+} from "@/contexts/slices/photosSlice";
+import { PhotoCursor } from "@/types";
 import { Outlet } from "react-router";
 import useIsInitialized from "@/contexts/FoldersContext";
 
+export interface PhotoOutletContext {
+    loadMorePhotos: () => void;
+}
+
 const HomePage = () => {
     useIsInitialized();
 
     const dispatch = useAppDispatch();
     const folder = useAppSelector(selectCurrentFolder);
     const previewPath = useAppSelector(selectPreviewDir);
+    const smartAlbumId = useAppSelector(selectSmartAlbumId);
+    const albumId = useAppSelector(selectAlbumId);
     const selectedTags = useAppSelector(selectSelectedTags);
-    const { data: loadedPhotos } = useQuery({
-        queryKey: ["photos", folder.path, selectedTags],
-        queryFn: () => getPhotosAtPath(folder.path, Array.from(selectedTags)),
-        enabled: !!folder.id,
+    const tagQuery = useAppSelector(selectTagQuery);
+    const sort = useAppSelector(selectSort);
+    const descending = useAppSelector(selectSortDescending);
+    const culling = useAppSelector(selectCulling);
+
+    // Pages are read as the grid is scrolled, each one starts after the last photo of the previous one
+    const { data: loadedPhotos, fetchNextPage, hasNextPage, isFetchingNextPage } = useInfiniteQuery({
+        queryKey: ["photos", folder.path, smartAlbumId, albumId, selectedTags, tagQuery, culling, sort, descending],
+        queryFn: ({ pageParam }) => {
+            const page = { sort, descending, after: pageParam };
+            // Albums keep their own order and are read at once
+            if (albumId) {
+                return getAlbumPhotos(albumId).then((photos) => ({ photos, tags: [], nextCursor: null }));
+            }
+            // A smart album replaces the folder and its filters with the saved search
+            if (smartAlbumId) {
+                return getSmartAlbumPhotos(smartAlbumId, page).then(({ photos, nextCursor }) => ({ photos, tags: [], nextCursor }));
+            }
+            return getPhotosAtPath(folder.path, Array.from(selectedTags), tagQuery, undefined, undefined, page, culling);
+        },
+        initialPageParam: null as PhotoCursor | null,
+        getNextPageParam: (lastPage) => lastPage.nextCursor ?? undefined,
+        enabled: !!folder.id || !!smartAlbumId || !!albumId,
+    });
+
+    const { data: photoCount } = useQuery({
+        queryKey: ["photoCount", folder.path, smartAlbumId, albumId, selectedTags, tagQuery, culling],
+        queryFn: () => {
+            if (albumId) {
+                return getAlbums().then((albums) => albums.find((album) => album.id === albumId)?.photoCount ?? 0);
+            }
+            if (smartAlbumId) {
+                return getSmartAlbumPhotos(smartAlbumId, { limit: 1 }).then((results) => results.total ?? 0);
+            }
+            return countPhotosAtPath(folder.path, Array.from(selectedTags), tagQuery, undefined, undefined, culling);
+        },
+        enabled: !!folder.id || !!smartAlbumId || !!albumId,
     });
 
+    const loadMorePhotos = useCallback(() => {
+        if (hasNextPage && !isFetchingNextPage) {
+            fetchNextPage();
+        }
+    }, [hasNextPage, isFetchingNextPage, fetchNextPage]);
+
     const { data: faceClusters, isFetched: isFacesFetched } = useQuery({
         queryKey: ["faces", folder.path],
         queryFn: () => getFaceClusters(folder.path),
@@ -35,9 +90,18 @@ const HomePage = () => {
             return;
         }
 
-        dispatch(setPhotos(loadedPhotos));
+        dispatch(
+            setPhotos({
+                photos: loadedPhotos.pages.flatMap((page) => page.photos),
+                tags: loadedPhotos.pages[0]?.tags ?? [],
+            }),
+        );
     }, [loadedPhotos]);
 
+    useEffect(() => {
+        dispatch(setPhotoCount(photoCount ?? 0));
+    }, [photoCount]);
+
     useEffect(() => {
         if (!isFacesFetched) {
             return;
@@ -57,7 +121,7 @@ const HomePage = () => {
             </ResizablePanel>
             <ResizableHandle />
             <ResizablePanel defaultSize={85}>
-                <Outlet />
+                <Outlet context={{ loadMorePhotos } satisfies PhotoOutletContext} />
             </ResizablePanel>
         </ResizablePanelGroup>
     );
@@ -0,0 +1,9 @@
+import { Photo } from "./photo";
+
+// A curated album, coverPhoto is the chosen cover or else the first photo
+export interface Album {
+    id: string;
+    name: string;
+    coverPhoto: Photo | null;
+    photoCount: number;
+}
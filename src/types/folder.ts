@@ -1,7 +1,20 @@
 export type Folder = {
     id: string;
     path: string;
-    isImported: boolean;
     children?: Folder[];
     photoCount: number;
+    utcOffsetMinutes?: number | null; // time zone of the cameras, for photos that don't record it
+    xmpSync?: boolean; // catalog changes are written to the XMP sidecars
+};
+
+export type SidecarSummary = {
+    written: number;
+    failures: string[];
+};
+
+export type FolderNode = {
+    name: string;
+    path: string;
+    photoCount: number;
+    children: FolderNode[];
 };
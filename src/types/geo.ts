@@ -0,0 +1,64 @@
+// Decimal degrees, `west` is greater than `east` when the area crosses the antimeridian
+export interface GeoBounds {
+    north: number;
+    south: number;
+    east: number;
+    west: number;
+}
+
+export interface GeoPhoto {
+    id: string;
+    path: string;
+    name: string;
+    dateTimeOriginal?: string | null;
+    latitude: number;
+    longitude: number;
+}
+
+export interface PhotoCluster {
+    latitude: number;
+    longitude: number;
+    photoCount: number;
+    photoId: string; // cover of the cluster, the most recent photo
+    north: number;
+    south: number;
+    east: number;
+    west: number;
+}
+
+// A country, optionally narrowed down to one of its regions or cities
+export interface PlaceFilter {
+    countryCode: string;
+    region?: string | null;
+    placeId?: number | null; // GeoNames id of a city
+}
+
+export interface PlaceNode {
+    name: string;
+    filter: PlaceFilter;
+    photoCount: number;
+    children: PlaceNode[];
+}
+
+export interface GeotagOptions {
+    cameraOffsetSeconds?: number; // how far the camera clock was ahead
+    utcOffsetMinutes?: number; // time zone the camera clock was set to
+    maxGapSeconds?: number | null;
+    directoryId?: string | null;
+}
+
+export interface GeotagMatch {
+    photoId: string;
+    name: string;
+    dateTimeOriginal: string;
+    latitude: number;
+    longitude: number;
+    altitude?: number | null;
+    gapSeconds: number;
+}
+
+export interface GeotagSummary {
+    tagged: number;
+    written: number;
+    failures: string[];
+}
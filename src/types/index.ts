@@ -1,2 +1,6 @@
+export * from "./album";
 export * from "./folder";
+export * from "./geo";
 export * from "./photo";
+export * from "./progress";
+export * from "./smartAlbum";
@@ -1,3 +1,5 @@
+import { PlaceFilter } from "./geo";
+
 export interface Photo {
     id: string;
     path: string;
@@ -7,6 +9,82 @@ export interface Photo {
 export interface PhotoData {
     photos: Photo[];
     tags: string[];
+    nextCursor?: PhotoCursor | null; // null on the last page
+}
+
+export type PhotoSort = "captureDate" | "importDate" | "name" | "fileSize" | "rating";
+
+// Keys of the last photo of a page, passed back as is to read the next one
+export interface PhotoCursor {
+    id: string;
+    name: string;
+    dateTimeOriginal?: string | null;
+    importedAt: string;
+    fileSize?: number | null;
+    rating: number;
+}
+
+export type PhotoFlag = "pick" | "reject";
+
+export type ColorLabel = "red" | "yellow" | "green" | "blue" | "purple";
+
+export type FlagFilter = "picked" | "rejected" | "unflagged" | "notRejected";
+
+// What was decided while culling, criteria left out match every photo
+export interface CullingFilter {
+    minRating?: number | null; // 0 (unrated) to 5 stars
+    maxRating?: number | null;
+    flag?: FlagFilter | null;
+    colorLabels?: ColorLabel[]; // any of them
+}
+
+export interface PhotoCulling {
+    photoId: string;
+    rating: number;
+    flag: PhotoFlag | null;
+    colorLabel: ColorLabel | null;
+}
+
+export type PhotoOrientation = "landscape" | "portrait" | "square";
+
+// Criteria left out match every photo, ranges are inclusive
+export interface PhotoQuery extends CullingFilter {
+    directoryId?: string | null;
+    subFolder?: string | null;
+    tags?: string | null; // tag query, e.g. "person AND dog NOT car"
+    place?: PlaceFilter | null;
+    make?: string | null;
+    model?: string | null;
+    lens?: string | null;
+    minIso?: number | null;
+    maxIso?: number | null;
+    minAperture?: number | null;
+    maxAperture?: number | null;
+    minFocalLength?: number | null; // millimeters
+    maxFocalLength?: number | null;
+    takenAfter?: string | null; // "2024-05-01T00:00:00"
+    takenBefore?: string | null;
+    orientation?: PhotoOrientation | null;
+    flashFired?: boolean | null;
+}
+
+export interface SearchResults {
+    photos: Photo[];
+    nextCursor?: PhotoCursor | null;
+    total?: number | null; // only on the first page
+}
+
+// Why a tag query can't be parsed, position is the index of the offending character
+export interface TagQueryError {
+    position: number;
+    message: string;
+}
+
+export interface PhotoPageRequest {
+    sort?: PhotoSort;
+    descending?: boolean;
+    after?: PhotoCursor | null;
+    limit?: number;
 }
 
 export interface PhotoSummary {
@@ -14,7 +92,8 @@ export interface PhotoSummary {
     name: string;
     make?: string | null;
     model?: string | null;
-    date_time_original?: string | null; // ISO string or nullable
+    date_time_original?: string | null; // local time of the camera
+    utc_offset_minutes?: number | null;
     width?: number | null;
     height?: number | null;
     iso_speed?: number | null;
@@ -23,4 +102,35 @@ export interface PhotoSummary {
     focal_length?: number | null;
     gps_latitude?: string | null;
     gps_longitude?: string | null;
+    caption?: string | null; // IPTC or XMP caption embedded in the file
+}
+
+export interface DuplicatePhoto {
+    id: string;
+    directoryId: string;
+    directoryPath: string;
+    name: string;
+    fileSize?: number | null;
+    modifiedTime?: string | null;
+    contentHash?: string | null;
+}
+
+export interface DuplicateGroup {
+    contentHash: string;
+    photos: DuplicatePhoto[];
+}
+
+export interface DuplicateReport {
+    groups: DuplicateGroup[];
+    incomplete: boolean; // some photos are still being hashed in the background
+}
+
+export interface SimilarPhoto {
+    id: string;
+    path: string;
+    name: string;
+    fileSize?: number | null;
+    dateTimeOriginal?: string | null;
+    width?: number | null;
+    height?: number | null;
 }
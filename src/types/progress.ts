@@ -0,0 +1,15 @@
+export type ProgressStage = "scanning" | "metadata" | "preview" | "tagging" | "faceAnalysis";
+
+// Emitted as the "progress" event by every background stage
+export interface ProgressEvent {
+    stage: ProgressStage;
+    directoryId: string; // UUID
+    directory: string;
+    done: number;
+    total: number;
+    currentFile?: string | null;
+    errors: number;
+    lastError?: string | null;
+    etaSeconds?: number | null;
+    finished: boolean;
+}
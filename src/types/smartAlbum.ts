@@ -0,0 +1,9 @@
+import { PhotoQuery } from "./photo";
+
+// A saved search, photoCount is counted every time the albums are read
+export interface SmartAlbum {
+    id: string;
+    name: string;
+    query: PhotoQuery;
+    photoCount: number;
+}
@@ -1,5 +1,6 @@
 use crate::face_clustering::nms::{Face, Nms, Rect};
 use anyhow::Result;
+use db_service::schema::FaceRegion;
 use image::{DynamicImage, GenericImageView};
 use itertools::iproduct;
 use ndarray::{Array, Array2, Array4, Axis, s};
@@ -108,7 +109,8 @@ fn decode_boxes(loc: &Array2<f32>, priors: &Vec<(f32, f32, f32, f32)>) -> Array2
     boxes
 }
 
-fn extract_faces(img: &DynamicImage, faces: &[Face]) -> Vec<DynamicImage> {
+/// Crops the faces out of the image, together with where they are once clamped to the image.
+fn extract_faces(img: &DynamicImage, faces: &[Face]) -> Vec<(FaceRegion, DynamicImage)> {
     let mut face_images = Vec::new();
     let (img_width, img_height) = img.dimensions();
 
@@ -122,13 +124,23 @@ fn extract_faces(img: &DynamicImage, faces: &[Face]) -> Vec<DynamicImage> {
         let height = rect.height * img_height as f32;
 
         let cropped = img.crop_imm(x as u32, y as u32, width as u32, height as u32);
-        face_images.push(cropped);
+        // The crop is clamped to the image, the region follows it
+        let region = FaceRegion {
+            x: (x as u32).min(img_width) as f32 / img_width as f32,
+            y: (y as u32).min(img_height) as f32 / img_height as f32,
+            width: cropped.width() as f32 / img_width as f32,
+            height: cropped.height() as f32 / img_height as f32,
+        };
+        face_images.push((region, cropped));
     }
 
     face_images
 }
 
-pub fn detect_faces(path: &PathBuf, model: Arc<Session>) -> Result<Vec<DynamicImage>> {
+pub fn detect_faces(
+    path: &PathBuf,
+    model: Arc<Session>,
+) -> Result<Vec<(FaceRegion, DynamicImage)>> {
     let (input_tensor, dynamic_image) = preprocess_image(&path)?;
 
     // Run the RetinaFace model.
@@ -1,11 +1,13 @@
 use crate::APP_NAME;
 use crate::face_clustering::calculate_embeddings::run_facenet_on_faces;
 use crate::face_clustering::detect_faces::detect_faces;
+use crate::progress::{report_batch, report_end, report_start};
 use anyhow::Result;
 use db_service::db::DbPoolConn;
-use db_service::schema::{Directory, Photo};
-use db_service::services::embeddings::add_embeddings;
-use db_service::services::photo::get_photos_from_directory;
+use db_service::schema::types::ProcessingStage;
+use db_service::schema::{Directory, FaceRegion, Photo};
+use db_service::services::embeddings::{PhotoFaceEmbeddings, add_embeddings};
+use db_service::services::processing::{claim_photos, finish_photos};
 use image::{DynamicImage, ImageFormat};
 use ort::session::Session;
 use rayon::prelude::*;
@@ -14,6 +16,15 @@ use std::path::Path;
 use std::sync::Arc;
 use uuid::Uuid;
 
+/// Photos claimed at once, so an interruption only loses the embeddings of one batch.
+const BATCH_SIZE: i64 = 128;
+
+/// Recorded with the embeddings, they are only comparable when computed by the same models.
+const MODEL_VERSION: &str = "retinaface+facenet";
+
+/// The faces found in a photo: their ids, where they are and their embeddings.
+type FaceResult = Result<(Vec<Uuid>, Vec<FaceRegion>, Vec<Vec<f32>>)>;
+
 fn save_cropped_faces(faces_cropped: &Vec<DynamicImage>, directory: &Path) -> Vec<Uuid> {
     // Save cropped faces
     faces_cropped
@@ -37,8 +48,6 @@ pub fn face_embeddings_pipeline(
     directory: Directory,
     conn: &mut DbPoolConn,
 ) -> Result<()> {
-    let photos = get_photos_from_directory(conn, directory.id);
-
     let local_photo_path = Path::new("/tagging_service/data");
     let output_folder = local_photo_path
         .join(APP_NAME)
@@ -46,37 +55,76 @@ pub fn face_embeddings_pipeline(
 
     fs::create_dir_all(&output_folder.join("faces"))?;
 
-    // Process images in parallel using Rayon.
-    let results: Vec<(&Photo, Vec<Uuid>, Vec<Vec<f32>>)> = photos
-        .par_iter()
-        .map(|photo| {
-            // Clone the Arc pointer for each thread.
-            let retinaface_model = Arc::clone(&retinaface_model);
-            let preview = output_folder.join(format!("{}.preview.{}", photo.id, "webp"));
-
-            let faces = match detect_faces(&preview, retinaface_model) {
-                Ok(faces) => faces,
-                Err(err) => {
-                    tracing::error!("Error when detecting faces: {:?}", err);
-                    return (photo, Vec::new(), Vec::new());
-                }
-            };
+    report_start(conn, directory.id, ProcessingStage::FaceEmbeddings);
+
+    loop {
+        let photos = claim_photos(
+            conn,
+            directory.id,
+            ProcessingStage::FaceEmbeddings,
+            BATCH_SIZE,
+        )?;
+        if photos.is_empty() {
+            break;
+        }
 
-            let ids = save_cropped_faces(&faces, &output_folder);
+        // Process images in parallel using Rayon.
+        let results: Vec<(&Photo, FaceResult)> = photos
+            .par_iter()
+            .map(|photo| {
+                // Clone the Arc pointer for each thread.
+                let retinaface_model = Arc::clone(&retinaface_model);
+                let preview = output_folder.join(format!("{}.preview.{}", photo.id, "webp"));
 
-            let facenet_model = Arc::clone(&facenet_model);
+                let (regions, faces): (Vec<FaceRegion>, Vec<DynamicImage>) =
+                    match detect_faces(&preview, retinaface_model) {
+                        Ok(faces) => faces.into_iter().unzip(),
+                        Err(err) => {
+                            return (photo, Err(err.context("Error when detecting faces")));
+                        }
+                    };
 
-            match run_facenet_on_faces(faces, facenet_model) {
-                Ok(embeddings) => (photo, ids, embeddings),
-                Err(err) => {
-                    tracing::error!("Error when creating embeddings: {:?}", err);
-                    (photo, Vec::new(), Vec::new())
+                let ids = save_cropped_faces(&faces, &output_folder);
+
+                let facenet_model = Arc::clone(&facenet_model);
+
+                let embeddings = run_facenet_on_faces(faces, facenet_model)
+                    .map(|embeddings| (ids, regions, embeddings))
+                    .map_err(|err| err.context("Error when creating embeddings"));
+
+                (photo, embeddings)
+            })
+            .collect();
+
+        let mut embeddings: Vec<PhotoFaceEmbeddings> = Vec::new();
+        let mut outcomes: Vec<(Uuid, Result<()>)> = Vec::new();
+        for (photo, result) in results {
+            match result {
+                Ok((ids, regions, photo_embeddings)) => {
+                    embeddings.push((photo, ids, regions, photo_embeddings));
+                    outcomes.push((photo.id, Ok(())));
                 }
+                Err(err) => outcomes.push((photo.id, Err(err))),
             }
-        })
-        .collect();
+        }
+
+        add_embeddings(conn, embeddings)?;
+        report_batch(
+            conn,
+            directory.id,
+            ProcessingStage::FaceEmbeddings,
+            &photos,
+            &outcomes,
+        );
+        finish_photos(
+            conn,
+            ProcessingStage::FaceEmbeddings,
+            Some(MODEL_VERSION),
+            outcomes,
+        )?;
+    }
 
-    add_embeddings(conn, results)?;
+    report_end(conn, directory.id, ProcessingStage::FaceEmbeddings);
 
     Ok(())
 }
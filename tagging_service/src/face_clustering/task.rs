@@ -2,12 +2,13 @@ use crate::face_clustering::face_clustering::cluster_faces;
 use crate::face_clustering::face_detection_pipeline::face_embeddings_pipeline;
 use anyhow::Result;
 use db_service::db::DbPoolConn;
-use db_service::services::directory::{change_directories_status, get_directories_by_status};
+use db_service::schema::types::ProcessingStage;
+use db_service::services::processing::{count_unfinished, get_directories_with_claimable};
 use ort::session::Session;
 use std::sync::Arc;
 
 pub fn face_embeddings_task(conn: &mut DbPoolConn) -> Result<()> {
-    let un_processed_dirs = get_directories_by_status(conn, "is_face_tagging_done", false)?;
+    let un_processed_dirs = get_directories_with_claimable(conn, ProcessingStage::FaceEmbeddings)?;
     const RETINAFACE_MODEL_PATH: &str = "models/retinaface.onnx";
     const FACENET_MODEL_PATH: &str = "models/facenet.onnx";
 
@@ -37,12 +38,15 @@ pub fn face_embeddings_task(conn: &mut DbPoolConn) -> Result<()> {
             dir,
             conn,
         ) {
-            Ok(_) => {
-                tracing::info!("Face embeddings done for {}!", name);
-                if let Err(e) = change_directories_status(conn, &id, "is_face_tagging_done") {
-                    tracing::error!("Cannot update status for {}: {}", name, e);
+            Ok(_) => match count_unfinished(conn, id, ProcessingStage::FaceEmbeddings) {
+                Ok(0) => tracing::info!("Face embeddings done for {}!", name),
+                Ok(left) => {
+                    tracing::warn!("{} photos of {} still need face embeddings", left, name)
                 }
-            }
+                Err(e) => {
+                    tracing::error!("Cannot count photos without embeddings in {}: {}", name, e)
+                }
+            },
             Err(err) => {
                 tracing::error!("Face embeddings failed for {}: {}", name, err);
             }
@@ -1,9 +1,12 @@
 use crate::face_clustering::task::{face_clustering_task, face_embeddings_task};
+use crate::similarity::task::perceptual_hash_task;
 use crate::tagging::task::tagging_task;
 use anyhow::Result;
-use db_service::db::{DbPoolConn, init_pool};
+use db_service::db::{DbPoolConn, init_pool, migrate};
+use db_service::listener::ChangeListener;
+use db_service::schema::types::ProcessingStage;
 use db_service::seed::insert_tags_from_yaml;
-use db_service::services::directory::hash_directories;
+use db_service::services::processing::{hash_claimable_work, release_interrupted};
 use ort::execution_providers::{
     CPUExecutionProvider, CUDAExecutionProvider, CoreMLExecutionProvider,
 };
@@ -11,14 +14,23 @@ use std::thread::sleep;
 use std::time::Duration;
 
 pub mod face_clustering;
+pub mod progress;
+pub mod similarity;
 pub mod tagging;
 
 pub const APP_NAME: &str = "photo-organizer";
 
+/// Changes are picked up through LISTEN/NOTIFY, polling only catches what was missed
+/// while the listener was disconnected.
+const POLL_INTERVAL: Duration = Duration::from_secs(60);
+
 fn run_tasks(conn: &mut DbPoolConn, first_time: bool) -> Result<()> {
     tracing::info!("Starting tagging task");
     tagging_task(conn)?;
 
+    tracing::info!("Starting perceptual hash task");
+    perceptual_hash_task(conn)?;
+
     tracing::info!("Starting face embedding task");
     face_embeddings_task(conn)?;
 
@@ -30,6 +42,38 @@ fn run_tasks(conn: &mut DbPoolConn, first_time: bool) -> Result<()> {
     Ok(())
 }
 
+fn connect_listener() -> Option<ChangeListener> {
+    match ChangeListener::connect() {
+        Ok(listener) => {
+            tracing::info!("Listening for library changes");
+            Some(listener)
+        }
+        Err(e) => {
+            tracing::error!("Cannot listen for library changes, polling instead: {}", e);
+            None
+        }
+    }
+}
+
+fn wait_for_changes(listener: &mut Option<ChangeListener>) {
+    let Some(active) = listener else {
+        sleep(POLL_INTERVAL);
+        *listener = connect_listener();
+        return;
+    };
+
+    match active.wait(POLL_INTERVAL) {
+        Ok(tables) if !tables.is_empty() => {
+            tracing::debug!("Notified of changes in {:?}", tables);
+        }
+        Ok(_) => {}
+        Err(e) => {
+            tracing::error!("Lost the library change listener: {}", e);
+            *listener = None;
+        }
+    }
+}
+
 fn main() -> Result<()> {
     tracing_subscriber::fmt::init();
 
@@ -42,22 +86,36 @@ fn main() -> Result<()> {
         ])
         .commit()?;
 
-    let pool = init_pool();
+    let pool = init_pool()?;
+    migrate(&pool)?;
 
     {
         let conn = &mut pool.get().expect("Can't get DB connection");
         insert_tags_from_yaml(conn, "models/coco.yaml")?;
+
+        // Photos that were being processed when the service stopped
+        let released = release_interrupted(
+            conn,
+            &[ProcessingStage::Tagging, ProcessingStage::FaceEmbeddings],
+        )?;
+        if released > 0 {
+            tracing::info!("Released {} interrupted photos", released);
+        }
     }
 
+    let mut listener = connect_listener();
     let mut first_run = true;
     let mut last_seen = String::new();
 
     loop {
         let conn = &mut pool.get().expect("Can't get DB connection");
-        let last_hash = hash_directories(conn)?;
+        let last_hash = hash_claimable_work(
+            conn,
+            &[ProcessingStage::Tagging, ProcessingStage::FaceEmbeddings],
+        )?;
 
         if last_seen != last_hash {
-            tracing::info!("Detected change in directories table, rerunning tasks...");
+            tracing::info!("Detected photos ready for processing, rerunning tasks...");
 
             if let Err(e) = run_tasks(conn, first_run) {
                 tracing::error!("Error running tasks: {}", e);
@@ -68,7 +126,6 @@ fn main() -> Result<()> {
             last_seen = last_hash;
         }
 
-        // Poll every 60 seconds; adjust as needed
-        sleep(Duration::from_secs(60));
+        wait_for_changes(&mut listener);
     }
 }
@@ -0,0 +1,52 @@
+use anyhow::Result;
+use db_service::db::DbPoolConn;
+use db_service::schema::Photo;
+use db_service::schema::types::ProcessingStage;
+use db_service::services::processing::count_unfinished;
+use db_service::services::progress::{finish_progress, record_progress, start_progress};
+use uuid::Uuid;
+
+// Progress is only informative, failing to publish it never stops the processing
+
+pub fn report_start(conn: &mut DbPoolConn, dir_id: Uuid, stage: ProcessingStage) {
+    let started = count_unfinished(conn, dir_id, stage)
+        .and_then(|total| start_progress(conn, &dir_id, stage, total));
+
+    if let Err(e) = started {
+        tracing::error!("Cannot publish {} progress of {}: {}", stage, dir_id, e);
+    }
+}
+
+/// Publishes the outcome of a processed batch, the desktop app relays it to the user.
+pub fn report_batch(
+    conn: &mut DbPoolConn,
+    dir_id: Uuid,
+    stage: ProcessingStage,
+    photos: &[Photo],
+    outcomes: &[(Uuid, Result<()>)],
+) {
+    let failed: Vec<String> = outcomes
+        .iter()
+        .filter_map(|(_, outcome)| outcome.as_ref().err())
+        .map(|err| format!("{:#}", err))
+        .collect();
+    let current_file = photos.last().map(|photo| photo.name.as_str());
+
+    if let Err(e) = record_progress(
+        conn,
+        &dir_id,
+        stage,
+        outcomes.len(),
+        failed.len(),
+        current_file,
+        failed.last().map(String::as_str),
+    ) {
+        tracing::error!("Cannot publish {} progress of {}: {}", stage, dir_id, e);
+    }
+}
+
+pub fn report_end(conn: &mut DbPoolConn, dir_id: Uuid, stage: ProcessingStage) {
+    if let Err(e) = finish_progress(conn, &dir_id, stage) {
+        tracing::error!("Cannot publish {} progress of {}: {}", stage, dir_id, e);
+    }
+}
@@ -0,0 +1,25 @@
+use anyhow::{Context, Result};
+use image::imageops::FilterType;
+use std::path::Path;
+
+/// Computes the difference hash of an image: it is shrunk to 9x8 grayscale pixels and every
+/// bit tells whether a pixel is brighter than its right neighbour. Resized, re-encoded and
+/// slightly different shots end up with hashes a few bits apart.
+pub fn dhash(preview_path: &Path) -> Result<u64> {
+    let img = image::open(preview_path)
+        .with_context(|| format!("failed to open image {:?}", preview_path))?;
+
+    let small = img.resize_exact(9, 8, FilterType::Triangle).to_luma8();
+
+    let mut hash = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            hash <<= 1;
+            if small.get_pixel(x, y)[0] > small.get_pixel(x + 1, y)[0] {
+                hash |= 1;
+            }
+        }
+    }
+
+    Ok(hash)
+}
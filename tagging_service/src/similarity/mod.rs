@@ -0,0 +1,2 @@
+pub mod dhash;
+pub mod task;
@@ -0,0 +1,48 @@
+use crate::APP_NAME;
+use crate::similarity::dhash::dhash;
+use anyhow::Result;
+use db_service::db::DbPoolConn;
+use db_service::services::directory::get_directories;
+use db_service::services::similarity::{get_photos_without_perceptual_hash, set_perceptual_hashes};
+use rayon::prelude::*;
+use std::path::Path;
+use uuid::Uuid;
+
+pub fn perceptual_hash_task(conn: &mut DbPoolConn) -> Result<()> {
+    // Hashes are computed from the previews, only the photos that have one are returned
+    for dir in get_directories(conn)? {
+        let photos = get_photos_without_perceptual_hash(conn, dir.id)?;
+        if photos.is_empty() {
+            continue;
+        }
+
+        let now = std::time::Instant::now();
+        let output_folder = Path::new("/tagging_service/data")
+            .join(APP_NAME)
+            .join(dir.id.to_string());
+
+        let hashes: Vec<(Uuid, u64)> = photos
+            .par_iter()
+            .filter_map(|photo| {
+                let preview = output_folder.join(format!("{}.preview.{}", photo.id, "webp"));
+                match dhash(&preview) {
+                    Ok(hash) => Some((photo.id, hash)),
+                    Err(err) => {
+                        tracing::error!("Error hashing {:?}: {}", preview, err);
+                        None
+                    }
+                }
+            })
+            .collect();
+
+        set_perceptual_hashes(conn, &hashes)?;
+        tracing::info!(
+            "Computed {} perceptual hashes for {} in {:?}",
+            hashes.len(),
+            dir.path,
+            now.elapsed()
+        );
+    }
+
+    Ok(())
+}
@@ -1,10 +1,11 @@
 use crate::tagging::yolo_detect::detect_objects_batch;
 use anyhow::Result;
 use db_service::db::DbPoolConn;
-use db_service::services::directory::{change_directories_status, get_directories_by_status};
+use db_service::schema::types::ProcessingStage;
+use db_service::services::processing::{count_unfinished, get_directories_with_claimable};
 
 pub fn tagging_task(conn: &mut DbPoolConn) -> Result<()> {
-    let un_processed_dirs = get_directories_by_status(conn, "is_tagged", false)?;
+    let un_processed_dirs = get_directories_with_claimable(conn, ProcessingStage::Tagging)?;
 
     if un_processed_dirs.is_empty() {
         tracing::info!("No directories to process for tagging");
@@ -17,12 +18,11 @@ pub fn tagging_task(conn: &mut DbPoolConn) -> Result<()> {
         let now = std::time::Instant::now();
         tracing::info!("Starting processing of {}", dir.path);
         match detect_objects_batch(String::from("models/yolo11l.onnx"), dir, conn) {
-            Ok(_) => {
-                tracing::info!("Object detection done for {}!", name);
-                if let Err(e) = change_directories_status(conn, &id, "is_tagged") {
-                    tracing::error!("Cannot update status for {}: {}", name, e);
-                }
-            }
+            Ok(_) => match count_unfinished(conn, id, ProcessingStage::Tagging) {
+                Ok(0) => tracing::info!("Object detection done for {}!", name),
+                Ok(left) => tracing::warn!("{} photos of {} still need tagging", left, name),
+                Err(e) => tracing::error!("Cannot count untagged photos of {}: {}", name, e),
+            },
             Err(err) => {
                 tracing::error!("Object detection failed for {}: {}", name, err);
             }
@@ -1,16 +1,22 @@
 use crate::APP_NAME;
-use anyhow::{Context, Result};
+use crate::progress::{report_batch, report_end, report_start};
+use anyhow::{Context, Result, anyhow};
 use db_service::db::DbPoolConn;
+use db_service::schema::types::ProcessingStage;
 use db_service::schema::{Directory, Photo};
-use db_service::services::photo::get_photos_from_directory;
+use db_service::services::processing::{claim_photos, finish_photos};
 use db_service::services::tags::{Detection, insert_photo_tags_mappings};
 use rayon::prelude::*;
 use std::collections::HashSet;
 use std::path::Path;
 use std::sync::Arc;
+use uuid::Uuid;
 use yolo_rs::model::YoloModelSession;
 use yolo_rs::{YoloEntityOutput, image_to_yolo_input_tensor, inference};
 
+/// Photos claimed at once, so an interruption only loses the detections of one batch.
+const BATCH_SIZE: i64 = 256;
+
 pub fn detect_objects_for_image(
     preview_path: &str,
     model: &YoloModelSession,
@@ -74,37 +80,69 @@ pub fn detect_objects_batch(
     // Wrap the model in an Arc to share between threads.
     let model = Arc::new(model);
 
-    let photos = get_photos_from_directory(conn, directory.id);
+    // Stored with every result, so photos tagged by an older model can be found later
+    let model_version = Path::new(&model_path)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string());
 
     let local_photo_path = Path::new("/tagging_service/data");
     let output_folder = local_photo_path
         .join(APP_NAME)
         .join(directory.id.to_string());
 
-    // Process images in parallel using Rayon.
-    let results: Vec<(&Photo, HashSet<Detection>)> = photos
-        .par_iter()
-        .map(|photo| {
-            // Clone the Arc pointer for each thread.
-            let model = Arc::clone(&model);
-            let preview = output_folder.join(format!("{}.preview.{}", photo.id, "webp"));
-            let preview_path = match preview.to_str() {
-                Some(path) => path,
-                None => return (photo, HashSet::new()),
-            };
-
-            // Attempt to process the image. If an error occurs, return an empty detection list.
-            match detect_objects_for_image(preview_path, &model) {
-                Ok(detections) => (photo, detections),
-                Err(err) => {
-                    tracing::error!("Error processing {}: {}", preview_path, err);
-                    (photo, HashSet::new())
+    report_start(conn, directory.id, ProcessingStage::Tagging);
+
+    loop {
+        let photos = claim_photos(conn, directory.id, ProcessingStage::Tagging, BATCH_SIZE)?;
+        if photos.is_empty() {
+            break;
+        }
+
+        // Process images in parallel using Rayon.
+        let results: Vec<(&Photo, Result<HashSet<Detection>>)> = photos
+            .par_iter()
+            .map(|photo| {
+                // Clone the Arc pointer for each thread.
+                let model = Arc::clone(&model);
+                let preview = output_folder.join(format!("{}.preview.{}", photo.id, "webp"));
+                let detections = preview
+                    .to_str()
+                    .ok_or_else(|| anyhow!("Invalid preview path {:?}", preview))
+                    .and_then(|preview_path| detect_objects_for_image(preview_path, &model));
+
+                (photo, detections)
+            })
+            .collect();
+
+        let mut detections: Vec<(&Photo, HashSet<Detection>)> = Vec::new();
+        let mut outcomes: Vec<(Uuid, Result<()>)> = Vec::new();
+        for (photo, result) in results {
+            match result {
+                Ok(tags) => {
+                    detections.push((photo, tags));
+                    outcomes.push((photo.id, Ok(())));
                 }
+                Err(err) => outcomes.push((photo.id, Err(err))),
             }
-        })
-        .collect();
+        }
+
+        insert_photo_tags_mappings(conn, detections)?;
+        report_batch(
+            conn,
+            directory.id,
+            ProcessingStage::Tagging,
+            &photos,
+            &outcomes,
+        );
+        finish_photos(
+            conn,
+            ProcessingStage::Tagging,
+            model_version.as_deref(),
+            outcomes,
+        )?;
+    }
 
-    insert_photo_tags_mappings(conn, results)?;
+    report_end(conn, directory.id, ProcessingStage::Tagging);
 
     Ok(())
 }